@@ -0,0 +1,263 @@
+use wgpu::util::DeviceExt;
+use winit::keyboard::{Key, NamedKey};
+use winit::window::Window;
+
+use crate::gpu::GpuContext;
+use crate::turmite::{Start, TURMITES, Turmite, TurmiteCompute, TurmiteWorld};
+
+/// Cells along each side of the grid the ants walk on.
+pub const ANT_GRID_SIZE: u32 = 256;
+/// The most ants, and the most steps they take a frame.
+const MAX_ANTS: u32 = 4096;
+const MAX_STEPS_PER_FRAME: u32 = 4096;
+
+/// What the ants world is started with. Every setting but the steps a frame starts it afresh.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AntSettings {
+    pub rule: Turmite,
+    pub count: u32,
+    pub start: Start,
+    pub steps_per_frame: u32,
+}
+
+impl Default for AntSettings {
+    /// A single Langton's ant.
+    fn default() -> Self {
+        Self {
+            rule: TURMITES[0].parse().expect("the built-in turmites parse"),
+            count: 1,
+            start: Start::default(),
+            steps_per_frame: 64,
+        }
+    }
+}
+
+/// Ants stepped on the GPU and drawn over their cells, which are coloured by how often they
+/// were visited.
+pub struct Ants {
+    turmites: TurmiteCompute,
+    settings: AntSettings,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    cells_pipeline: wgpu::RenderPipeline,
+    ants_pipeline: wgpu::RenderPipeline,
+}
+
+impl Ants {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        settings: AntSettings,
+    ) -> Self {
+        let storage = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Ants Bind Group Layout"),
+            entries: &[
+                // Cells
+                storage(0, wgpu::ShaderStages::FRAGMENT),
+                // Claims, then the most visits
+                storage(1, wgpu::ShaderStages::FRAGMENT),
+                // Ants
+                storage(2, wgpu::ShaderStages::VERTEX),
+                // Grid size
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Ants Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("ants.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ants Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |label, vertex, fragment| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some(vertex),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(fragment),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let cells_pipeline = pipeline("Ant Cells Pipeline", "vs_cells", "fs_cells");
+        let ants_pipeline = pipeline("Ants Pipeline", "vs_ant", "fs_ant");
+
+        let (turmites, bind_group) = start(device, queue, &bind_group_layout, &settings);
+        Self {
+            turmites,
+            settings,
+            bind_group_layout,
+            bind_group,
+            cells_pipeline,
+            ants_pipeline,
+        }
+    }
+
+    pub fn settings(&self) -> &AntSettings {
+        &self.settings
+    }
+
+    /// Adjusts the settings, returning whether the key was used: Tab picks the next of
+    /// `TURMITES`, `[` and `]` halve and double the ants, `p` moves where they start, and `-`
+    /// and `=` halve and double the steps a frame.
+    pub fn handle_key(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, key: &Key) -> bool {
+        let mut settings = self.settings.clone();
+        match key {
+            Key::Named(NamedKey::Tab) => {
+                let next = TURMITES
+                    .iter()
+                    .position(|rule| rule.parse().ok().as_ref() == Some(&settings.rule))
+                    .map_or(0, |i| (i + 1) % TURMITES.len());
+                settings.rule = TURMITES[next].parse().expect("the built-in turmites parse");
+            }
+            Key::Character(c) if c == "[" => settings.count = (settings.count / 2).max(1),
+            Key::Character(c) if c == "]" => settings.count = (settings.count * 2).min(MAX_ANTS),
+            Key::Character(c) if c == "p" => settings.start = settings.start.next(),
+            Key::Character(c) if c == "-" => {
+                self.settings.steps_per_frame = (settings.steps_per_frame / 2).max(1);
+                return true;
+            }
+            Key::Character(c) if c == "=" || c == "+" => {
+                self.settings.steps_per_frame =
+                    (settings.steps_per_frame * 2).min(MAX_STEPS_PER_FRAME);
+                return true;
+            }
+            _ => return false,
+        }
+        (self.turmites, self.bind_group) = start(device, queue, &self.bind_group_layout, &settings);
+        self.settings = settings;
+        true
+    }
+
+    /// Steps the ants, then records a pass drawing them over the whole of `target`.
+    pub fn draw(&mut self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        self.turmites.step(encoder, self.settings.steps_per_frame);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Ants Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_pipeline(&self.cells_pipeline);
+        render_pass.draw(0..3, 0..1);
+        render_pass.set_pipeline(&self.ants_pipeline);
+        render_pass.draw(0..6, 0..self.turmites.ant_count());
+    }
+
+    pub fn render(&mut self, window: &Window, ctx: &GpuContext) -> Result<(), wgpu::SurfaceError> {
+        window.request_redraw();
+
+        if !ctx.surface.is_configured() {
+            return Ok(());
+        }
+
+        let output = ctx.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Ants Encoder"),
+            });
+        self.draw(&mut encoder, &view);
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+}
+
+/// A fresh world of blank cells with the ants placed as `settings` says, and the bind group
+/// drawing it.
+fn start(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    settings: &AntSettings,
+) -> (TurmiteCompute, wgpu::BindGroup) {
+    let size = (ANT_GRID_SIZE, ANT_GRID_SIZE);
+    let ants = settings.start.place(settings.count, size, &mut rand::rng());
+    let world = TurmiteWorld::new(size.0, size.1, ants);
+    let turmites = TurmiteCompute::new(device, queue, &world, &settings.rule);
+
+    let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Ants View Buffer"),
+        contents: bytemuck::cast_slice(&[size.0, size.1]),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let (cells, owners) = turmites.cell_buffers();
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Ants Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: cells.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: owners.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: turmites.ant_buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: view_buffer.as_entire_binding(),
+            },
+        ],
+    });
+    (turmites, bind_group)
+}
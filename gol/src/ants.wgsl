@@ -0,0 +1,85 @@
+// Turmite cells coloured by how often ants visited them, with the ants drawn over them
+
+// Cells across and down the grid
+struct View {
+    width: u32,
+    height: u32,
+}
+
+// As turmite.wgsl keeps them
+struct Ant {
+    x: u32,
+    y: u32,
+    heading: u32,
+    claimed: u32,
+}
+
+@group(0) @binding(0) var<storage, read> cells: array<u32>;
+// Claims, then the most visits to any cell
+@group(0) @binding(1) var<storage, read> owners: array<u32>;
+@group(0) @binding(2) var<storage, read> ants: array<Ant>;
+@group(0) @binding(3) var<uniform> view: View;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// A triangle covering the window, with row 0 of the grid at the top
+@vertex
+fn vs_cells(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+// Black through purple and orange to pale yellow
+fn heat(t: f32) -> vec3<f32> {
+    let purple = vec3<f32>(0.45, 0.1, 0.55);
+    let orange = vec3<f32>(0.95, 0.45, 0.1);
+    let yellow = vec3<f32>(1.0, 0.95, 0.6);
+    if (t < 0.5) {
+        return mix(vec3<f32>(0.0), purple, t * 2.0);
+    }
+    if (t < 0.8) {
+        return mix(purple, orange, (t - 0.5) / 0.3);
+    }
+    return mix(orange, yellow, (t - 0.8) / 0.2);
+}
+
+@fragment
+fn fs_cells(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<u32>(view.width, view.height);
+    let cell = min(vec2<u32>(in.uv * vec2<f32>(size)), size - 1u);
+    let value = cells[cell.y * view.width + cell.x];
+    let visits = f32(value >> 8u);
+    let most = f32(owners[view.width * view.height]);
+    // Visits on a log scale, so cells passed once stay visible next to the busiest
+    let t = select(0.0, log2(visits + 1.0) / log2(most + 1.0), most > 0.0);
+    // Cells left coloured are a little lighter
+    let painted = select(0.0, 0.12, (value & 0xffu) != 0u);
+    return vec4<f32>(heat(t) + painted, 1.0);
+}
+
+// A square for each ant, one instance per ant
+@vertex
+fn vs_ant(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> @builtin(position) vec4<f32> {
+    let corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0),
+    );
+    let ant = ants[instance_index];
+    let size = vec2<f32>(f32(view.width), f32(view.height));
+    let position = (vec2<f32>(f32(ant.x), f32(ant.y)) + corners[vertex_index]) / size;
+    return vec4<f32>(position.x * 2.0 - 1.0, 1.0 - position.y * 2.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_ant() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.3, 1.0, 0.5, 1.0);
+}
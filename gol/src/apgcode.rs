@@ -0,0 +1,151 @@
+//! Canonical names for small objects, in the style of the apgcodes used by soup-search tools.
+//!
+//! A still life is `xs<population>_<cells>`, an oscillator `xp<period>_<cells>` and a spaceship
+//! `xq<period>_<cells>`. The cells are written in extended Wechsler format, and the code is the
+//! shortest (then alphabetically first) encoding over every rotation, reflection and phase, so
+//! the same object always gets the same name wherever and however it appears.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::rule::Rule;
+
+/// Objects that have not repeated after this many generations are left unidentified.
+pub const MAX_PERIOD: u32 = 256;
+
+/// The code given to objects whose period could not be found.
+pub const UNIDENTIFIED: &str = "zz_UNIDENTIFIED";
+
+pub type Cells = Vec<(i32, i32)>;
+
+/// Advances a finite set of cells one generation on an unbounded plane.
+pub fn step(cells: &[(i32, i32)], rule: &Rule) -> Cells {
+    let alive: HashSet<(i32, i32)> = cells.iter().copied().collect();
+    let mut counts: HashMap<(i32, i32), u32> = HashMap::new();
+    for &(x, y) in cells {
+        for &(dx, dy) in rule.neighbourhood.offsets(x.into(), y.into()) {
+            *counts.entry((x + dx, y + dy)).or_default() += 1;
+        }
+    }
+    // Dead cells with no live neighbours never appear in `counts`, so rules with B0 are not
+    // supported here.
+    let mut next: Cells = counts
+        .into_iter()
+        .filter(|(cell, n)| rule.next(alive.contains(cell), *n))
+        .map(|(cell, _)| cell)
+        .collect();
+    next.sort_unstable();
+    next
+}
+
+/// Moves the cells so the bounding box starts at the origin, returning the offset removed.
+fn normalise(cells: &[(i32, i32)]) -> (Cells, (i32, i32)) {
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let mut shifted: Cells = cells.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    shifted.sort_unstable();
+    (shifted, (min_x, min_y))
+}
+
+/// The eight images of the cells under rotation and reflection, each normalised.
+fn symmetries(cells: &[(i32, i32)]) -> impl Iterator<Item = Cells> + '_ {
+    (0..8).map(move |t| {
+        let transformed: Cells = cells
+            .iter()
+            .map(|&(x, y)| {
+                let (x, y) = if t & 4 != 0 { (y, x) } else { (x, y) };
+                let x = if t & 1 != 0 { -x } else { x };
+                let y = if t & 2 != 0 { -y } else { y };
+                (x, y)
+            })
+            .collect();
+        normalise(&transformed).0
+    })
+}
+
+/// Extended Wechsler encoding of normalised cells.
+fn wechsler(cells: &[(i32, i32)]) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let width = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+    let mut strips = vec![vec![0u8; width as usize]; (height as usize).div_ceil(5)];
+    for &(x, y) in cells {
+        strips[y as usize / 5][x as usize] |= 1 << (y % 5);
+    }
+
+    let mut out = String::new();
+    for (i, strip) in strips.iter().enumerate() {
+        if i > 0 {
+            out.push('z');
+        }
+        let end = strip.iter().rposition(|&v| v != 0).map_or(0, |p| p + 1);
+        let mut zeros = 0;
+        let flush = |out: &mut String, zeros: &mut usize| {
+            while *zeros > 0 {
+                match *zeros {
+                    1 => out.push('0'),
+                    2 => out.push('w'),
+                    3 => out.push('x'),
+                    n => {
+                        let run = n.min(39);
+                        out.push('y');
+                        out.push(char::from(DIGITS[run - 4]));
+                        *zeros -= run;
+                        continue;
+                    }
+                }
+                *zeros = 0;
+            }
+        };
+        for &v in &strip[..end] {
+            if v == 0 {
+                zeros += 1;
+            } else {
+                flush(&mut out, &mut zeros);
+                out.push(char::from(DIGITS[v as usize]));
+            }
+        }
+    }
+    out
+}
+
+/// Finds the canonical code of a single object. The cells may be at any position.
+pub fn classify(cells: &[(i32, i32)], rule: &Rule) -> String {
+    let (start, origin) = normalise(cells);
+    if start.is_empty() {
+        return UNIDENTIFIED.to_string();
+    }
+
+    let mut phases = vec![start.clone()];
+    let mut current = cells.to_vec();
+    let mut found = None;
+    for generation in 1..=MAX_PERIOD {
+        current = step(&current, rule);
+        let (shape, offset) = normalise(&current);
+        if shape.is_empty() {
+            break;
+        }
+        if shape == start {
+            found = Some((generation, offset != origin));
+            break;
+        }
+        phases.push(shape);
+    }
+
+    let Some((period, moves)) = found else {
+        return UNIDENTIFIED.to_string();
+    };
+
+    let best = phases
+        .iter()
+        .flat_map(|phase| symmetries(phase))
+        .map(|image| wechsler(&image))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+
+    match (period, moves) {
+        (_, true) => format!("xq{period}_{best}"),
+        (1, false) => format!("xs{}_{best}", start.len()),
+        (_, false) => format!("xp{period}_{best}"),
+    }
+}
@@ -1,61 +1,1406 @@
+use crate::ants::{AntSettings, Ants};
+use crate::clipboard;
 use crate::gpu::GpuContext;
+use crate::grid::{Boundary, Grid, Rect};
+use crate::guides::Guides;
+use crate::hashlife::HashLife;
+use crate::life3d::{Grid3d, RULES_3D};
+use crate::macrocell;
+use crate::options::{MAX_SIZE, Options};
+use crate::pattern::{self, Pattern};
+use crate::picture::{Dither, Picture};
+use crate::placement::Placement;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::recording::RecordingOptions;
+use crate::remote::Remote;
 use crate::renderer::Renderer;
+use crate::rule::{Rule, Variant};
+use crate::sand;
+use crate::scheme::UpdateScheme;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::script::{self, ScriptWorld};
+use crate::selection::{Selection, SelectionOp};
+use crate::session::{self, Session};
+use crate::shared::{ClientMessage, ServerMessage, SharedWorld};
+use crate::surface::Surface;
+use crate::viewport::{MIN_ZOOM, Viewport};
+use crate::voxels::{VOXEL_GRID_SIZE, Voxels};
+#[cfg(target_arch = "wasm32")]
+use crate::web::Command;
 use std::sync::Arc;
+use web_time::{Duration, Instant};
 
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Context;
 use anyhow::Result;
 use winit::{
-    application::ApplicationHandler, event::*, event_loop::ActiveEventLoop,
-    keyboard::Key,
+    application::ApplicationHandler,
+    dpi::PhysicalPosition,
+    event::*,
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    keyboard::{Key, NamedKey},
     window::{Window, WindowId},
 };
 
+const TITLE: &str = "Conway's Game of Life";
+/// Cells along each side of the square painted by the falling sand brush.
+const BRUSH_SIZE: u32 = 3;
+/// Where the whole world is exported as a macrocell pattern.
+#[cfg(not(target_arch = "wasm32"))]
+const MACROCELL_FILE: &str = "pattern.mc";
+/// How long an error stays on screen.
+const MESSAGE_DURATION: Duration = Duration::from_secs(5);
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-pub struct App {
+/// Work finished outside the event loop, delivered through the `EventLoopProxy`.
+pub enum UserEvent {
+    /// The GPU is ready. Only sent on the web, where initialisation is asynchronous.
+    #[cfg(target_arch = "wasm32")]
+    Ready(Box<AppState>),
+    /// The selected cells were read back from the GPU for a selection command.
+    SelectionRead(SelectionOp, Rect, Grid),
+    /// The world was read back from the GPU to be rebuilt with another boundary or variant.
+    Rebuild(Box<Session>),
+    /// The world was read back from the GPU to be run with HashLife.
+    HashLifeRead(Box<Session>),
+    /// An image was chosen with the page's file input.
+    #[cfg(target_arch = "wasm32")]
+    PictureLoaded(Vec<u8>),
+    /// A file, named by the first field, was dropped on the page's canvas.
+    #[cfg(target_arch = "wasm32")]
+    FileDropped(String, Vec<u8>),
+    /// The system clipboard was read for a paste.
+    Pasted(Option<String>),
+    /// Work outside the event loop failed, and the user should know.
+    Failed(String),
+    /// The server of the shared world sent a message.
+    Shared(ServerMessage),
+    /// The connection to the shared world was lost, for this reason.
+    Disconnected(String),
+    /// The script run with F5 finished, leaving this world, or failed.
+    #[cfg(not(target_arch = "wasm32"))]
+    ScriptFinished(Result<Box<ScriptWorld>, String>),
+    /// The page's script called the JavaScript API.
     #[cfg(target_arch = "wasm32")]
-    proxy: Option<winit::event_loop::EventLoopProxy<AppState>>,
+    Command(Command),
+}
+
+pub struct App {
+    /// How the first world starts.
+    options: Options,
+    proxy: EventLoopProxy<UserEvent>,
     window: Option<Arc<Window>>,
     state: Option<AppState>,
     keyboard_modifiers: winit::keyboard::ModifiersState,
+    /// Commands from the page sent before the GPU was ready.
+    #[cfg(target_arch = "wasm32")]
+    pending: Vec<Command>,
 }
 
 pub struct AppState {
     gpu: GpuContext,
     renderer: Renderer,
+    proxy: EventLoopProxy<UserEvent>,
+    placement: Option<Placement>,
+    selection: Option<Selection>,
+    dragging: bool,
+    /// The left button is held down painting falling sand.
+    brushing: bool,
+    panning: bool,
+    clipboard: Option<Grid>,
+    cursor: Option<PhysicalPosition<f64>>,
+    /// The last imported picture, kept so the threshold and dithering can be adjusted.
+    picture: Option<Picture>,
+    threshold: f32,
+    dither: Dither,
+    /// The colour given to placed patterns and noise in multi-colour variants, or `None` for a
+    /// random colour per cell. In falling sand it is also the brush's material, and `None`
+    /// erases.
+    paint: Option<u8>,
+    /// The shape the world is drawn on, kept across rebuilds while the boundary suits it.
+    surface: Option<Surface>,
+    /// Grid lines and the minimap, kept across rebuilds.
+    guides: Guides,
+    /// A 3D world shown in place of the 2D one.
+    voxels: Option<Voxels>,
+    /// Turmites shown in place of the world.
+    ants: Option<Ants>,
+    /// How the world started and how fast it runs.
+    options: Options,
+    /// When the last frame was drawn, to step the world at `options.speed`.
+    last_frame: Option<Instant>,
+    /// Generations due but not yet stepped, when running at a set speed.
+    owed: f32,
+    /// An error shown on screen, and when it was first shown.
+    message: Option<(String, Instant)>,
+    /// Generations only advance when stepped.
+    paused: bool,
+    /// Generations still to be stepped while paused.
+    pending_steps: u32,
+    /// The page's function called with each new generation.
+    #[cfg(target_arch = "wasm32")]
+    on_generation: Option<js_sys::Function>,
+    #[cfg(target_arch = "wasm32")]
+    reported_generation: Option<u64>,
+    /// The shared world shown in place of our own, if one has been joined.
+    remote: Option<Remote>,
+    /// A script is running on a copy of the world, which replaces it when the script finishes.
+    #[cfg(not(target_arch = "wasm32"))]
+    scripting: bool,
 }
 
 impl AppState {
-    async fn new(window: Arc<Window>) -> Result<Self> {
-        let gpu = GpuContext::new(window).await?;
+    async fn new(
+        window: Arc<Window>,
+        proxy: EventLoopProxy<UserEvent>,
+        options: Options,
+    ) -> Result<Self> {
+        let gpu = GpuContext::new(window, &options.gpu).await?;
 
-        let renderer = Renderer::new(&"Main", &gpu);
+        let renderer = Renderer::new("Main", &gpu, &options);
 
-        Ok(Self {
+        let mut state = Self {
             gpu,
             renderer,
-        })
+            proxy,
+            placement: None,
+            selection: None,
+            dragging: false,
+            brushing: false,
+            panning: false,
+            clipboard: None,
+            cursor: None,
+            picture: None,
+            threshold: 0.5,
+            dither: Dither::default(),
+            paint: None,
+            surface: None,
+            guides: Guides::default(),
+            voxels: None,
+            ants: None,
+            options,
+            last_frame: None,
+            owed: 0.0,
+            message: None,
+            paused: false,
+            pending_steps: 0,
+            #[cfg(target_arch = "wasm32")]
+            on_generation: None,
+            #[cfg(target_arch = "wasm32")]
+            reported_generation: None,
+            remote: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            scripting: false,
+        };
+        if let Some(url) = state.options.connect.clone() {
+            state.join(&url);
+        }
+        Ok(state)
+    }
+
+    /// The cell under the cursor, or `None` when the world is drawn on a 3D shape or hidden.
+    fn cursor_cell(&self) -> Option<(i32, i32)> {
+        if self.voxels.is_some() || self.ants.is_some() || self.renderer.surface().is_some() {
+            return None;
+        }
+        let cursor = self.cursor?;
+        Some(self.renderer.cell_at(&self.gpu.surface.window, cursor))
+    }
+
+    /// Where the placed pattern's top-left corner goes for the current cursor position.
+    fn placement_origin(&self) -> Option<(i32, i32)> {
+        let placement = self.placement.as_ref()?;
+        Some(placement.origin(self.cursor_cell()?))
+    }
+
+    /// Re-uploads the ghost preview after the placed pattern changed.
+    fn update_placement(&mut self) {
+        let origin = self.placement_origin().unwrap_or_default();
+        let cells = self.placement.as_ref().map(Placement::grid);
+        self.renderer.show_ghost(&self.gpu, cells, origin);
+        self.update_title();
+    }
+
+    /// Shows `title`, followed by the message if there is one.
+    fn set_title(&self, mut title: String) {
+        if let Some((message, _)) = &self.message {
+            title.push_str(&format!(" - {message}"));
+        }
+        self.gpu.surface.window.set_title(&title);
+        #[cfg(target_arch = "wasm32")]
+        crate::web::show_message(self.message.as_ref().map(|(message, _)| message.as_str()));
+    }
+
+    /// Logs an error and shows it on screen for a while.
+    fn report(&mut self, message: String) {
+        log::error!("{message}");
+        #[cfg(not(target_arch = "wasm32"))]
+        self.renderer.set_message(Some(&message));
+        self.message = Some((message, Instant::now()));
+        self.update_title();
+    }
+
+    /// Clears the message once it has been shown long enough.
+    fn expire_message(&mut self) {
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(_, shown)| shown.elapsed() > MESSAGE_DURATION)
+        {
+            self.message = None;
+            #[cfg(not(target_arch = "wasm32"))]
+            self.renderer.set_message(None);
+            self.update_title();
+        }
+    }
+
+    fn update_title(&self) {
+        #[allow(unused_mut)]
+        let mut title = match (&self.placement, &self.picture) {
+            (Some(placement), _) => format!("{TITLE} - placing {}", placement.name()),
+            (None, Some(_)) => format!(
+                "{TITLE} - image at threshold {:.2} with {}",
+                self.threshold, self.dither
+            ),
+            (None, None) => TITLE.to_string(),
+        };
+        if let Some(voxels) = &self.voxels {
+            title.push_str(&format!(" [3D {}]", voxels.rule()));
+            self.set_title(title);
+            return;
+        }
+        if let Some(ants) = &self.ants {
+            let settings = ants.settings();
+            let ants = if settings.count == 1 { "ant" } else { "ants" };
+            title.push_str(&format!(
+                " [turmite {}, {} {ants} {}, {} steps a frame]",
+                settings.rule, settings.count, settings.start, settings.steps_per_frame
+            ));
+            self.set_title(title);
+            return;
+        }
+        let rule = self.renderer.rule();
+        if rule != Rule::default() {
+            title.push_str(&format!(" [{rule}]"));
+        }
+        let variant = self.renderer.variant();
+        if variant != Variant::Life {
+            let paint = match self.paint {
+                Some(colour) => format!("painting {}", variant.colour_name(colour)),
+                None if variant == Variant::Sand => "erasing".to_string(),
+                None => "painting mixed colours".to_string(),
+            };
+            title.push_str(&format!(" [{variant}, {paint}]"));
+        }
+        let scheme = self.renderer.scheme();
+        if scheme != UpdateScheme::Synchronous {
+            title.push_str(&format!(" [{scheme} updates]"));
+        }
+        if let Some(surface) = self.renderer.surface() {
+            title.push_str(&format!(" [on a {surface}]"));
+        }
+        if let Some(step) = self.renderer.hashlife_step() {
+            title.push_str(&format!(" [HashLife, 2^{step} generations a frame]"));
+        }
+        if let Some(speed) = self.options.speed {
+            title.push_str(&format!(" [{speed} generations a second]"));
+        }
+        if let Some(remote) = &self.remote {
+            title.push_str(&format!(" [shared at {}]", remote.url()));
+        }
+        if self.paused {
+            title.push_str(" [paused]");
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.scripting {
+            title.push_str(" [running script]");
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.renderer.is_recording() {
+            title.push_str(" [recording]");
+        }
+        self.set_title(title);
+        #[cfg(target_arch = "wasm32")]
+        crate::web::set_location_hash(&self.current_options());
+    }
+
+    /// The options that would start the world as it is now, except for the cells it has
+    /// reached.
+    #[cfg(target_arch = "wasm32")]
+    fn current_options(&self) -> Options {
+        Options {
+            rule: Some(self.renderer.rule()),
+            size: self.renderer.grid_size().1,
+            boundary: self.renderer.boundary(),
+            variant: self.renderer.variant(),
+            scheme: self.renderer.scheme(),
+            seed: Some(self.renderer.seed()),
+            ..self.options.clone()
+        }
+    }
+
+    /// How many generations to step this frame: one, those due at the set speed, or those
+    /// still to be stepped while paused.
+    fn steps_due(&mut self) -> u32 {
+        let now = Instant::now();
+        let elapsed = self
+            .last_frame
+            .replace(now)
+            .map_or(0.0, |last| (now - last).as_secs_f32());
+        if self.remote.is_some() {
+            // The server steps a shared world
+            return 0;
+        }
+        let max = self.renderer.max_steps_per_frame();
+        if self.paused {
+            self.owed = 0.0;
+            return self.pending_steps.min(max);
+        }
+        let Some(speed) = self.options.speed else {
+            return 1;
+        };
+        self.owed = (self.owed + elapsed * speed).min(max as f32);
+        let steps = self.owed as u32;
+        self.owed -= steps as f32;
+        steps
+    }
+
+    /// Whether regions can be selected; unbounded worlds cannot be read a region at a time.
+    fn can_select(&self) -> bool {
+        self.renderer.boundary() != Boundary::Unbounded
+    }
+
+    fn selection_rect(&self) -> Option<Rect> {
+        self.selection?.rect(self.renderer.grid_size())
+    }
+
+    fn set_selection(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+        let rect = self.selection_rect();
+        self.renderer.show_selection(&self.gpu, rect);
+    }
+
+    fn handle_key(&mut self, key: &Key, shift: bool) {
+        if let Some(ants) = &mut self.ants {
+            if ants.handle_key(&self.gpu.device, &self.gpu.queue, key) {
+                self.update_title();
+            }
+            return;
+        }
+        match key {
+            Key::Character(c) if c.len() == 1 && c.chars().all(|c| c.is_ascii_digit()) => {
+                // Keys 1 to 9 then 0 select the first ten library patterns
+                let digit = c.parse::<usize>().unwrap_or_default();
+                let index = (digit + 9) % 10;
+                match &mut self.placement {
+                    Some(placement) => placement.select(index),
+                    None => self.placement = Some(Placement::new(index)),
+                }
+            }
+            Key::Named(NamedKey::Tab) => match &mut self.placement {
+                Some(placement) if shift => placement.previous(),
+                Some(placement) => placement.next(),
+                None => self.placement = Some(Placement::new(0)),
+            },
+            Key::Named(NamedKey::Escape) if self.placement.is_some() => self.placement = None,
+            Key::Character(c) if c == "c" && self.renderer.variant() != Variant::Life => {
+                // Mixed, then each of the variant's colours in turn
+                let colours = self.renderer.variant().colours();
+                self.paint = match self.paint {
+                    None => Some(1),
+                    Some(colour) if colour < colours => Some(colour + 1),
+                    Some(_) => None,
+                };
+                self.update_title();
+                return;
+            }
+            Key::Character(c) if c == "," || c == "." => {
+                // Lowers or raises the update scheme's probabilities
+                let delta = if c == "," { -0.05 } else { 0.05 };
+                self.set_scheme(self.renderer.scheme().adjust(delta));
+                return;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Key::Named(NamedKey::F5) => {
+                self.run_script();
+                return;
+            }
+            Key::Named(NamedKey::Space) => {
+                self.set_paused(!self.paused);
+                self.update_title();
+                return;
+            }
+            Key::Character(c) if c == "g" => {
+                self.guides.grid_lines = !self.guides.grid_lines;
+                self.renderer.set_guides(self.guides);
+                return;
+            }
+            Key::Character(c) if c == "m" => {
+                self.guides.minimap = !self.guides.minimap;
+                self.renderer.set_guides(self.guides);
+                return;
+            }
+            Key::Character(c) if c == "-" || c == "=" => {
+                self.options.adjust_speed(c == "=");
+                self.update_title();
+                return;
+            }
+            Key::Named(key @ (NamedKey::PageUp | NamedKey::PageDown)) => {
+                if let Some(step) = self.renderer.hashlife_step() {
+                    let step = match key {
+                        NamedKey::PageUp => step + 1,
+                        _ => step.saturating_sub(1),
+                    };
+                    self.renderer.set_hashlife_step(step);
+                    self.update_title();
+                }
+                return;
+            }
+            _ => {
+                match &mut self.placement {
+                    Some(placement) => match key {
+                        Key::Character(c) if c == "r" => placement.rotate(),
+                        Key::Character(c) if c == "f" => placement.flip(),
+                        _ => return,
+                    },
+                    None => {
+                        if !self.handle_picture_key(key) {
+                            self.handle_selection_key(key);
+                        }
+                    }
+                }
+                if self.placement.is_none() {
+                    return;
+                }
+            }
+        }
+        self.update_placement();
+    }
+
+    /// Adjusts how the imported picture is converted, returning whether the key was used.
+    fn handle_picture_key(&mut self, key: &Key) -> bool {
+        if self.picture.is_none() {
+            return false;
+        }
+        match key {
+            Key::Character(c) if c == "[" => self.threshold = (self.threshold - 0.05).max(0.0),
+            Key::Character(c) if c == "]" => self.threshold = (self.threshold + 0.05).min(1.0),
+            Key::Character(c) if c == "d" => self.dither = self.dither.next(),
+            Key::Named(NamedKey::Escape) => {
+                self.picture = None;
+                self.update_title();
+                return true;
+            }
+            _ => return false,
+        }
+        self.apply_picture();
+        true
+    }
+
+    /// Replaces the world with a PNG or JPEG image resampled to the grid.
+    fn import_picture(&mut self, bytes: &[u8]) {
+        let (width, height) = self.renderer.grid_size();
+        match Picture::decode(bytes, width, height) {
+            Ok(picture) => {
+                self.picture = Some(picture);
+                self.apply_picture();
+            }
+            Err(e) => self.report(format!("Unable to import the image: {e:#}")),
+        }
     }
+
+    /// Loads a dropped or chosen file: a pattern if its name says so, otherwise an image.
+    fn open_file(&mut self, name: &str, bytes: &[u8]) {
+        if !pattern::is_pattern_file(name) {
+            self.import_picture(bytes);
+            return;
+        }
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => return self.report(format!("Unable to load {name}: {e}")),
+        };
+        if text.trim_start().starts_with("[M2]") {
+            match macrocell::read(text) {
+                Ok(life) => self.load_universe(life),
+                Err(e) => self.report(format!("Unable to load {name}: {e:#}")),
+            }
+            return;
+        }
+        match Pattern::from_text(text) {
+            Ok(pattern) => self.load_pattern(pattern),
+            Err(e) => self.report(format!("Unable to load {name}: {e:#}")),
+        }
+    }
+
+    /// Starts the world again from a universe read from a macrocell file: in a grid if it
+    /// fits, or else run with HashLife.
+    fn load_universe(&mut self, life: HashLife) {
+        if self.refuse_shared() {
+            return;
+        }
+        let Some(((left, top), (width, height))) = life.extent() else {
+            return self.load_pattern(Pattern::new(Grid::new(0, 0)));
+        };
+        if width <= u64::from(MAX_SIZE) && height <= u64::from(MAX_SIZE) {
+            match life.to_pattern() {
+                Ok((pattern, _)) => self.load_pattern(pattern),
+                Err(e) => self.report(format!("Unable to expand the pattern: {e:#}")),
+            }
+            return;
+        }
+        log::info!("Running a {width} × {height} pattern with HashLife");
+        let viewport = Viewport {
+            centre: (
+                (left as f64 + width as f64 / 2.0) as f32,
+                (top as f64 + height as f64 / 2.0) as f32,
+            ),
+            zoom: MIN_ZOOM,
+        };
+        let renderer = Renderer::from_hashlife("Main", &self.gpu, life, viewport, 0);
+        self.set_selection(None);
+        self.replace_renderer(renderer);
+    }
+
+    /// Writes the whole world as a macrocell pattern: to `pattern.mc` natively, and to the
+    /// clipboard on the web.
+    fn export_macrocell(&self) {
+        let text = self.renderer.macrocell(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            let exported = text.await.and_then(|text| {
+                std::fs::write(MACROCELL_FILE, text)
+                    .with_context(|| format!("unable to write {MACROCELL_FILE}"))?;
+                log::info!("Exported the pattern to {MACROCELL_FILE}");
+                Ok(())
+            });
+            #[cfg(target_arch = "wasm32")]
+            let exported = text.await.map(clipboard::write_text);
+            if let Err(e) = exported {
+                let message = format!("Unable to export the pattern: {e:#}");
+                let _ = proxy.send_event(UserEvent::Failed(message));
+            }
+        });
+    }
+
+    fn apply_picture(&mut self) {
+        let Some(picture) = &self.picture else {
+            return;
+        };
+        let grid = picture.to_grid(self.threshold, self.dither);
+        match self.remote {
+            Some(_) => self.stamp(&grid, (0, 0)),
+            None => self.renderer.restart(&self.gpu, &grid),
+        }
+        self.update_title();
+    }
+
+    fn handle_selection_key(&mut self, key: &Key) {
+        let Some(rect) = self.selection_rect() else {
+            return;
+        };
+        match key {
+            Key::Character(c) if c == "r" => self.read_selection(SelectionOp::Rotate),
+            Key::Character(c) if c == "f" => self.read_selection(SelectionOp::Flip),
+            Key::Character(c) if c == "i" => self.read_selection(SelectionOp::Invert),
+            Key::Character(c) if c == "n" => {
+                let noise = Grid::random(rect.width, rect.height, 0.3, &mut rand::rng());
+                let noise = self.painted(&noise);
+                self.stamp(&noise, (rect.x as i32, rect.y as i32));
+            }
+            Key::Named(NamedKey::Delete | NamedKey::Backspace) => {
+                let empty = Grid::new(rect.width, rect.height);
+                self.stamp(&empty, (rect.x as i32, rect.y as i32));
+            }
+            Key::Named(NamedKey::Escape) => self.set_selection(None),
+            _ => {}
+        }
+    }
+
+    /// Plain cells in the paint colour for a multi-colour variant. Cells that already have colours
+    /// keep them.
+    fn painted(&self, cells: &Grid) -> Grid {
+        let mut cells = cells.clone();
+        let variant = self.renderer.variant();
+        if variant != Variant::Life && cells.cells.iter().all(|&c| c <= 1) {
+            cells.recolour(self.paint, variant.colours(), &mut rand::rng());
+        }
+        cells
+    }
+
+    /// Writes `cells` into the world, or asks the server to if it is shared.
+    fn stamp(&mut self, cells: &Grid, origin: (i32, i32)) {
+        match &self.remote {
+            Some(remote) => remote.send(ClientMessage::Stamp {
+                origin,
+                cells: cells.clone(),
+            }),
+            None => self.renderer.stamp(&self.gpu, cells, origin),
+        }
+    }
+
+    /// Fills the brush's square around `cell` with the paint material, or empties it.
+    fn brush(&mut self, (x, y): (i32, i32)) {
+        let mut cells = Grid::new(BRUSH_SIZE, BRUSH_SIZE);
+        cells.cells.fill(self.paint.unwrap_or(sand::EMPTY));
+        let half = (BRUSH_SIZE / 2) as i32;
+        self.stamp(&cells, (x - half, y - half));
+    }
+
+    /// Reads the selected cells back from the GPU, then applies `op` when they arrive.
+    fn read_selection(&self, op: SelectionOp) {
+        let Some(rect) = self.selection_rect() else {
+            return;
+        };
+        let cells = self.renderer.read_region(&self.gpu, rect);
+        let proxy = self.proxy.clone();
+        let task = async move {
+            match cells.await {
+                Ok(cells) => {
+                    let _ = proxy.send_event(UserEvent::SelectionRead(op, rect, cells));
+                }
+                Err(e) => {
+                    let message = format!("Unable to read the selection: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        };
+
+        spawn(task);
+    }
+
+    fn apply_selection_op(&mut self, op: SelectionOp, rect: Rect, cells: Grid) {
+        let origin = (rect.x as i32, rect.y as i32);
+        match op {
+            SelectionOp::Copy => self.copy(cells),
+            SelectionOp::Cut => {
+                self.copy(cells);
+                let empty = Grid::new(rect.width, rect.height);
+                self.stamp(&empty, origin);
+            }
+            SelectionOp::Rotate => {
+                // Turn about the centre of the selection, which then follows the rotated cells
+                let rotated = cells.rotate_clockwise();
+                let centre = (
+                    rect.x as i32 + rect.width as i32 / 2,
+                    rect.y as i32 + rect.height as i32 / 2,
+                );
+                let rotated_origin = (
+                    centre.0 - rotated.width as i32 / 2,
+                    centre.1 - rotated.height as i32 / 2,
+                );
+                let empty = Grid::new(rect.width, rect.height);
+                self.stamp(&empty, origin);
+                self.stamp(&rotated, rotated_origin);
+
+                let mut selection = Selection::new(rotated_origin);
+                selection.drag_to((
+                    rotated_origin.0 + rotated.width as i32 - 1,
+                    rotated_origin.1 + rotated.height as i32 - 1,
+                ));
+                self.set_selection(Some(selection));
+            }
+            SelectionOp::Flip => {
+                self.stamp(&cells.flip_horizontal(), origin);
+            }
+            SelectionOp::Invert => self.stamp(&cells.invert(), origin),
+        }
+    }
+
+    /// Swaps in a new world, keeping the placement and selection.
+    fn replace_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+        self.renderer.set_guides(self.guides);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.renderer
+            .set_message(self.message.as_ref().map(|(message, _)| message.as_str()));
+        if let Some(surface) = self.surface
+            && let Err(e) = self.renderer.set_surface(&self.gpu, Some(surface))
+        {
+            log::info!("Drawing flat: {e:#}");
+            self.surface = None;
+        }
+        // The picture was resampled for the old grid
+        self.picture = None;
+        self.update_placement();
+        let selection = self.selection;
+        self.set_selection(selection);
+        self.gpu.surface.window.request_redraw();
+    }
+
+    /// Rebuilds the world with the next boundary: torus, dead, then unbounded. Falling sand
+    /// stays bounded.
+    fn cycle_boundary(&self) {
+        let sand = self.renderer.variant() == Variant::Sand;
+        let boundary = match self.renderer.boundary() {
+            Boundary::Torus => Boundary::Dead,
+            Boundary::Dead if sand => Boundary::Torus,
+            Boundary::Dead => Boundary::Unbounded,
+            Boundary::Unbounded | Boundary::Cube => Boundary::Torus,
+        };
+        self.set_boundary(boundary);
+    }
+
+    /// Rebuilds the world with `boundary`.
+    fn set_boundary(&self, boundary: Boundary) {
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            match session.await {
+                Ok(session) => {
+                    let session = Box::new(session.with_boundary(boundary));
+                    let _ = proxy.send_event(UserEvent::Rebuild(session));
+                }
+                Err(e) => {
+                    let message = format!("Unable to change the boundary: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    }
+
+    /// Draws the world on the next shape: a torus, a cube, a sphere, then flat again. The world
+    /// is rebuilt first if its edges do not meet as the shape's do.
+    fn cycle_surface(&mut self) {
+        self.surface = Surface::next(self.surface);
+        match self.surface {
+            Some(surface) if self.renderer.boundary() != surface.boundary() => {
+                self.set_boundary(surface.boundary());
+            }
+            surface => {
+                if let Err(e) = self.renderer.set_surface(&self.gpu, surface) {
+                    self.surface = None;
+                    self.report(format!("Unable to draw on a shape: {e:#}"));
+                }
+                self.update_placement();
+            }
+        }
+    }
+
+    /// Shows the next 3D world in place of the 2D one, each a new soup under one of `RULES_3D`,
+    /// then the 2D world again.
+    fn cycle_voxels(&mut self) {
+        let next = match &self.voxels {
+            None => Some(0),
+            Some(voxels) => RULES_3D
+                .iter()
+                .position(|&(rule, _)| rule == voxels.rule())
+                .map(|i| i + 1)
+                .filter(|&i| i < RULES_3D.len()),
+        };
+        self.ants = None;
+        self.voxels = next.map(|i| {
+            let (rule, density) = RULES_3D[i];
+            let soup = Grid3d::soup(
+                VOXEL_GRID_SIZE,
+                VOXEL_GRID_SIZE / 2,
+                density,
+                &mut rand::rng(),
+            );
+            Voxels::new(
+                &self.gpu.device,
+                &self.gpu.queue,
+                self.gpu.surface.format(),
+                &soup,
+                rule,
+            )
+        });
+        self.update_placement();
+    }
+
+    /// Shows turmites in place of the world, starting with a single Langton's ant, or hides them
+    /// again.
+    fn toggle_ants(&mut self) {
+        self.voxels = None;
+        self.ants = match self.ants {
+            Some(_) => None,
+            None => Some(Ants::new(
+                &self.gpu.device,
+                &self.gpu.queue,
+                self.gpu.surface.format(),
+                AntSettings::default(),
+            )),
+        };
+        self.update_placement();
+    }
+
+    /// Passes a key to whichever camera is looking at a 3D world, returning whether it moves
+    /// the camera.
+    fn camera_key(&mut self, key: winit::keyboard::PhysicalKey, pressed: bool) -> bool {
+        match &mut self.voxels {
+            Some(voxels) => voxels.handle_key(key, pressed),
+            None => self.renderer.camera_key(key, pressed),
+        }
+    }
+
+    /// Rebuilds the world with the next shape of cells: square, hexagonal, then triangular, each
+    /// with its own rule.
+    fn cycle_neighbourhood(&self) {
+        self.set_rule(self.renderer.rule().neighbourhood.next().rule());
+    }
+
+    /// Rebuilds the world under `rule`, or asks the server to if it is shared.
+    fn set_rule(&self, rule: Rule) {
+        if let Some(remote) = &self.remote {
+            remote.send(ClientMessage::SetRule(rule));
+            return;
+        }
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            match session.await {
+                Ok(session) => {
+                    let session = Box::new(session.with_rule(rule));
+                    let _ = proxy.send_event(UserEvent::Rebuild(session));
+                }
+                Err(e) => {
+                    let message = format!("Unable to change the rule: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    }
+
+    /// Starts the world again from `pattern`.
+    fn load_pattern(&mut self, pattern: Pattern) {
+        let session = self.renderer.session(&self.gpu);
+        self.options.pattern = Some(pattern.clone());
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            match session.await {
+                Ok(session) => {
+                    let session = Box::new(session.with_pattern(&pattern));
+                    let _ = proxy.send_event(UserEvent::Rebuild(session));
+                }
+                Err(e) => {
+                    let message = format!("Unable to load the pattern: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    }
+
+    /// Carries out a request from the page's script.
+    #[cfg(target_arch = "wasm32")]
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::SetRule(rule) => self.set_rule(rule),
+            Command::LoadPattern(pattern) => self.load_pattern(pattern),
+            Command::Step(generations) => {
+                self.paused = true;
+                self.pending_steps = self.pending_steps.saturating_add(generations);
+            }
+            Command::Pause => self.set_paused(true),
+            Command::Resume => self.set_paused(false),
+            Command::SetCell { x, y, alive } if self.remote.is_some() => {
+                if let Some(remote) = &self.remote {
+                    remote.send(ClientMessage::Paint {
+                        cells: vec![(x, y)],
+                        alive,
+                    });
+                }
+            }
+            Command::SetCell { x, y, alive } => {
+                let mut cell = Grid::new(1, 1);
+                cell.set(0, 0, alive);
+                let cell = self.painted(&cell);
+                self.stamp(&cell, (x, y));
+            }
+            Command::Population(reply) => {
+                let session = self.renderer.session(&self.gpu);
+                spawn(async move {
+                    let population = session.await.map(|session| session.grid.population());
+                    let _ = reply.send(population.map(|p| p as u64).map_err(|e| format!("{e:#}")));
+                });
+            }
+            Command::ExportRle(reply) => {
+                let session = self.renderer.session(&self.gpu);
+                spawn(async move {
+                    let rle = session.await.map(|session| {
+                        Pattern {
+                            name: None,
+                            rule: Some(session.rule),
+                            grid: session.grid,
+                        }
+                        .to_rle()
+                    });
+                    let _ = reply.send(rle.map_err(|e| format!("{e:#}")));
+                });
+            }
+            Command::OnGeneration(callback) => {
+                self.on_generation = callback;
+                self.reported_generation = None;
+            }
+        }
+        self.update_title();
+    }
+
+    /// Pauses or resumes the world, or asks the server to if it is shared.
+    fn set_paused(&mut self, paused: bool) {
+        match &self.remote {
+            Some(remote) => remote.send(if paused {
+                ClientMessage::Pause
+            } else {
+                ClientMessage::Resume
+            }),
+            None => {
+                self.paused = paused;
+                if !paused {
+                    self.pending_steps = 0;
+                }
+            }
+        }
+    }
+
+    /// Starts joining the world shared at `url`, which replaces ours once its server sends it.
+    fn join(&mut self, url: &str) {
+        match Remote::connect(url, self.proxy.clone()) {
+            Ok(remote) => {
+                log::info!("Joining the shared world at {url}");
+                self.remote = Some(remote);
+            }
+            Err(e) => self.report(format!("Unable to join {url}: {e:#}")),
+        }
+    }
+
+    /// Shows the shared world as its server last described it.
+    fn receive_shared(&mut self, message: ServerMessage) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+        let rebuild = matches!(message, ServerMessage::Welcome(_) | ServerMessage::Rule(_));
+        if let Err(e) = remote.apply(message) {
+            return self.leave(format!("{e:#}"));
+        }
+        let Some(world) = self.remote.as_ref().and_then(Remote::world) else {
+            return;
+        };
+        self.paused = world.paused;
+        if rebuild {
+            let session = self.shared_session(world);
+            let renderer = Renderer::from_session("Main", &self.gpu, &session);
+            self.replace_renderer(renderer);
+        } else {
+            self.renderer
+                .overwrite(&self.gpu, &world.grid, world.generation);
+        }
+        self.update_title();
+    }
+
+    /// A session showing the shared world, keeping the view if the grid is the same.
+    fn shared_session(&self, world: &SharedWorld) -> Session {
+        let size = (world.grid.width, world.grid.height);
+        let viewport = if self.renderer.grid_size() == size
+            && self.renderer.rule().neighbourhood == world.rule.neighbourhood
+        {
+            self.renderer.viewport()
+        } else {
+            Viewport::new(size, world.rule.neighbourhood)
+        };
+        Session {
+            grid: world.grid.clone(),
+            rule: world.rule,
+            boundary: world.boundary,
+            variant: world.variant,
+            scheme: UpdateScheme::Synchronous,
+            origin: (0, 0),
+            generation: world.generation,
+            seed: 0,
+            viewport,
+        }
+    }
+
+    /// Goes back to running our own world, from where the shared one was.
+    fn leave(&mut self, reason: String) {
+        self.remote = None;
+        self.paused = false;
+        self.report(format!("Left the shared world: {reason}"));
+    }
+
+    /// Whether the world is shared, in which case only its server changes how it runs.
+    fn refuse_shared(&mut self) -> bool {
+        if self.remote.is_some() {
+            self.report("Only the server can change how a shared world runs".to_string());
+        }
+        self.remote.is_some()
+    }
+
+    /// Calls the page's generation callback if the generation has moved on.
+    #[cfg(target_arch = "wasm32")]
+    fn report_generation(&mut self) {
+        let Some(callback) = &self.on_generation else {
+            return;
+        };
+        let generation = self.renderer.generation();
+        if self.reported_generation != Some(generation) {
+            self.reported_generation = Some(generation);
+            if let Err(e) = callback.call1(&JsValue::NULL, &JsValue::from_f64(generation as f64)) {
+                log::error!("The generation callback failed: {e:?}");
+            }
+        }
+    }
+
+    /// Rebuilds the world as the next variant: Life, Immigration, QuadLife, then falling sand.
+    /// Cells are coloured at random, or all white for Life.
+    fn cycle_variant(&self) {
+        let variant = self.renderer.variant().next();
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            match session.await {
+                Ok(session) => {
+                    let session = Box::new(session.with_variant(variant, &mut rand::rng()));
+                    let _ = proxy.send_event(UserEvent::Rebuild(session));
+                }
+                Err(e) => {
+                    let message = format!("Unable to change the variant: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    }
+
+    /// Updates the world under `scheme` from the next generation on.
+    fn set_scheme(&mut self, scheme: UpdateScheme) {
+        if self.refuse_shared() {
+            return;
+        }
+        match self.renderer.set_scheme(&self.gpu, scheme) {
+            Ok(()) => self.update_title(),
+            Err(e) => self.report(format!("Unable to change the update scheme: {e:#}")),
+        }
+    }
+
+    /// Moves the world between HashLife and the GPU, which runs it unbounded.
+    fn toggle_hashlife(&mut self) {
+        if self.renderer.variant() == Variant::Sand {
+            self.report("Falling sand cannot run with HashLife".to_string());
+            return;
+        }
+        let to_hashlife = self.renderer.hashlife_step().is_none();
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            match session.await {
+                Ok(session) => {
+                    let session = Box::new(session.with_boundary(Boundary::Unbounded));
+                    let _ = proxy.send_event(if to_hashlife {
+                        UserEvent::HashLifeRead(session)
+                    } else {
+                        UserEvent::Rebuild(session)
+                    });
+                }
+                Err(e) => {
+                    let message = format!("Unable to switch engines: {e:#}");
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    }
+
+    fn start_hashlife(&mut self, session: &Session) {
+        if self.refuse_shared() {
+            return;
+        }
+        match Renderer::hashlife("Main", &self.gpu, session, 0) {
+            Ok(renderer) => {
+                self.set_selection(None);
+                self.replace_renderer(renderer);
+            }
+            Err(e) => self.report(format!("Unable to run HashLife: {e:#}")),
+        }
+    }
+
+    fn rebuild(&mut self, session: &Session) {
+        if self.refuse_shared() {
+            return;
+        }
+        log::info!(
+            "Boundary: {:?}, variant: {}, rule: {}",
+            session.boundary,
+            session.variant,
+            session.rule
+        );
+        if session.boundary == Boundary::Unbounded {
+            self.set_selection(None);
+        }
+        if self.paint > Some(session.variant.colours()) {
+            self.paint = None;
+        }
+        let renderer = Renderer::from_session("Main", &self.gpu, session);
+        self.replace_renderer(renderer);
+    }
+
+    fn save_session(&self) {
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            if let Err(e) = session.await.and_then(|session| session::save(&session)) {
+                let message = format!("Unable to save the session: {e:#}");
+                let _ = proxy.send_event(UserEvent::Failed(message));
+            }
+        });
+    }
+
+    /// Starts or stops recording to `recording.gif`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn toggle_recording(&mut self) {
+        let options = RecordingOptions::default();
+        match self.renderer.stop_recording() {
+            Some(frames) => log::info!("Wrote {frames} frames to {}", options.output.display()),
+            None => {
+                if let Err(e) = self.renderer.start_recording(&self.gpu, &options) {
+                    self.report(format!("Unable to start recording: {e:#}"));
+                }
+            }
+        }
+        self.update_title();
+    }
+
+    /// Runs the script given with `--script` on a copy of the world, on a thread of its own.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_script(&mut self) {
+        if self.refuse_shared() {
+            return;
+        }
+        let Some(path) = self.options.script.clone() else {
+            return self.report("No script to run; give one with --script".to_string());
+        };
+        if self.scripting {
+            return self.report("A script is already running".to_string());
+        }
+        let session = self.renderer.session(&self.gpu);
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            let session = match session.await {
+                Ok(session) => session,
+                Err(e) => {
+                    let message = format!("unable to read the world: {e:#}");
+                    let _ = proxy.send_event(UserEvent::ScriptFinished(Err(message)));
+                    return;
+                }
+            };
+            std::thread::spawn(move || {
+                let finished = ScriptWorld::new(session)
+                    .and_then(|world| script::run_file(&path, world))
+                    .map(Box::new)
+                    .map_err(|e| format!("{e:#}"));
+                let _ = proxy.send_event(UserEvent::ScriptFinished(finished));
+            });
+        });
+        self.scripting = true;
+        self.update_title();
+    }
+
+    /// Shows the world a script left, in a grid if it fits, or else run with HashLife as it was
+    /// before.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn finish_script(&mut self, finished: Result<Box<ScriptWorld>, String>) {
+        self.scripting = false;
+        let world = match finished {
+            Ok(world) => world,
+            Err(message) => return self.report(format!("The script failed: {message}")),
+        };
+        if self.refuse_shared() {
+            return;
+        }
+        let renderer = match world.into_parts() {
+            (session, None) => Renderer::from_session("Main", &self.gpu, &session),
+            (mut session, Some(life)) => match life.to_grid() {
+                Ok((grid, (x, y)))
+                    if self.renderer.hashlife_step().is_none()
+                        && let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) =>
+                {
+                    session.grid = grid;
+                    session.origin = (x, y);
+                    session.generation = life.generation();
+                    Renderer::from_session("Main", &self.gpu, &session)
+                }
+                _ => {
+                    Renderer::from_hashlife("Main", &self.gpu, life, session.viewport, session.seed)
+                }
+            },
+        };
+        self.set_selection(None);
+        self.replace_renderer(renderer);
+    }
+
+    fn load_session(&mut self) {
+        if self.refuse_shared() {
+            return;
+        }
+        match session::load() {
+            Ok(session) => {
+                let renderer = Renderer::from_session("Main", &self.gpu, &session);
+                self.replace_renderer(renderer);
+            }
+            Err(e) => self.report(format!("Unable to load the session: {e:#}")),
+        }
+    }
+
+    /// Puts cells on the internal clipboard and, as RLE, on the system clipboard.
+    fn copy(&mut self, cells: Grid) {
+        let pattern = Pattern {
+            name: None,
+            rule: Some(self.renderer.rule()),
+            grid: cells,
+        };
+        clipboard::write_text(pattern.to_rle());
+        self.clipboard = Some(pattern.grid);
+    }
+
+    /// Reads the system clipboard, which browsers only allow asynchronously, then pastes.
+    fn paste(&self) {
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            let text = clipboard::read_text().await;
+            let _ = proxy.send_event(UserEvent::Pasted(text));
+        });
+    }
+
+    /// Starts placing the clipboard contents, preferring a pattern in `text` from the system
+    /// clipboard.
+    fn paste_text(&mut self, text: Option<String>) {
+        let pattern = text
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| Pattern::from_text(&text));
+        let cells = match pattern {
+            Some(Ok(pattern)) => pattern.grid,
+            Some(Err(e)) if self.clipboard.is_none() => {
+                self.report(format!("The clipboard holds no pattern: {e:#}"));
+                return;
+            }
+            _ => match &self.clipboard {
+                Some(cells) => cells.clone(),
+                None => return,
+            },
+        };
+        self.set_selection(None);
+        self.placement = Some(Placement::paste(cells));
+        self.update_placement();
+    }
+}
+
+/// Runs a task to completion: immediately on native, from the browser's event loop on the web.
+fn spawn(task: impl Future<Output = ()> + 'static) {
+    #[cfg(not(target_arch = "wasm32"))]
+    pollster::block_on(task);
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+/// Sends images chosen with the page's file input to the event loop.
+#[cfg(target_arch = "wasm32")]
+fn watch_image_input(proxy: EventLoopProxy<UserEvent>) {
+    use wasm_bindgen::JsCast;
+
+    const IMAGE_INPUT_ID: &str = "image-input";
+
+    let Some(input) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(IMAGE_INPUT_ID))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok())
+    else {
+        log::warn!("No #{IMAGE_INPUT_ID} element; image import is unavailable");
+        return;
+    };
+
+    let target = input.clone();
+    let on_change = Closure::<dyn FnMut()>::new(move || {
+        let Some(file) = target.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        // Clear the input so choosing the same file again fires another change event
+        target.set_value("");
+        let proxy = proxy.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => {
+                    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                    let _ = proxy.send_event(UserEvent::PictureLoaded(bytes));
+                }
+                Err(e) => {
+                    let message = format!("Unable to read {}: {e:?}", file.name());
+                    let _ = proxy.send_event(UserEvent::Failed(message));
+                }
+            }
+        });
+    });
+    input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    on_change.forget();
+}
+
+/// Sends files dropped on the canvas to the event loop.
+#[cfg(target_arch = "wasm32")]
+fn watch_drops(canvas: &web_sys::Element, proxy: EventLoopProxy<UserEvent>) {
+    use wasm_bindgen::JsCast;
+
+    // The browser only allows a drop where dragging over was cancelled
+    let on_drag_over =
+        Closure::<dyn FnMut(web_sys::DragEvent)>::new(|event: web_sys::DragEvent| {
+            event.prevent_default();
+        });
+    let on_drop =
+        Closure::<dyn FnMut(web_sys::DragEvent)>::new(move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            let Some(file) = event
+                .data_transfer()
+                .and_then(|transfer| transfer.files())
+                .and_then(|files| files.get(0))
+            else {
+                return;
+            };
+            let proxy = proxy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                    Ok(buffer) => {
+                        let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                        let _ = proxy.send_event(UserEvent::FileDropped(file.name(), bytes));
+                    }
+                    Err(e) => {
+                        let message = format!("Unable to read {}: {e:?}", file.name());
+                        let _ = proxy.send_event(UserEvent::Failed(message));
+                    }
+                }
+            });
+        });
+    for (event, listener) in [("dragover", &on_drag_over), ("drop", &on_drop)] {
+        if let Err(e) =
+            canvas.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+        {
+            log::warn!("Unable to listen for {event} events: {e:?}");
+        }
+    }
+    on_drag_over.forget();
+    on_drop.forget();
 }
 
 impl App {
-    pub fn new(
-        #[cfg(target_arch = "wasm32")] event_loop: &winit::event_loop::EventLoop<AppState>,
-    ) -> Self {
+    pub fn new(event_loop: &EventLoop<UserEvent>, options: Options) -> Self {
         Self {
-            #[cfg(target_arch = "wasm32")]
-            proxy: Some(event_loop.create_proxy()),
+            options,
+            proxy: event_loop.create_proxy(),
             window: None,
             state: None,
             keyboard_modifiers: Default::default(),
+            #[cfg(target_arch = "wasm32")]
+            pending: Vec::new(),
         }
     }
 }
 
-impl ApplicationHandler<AppState> for App {
+impl ApplicationHandler<UserEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         #[allow(unused_mut)]
-        let mut window_attributes = Window::default_attributes();
+        let mut window_attributes = Window::default_attributes().with_title(TITLE);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((width, height)) = self.options.window_size {
+            window_attributes =
+                window_attributes.with_inner_size(winit::dpi::PhysicalSize::new(width, height));
+        }
 
         #[cfg(target_arch = "wasm32")]
         {
@@ -67,8 +1412,11 @@ impl ApplicationHandler<AppState> for App {
             let browser = wgpu::web_sys::window().unwrap_throw();
             let document = browser.document().unwrap_throw();
             let canvas = document.get_element_by_id(CANVAS_ID).unwrap_throw();
+            watch_drops(&canvas, self.proxy.clone());
             let html_canvas_element = canvas.unchecked_into();
             window_attributes = window_attributes.with_canvas(Some(html_canvas_element));
+
+            watch_image_input(self.proxy.clone());
         }
 
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
@@ -79,36 +1427,77 @@ impl ApplicationHandler<AppState> for App {
         {
             // If we are not on web we can use pollster to
             // await the window
-            self.state = Some(pollster::block_on(AppState::new(window)).unwrap());
+            self.state = Some(
+                pollster::block_on(AppState::new(
+                    window,
+                    self.proxy.clone(),
+                    self.options.clone(),
+                ))
+                .unwrap(),
+            );
         }
 
         #[cfg(target_arch = "wasm32")]
         {
             // Run the future asynchronously and use the
             // proxy to send the results to the event loop
-            if let Some(proxy) = self.proxy.take() {
-                wasm_bindgen_futures::spawn_local(async move {
-                    assert!(
-                        proxy
-                            .send_event(
-                                AppState::new(window)
-                                    .await
-                                    .expect("Unable to create canvas!")
-                            )
-                            .is_ok()
-                    )
-                });
-            }
+            let proxy = self.proxy.clone();
+            let options = self.options.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let state = AppState::new(window, proxy.clone(), options)
+                    .await
+                    .expect("Unable to create canvas!");
+                assert!(proxy.send_event(UserEvent::Ready(Box::new(state))).is_ok())
+            });
         }
     }
 
     // This is where proxy.send_event() ends up
-    #[cfg(target_arch = "wasm32")]
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, mut state: AppState) {
-        state.gpu.resize();
-        state.gpu.surface.window.request_redraw();
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        match (&mut self.state, event) {
+            #[cfg(target_arch = "wasm32")]
+            (_, UserEvent::Ready(mut state)) => {
+                state.gpu.resize();
+                state.gpu.surface.window.request_redraw();
 
-        self.state = Some(state);
+                for command in self.pending.drain(..) {
+                    state.run_command(command);
+                }
+                self.state = Some(*state);
+            }
+            (Some(state), UserEvent::SelectionRead(op, rect, cells)) => {
+                state.apply_selection_op(op, rect, cells);
+            }
+            (None, UserEvent::SelectionRead(..)) => {}
+            (Some(state), UserEvent::Rebuild(session)) => state.rebuild(&session),
+            (None, UserEvent::Rebuild(_)) => {}
+            (Some(state), UserEvent::HashLifeRead(session)) => state.start_hashlife(&session),
+            (None, UserEvent::HashLifeRead(_)) => {}
+            #[cfg(target_arch = "wasm32")]
+            (Some(state), UserEvent::PictureLoaded(bytes)) => state.import_picture(&bytes),
+            #[cfg(target_arch = "wasm32")]
+            (None, UserEvent::PictureLoaded(_)) => {}
+            #[cfg(target_arch = "wasm32")]
+            (Some(state), UserEvent::FileDropped(name, bytes)) => state.open_file(&name, &bytes),
+            #[cfg(target_arch = "wasm32")]
+            (None, UserEvent::FileDropped(..)) => {}
+            (Some(state), UserEvent::Pasted(text)) => state.paste_text(text),
+            (None, UserEvent::Pasted(_)) => {}
+            (Some(state), UserEvent::Failed(message)) => state.report(message),
+            (None, UserEvent::Failed(message)) => log::error!("{message}"),
+            (Some(state), UserEvent::Shared(message)) => state.receive_shared(message),
+            (None, UserEvent::Shared(_)) => {}
+            (Some(state), UserEvent::Disconnected(reason)) => state.leave(reason),
+            (None, UserEvent::Disconnected(reason)) => log::error!("{reason}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Some(state), UserEvent::ScriptFinished(finished)) => state.finish_script(finished),
+            #[cfg(not(target_arch = "wasm32"))]
+            (None, UserEvent::ScriptFinished(_)) => {}
+            #[cfg(target_arch = "wasm32")]
+            (Some(state), UserEvent::Command(command)) => state.run_command(command),
+            #[cfg(target_arch = "wasm32")]
+            (None, UserEvent::Command(command)) => self.pending.push(command),
+        }
     }
 
     fn window_event(
@@ -121,7 +1510,28 @@ impl ApplicationHandler<AppState> for App {
             (_, WindowEvent::CloseRequested) => event_loop.exit(),
             (Some(state), WindowEvent::Resized(_)) => state.gpu.resize(),
             (Some(state), WindowEvent::RedrawRequested) => {
-                match state.renderer.render(&state.gpu.surface.window, &state.gpu) {
+                state.expire_message();
+                let steps = state.steps_due();
+                let window = &state.gpu.surface.window;
+                let rendered = match (&mut state.voxels, &mut state.ants) {
+                    (Some(voxels), _) => voxels.render(window, &state.gpu),
+                    (None, Some(ants)) => ants.render(window, &state.gpu),
+                    (None, None) => {
+                        let rendered =
+                            state
+                                .renderer
+                                .render(&state.gpu.surface.window, &state.gpu, steps);
+                        if let Ok(taken) = rendered {
+                            state.pending_steps = state.pending_steps.saturating_sub(taken);
+                        }
+                        if let Some(message) = state.renderer.take_failure() {
+                            state.report(message);
+                            state.set_paused(true);
+                        }
+                        rendered.map(|_| ())
+                    }
+                };
+                match rendered {
                     Ok(_) => {}
                     Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                         state.gpu.resize();
@@ -130,35 +1540,236 @@ impl ApplicationHandler<AppState> for App {
                         log::error!("Unable to render {e}");
                     }
                 }
+                #[cfg(target_arch = "wasm32")]
+                state.report_generation();
             }
             (_, WindowEvent::ModifiersChanged(modifiers)) => {
                 self.keyboard_modifiers = modifiers.state();
             }
-            (state, WindowEvent::KeyboardInput { event, .. }) => {
-                if self.keyboard_modifiers.control_key() && event.state == winit::event::ElementState::Pressed {
-                    match event.logical_key {
-                        Key::Character(ref key) if key == &"q" => {
-                            #[cfg(target_arch = "wasm32")]
-                            if let Some(window) = web_sys::window() {
-                                let _ = window.close();
-                            }
-                            #[cfg(not(target_arch = "wasm32"))]
-                            {
-                                event_loop.exit();
-                            }
-                        }
-                        Key::Character(ref key) if key == &"r" => {
-                            #[cfg(target_arch = "wasm32")]
-                            if let Some(window) = web_sys::window() {
-                                let _ = window.location().reload();
-                            }
-                            #[cfg(not(target_arch = "wasm32"))]
-                            if let (Some(state), Some(window)) = (state, &self.window) {
-                                state.renderer = Renderer::new(&"Main", &state.gpu);
-                                window.request_redraw();
-                            }
-                        }
-                        _ => {}
+            (state, WindowEvent::KeyboardInput { event, .. })
+                if self.keyboard_modifiers.control_key()
+                    && event.state == winit::event::ElementState::Pressed =>
+            {
+                match event.logical_key {
+                    Key::Character(ref key) if key == "q" => {
+                        #[cfg(target_arch = "wasm32")]
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.close();
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            event_loop.exit();
+                        }
+                    }
+                    Key::Character(ref key) if key == "r" => {
+                        #[cfg(target_arch = "wasm32")]
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.location().reload();
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if let Some(state) = state
+                            && !state.refuse_shared()
+                        {
+                            let options = Options {
+                                rule: Some(state.renderer.rule()),
+                                boundary: state.renderer.boundary(),
+                                variant: state.renderer.variant(),
+                                scheme: state.renderer.scheme(),
+                                seed: None,
+                                pattern: None,
+                                ..state.options.clone()
+                            };
+                            let renderer = Renderer::new("Main", &state.gpu, &options);
+                            state.replace_renderer(renderer);
+                        }
+                    }
+                    Key::Character(ref key) if key == "s" => {
+                        if let Some(state) = state {
+                            state.save_session();
+                        }
+                    }
+                    Key::Character(ref key) if key == "S" => {
+                        if let Some(state) = state {
+                            state.export_macrocell();
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Key::Character(ref key) if key == "g" => {
+                        if let Some(state) = state {
+                            state.toggle_recording();
+                        }
+                    }
+                    Key::Character(ref key) if key == "b" => {
+                        if let Some(state) = state {
+                            state.cycle_boundary();
+                        }
+                    }
+                    Key::Character(ref key) if key == "m" => {
+                        if let Some(state) = state {
+                            state.cycle_variant();
+                        }
+                    }
+                    Key::Character(ref key) if key == "t" => {
+                        if let Some(state) = state {
+                            state.cycle_neighbourhood();
+                        }
+                    }
+                    Key::Character(ref key) if key == "u" => {
+                        if let Some(state) = state {
+                            state.cycle_surface();
+                        }
+                    }
+                    Key::Character(ref key) if key == "e" => {
+                        if let Some(state) = state {
+                            state.cycle_voxels();
+                        }
+                    }
+                    Key::Character(ref key) if key == "n" => {
+                        if let Some(state) = state {
+                            state.toggle_ants();
+                        }
+                    }
+                    Key::Character(ref key) if key == "y" => {
+                        if let Some(state) = state {
+                            let scheme = state.renderer.scheme().next();
+                            state.set_scheme(scheme);
+                        }
+                    }
+                    Key::Character(ref key) if key == "h" => {
+                        if let Some(state) = state {
+                            state.toggle_hashlife();
+                        }
+                    }
+                    Key::Character(ref key) if key == "o" => {
+                        if let Some(state) = state {
+                            state.load_session();
+                        }
+                    }
+                    Key::Character(ref key) if key == "c" => {
+                        if let Some(state) = state {
+                            state.read_selection(SelectionOp::Copy);
+                        }
+                    }
+                    Key::Character(ref key) if key == "x" => {
+                        if let Some(state) = state {
+                            state.read_selection(SelectionOp::Cut);
+                        }
+                    }
+                    Key::Character(ref key) if key == "v" => {
+                        if let Some(state) = state {
+                            state.paste();
+                        }
+                    }
+                    Key::Character(ref key) if key == "a" => {
+                        if let Some(state) = state
+                            && state.can_select()
+                        {
+                            let (width, height) = state.renderer.grid_size();
+                            state.set_selection(Some(Selection::from_rect(Rect {
+                                x: 0,
+                                y: 0,
+                                width,
+                                height,
+                            })));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (Some(state), WindowEvent::KeyboardInput { event, .. }) => {
+                // The camera looking at a 3D shape takes its keys first, and needs their releases
+                let pressed = event.state == ElementState::Pressed;
+                if !state.camera_key(event.physical_key, pressed) && pressed {
+                    state.handle_key(&event.logical_key, self.keyboard_modifiers.shift_key());
+                }
+            }
+            (Some(state), WindowEvent::CursorMoved { position, .. }) => {
+                if let (true, Some(previous)) = (state.panning, state.cursor) {
+                    let window = state.gpu.surface.window.clone();
+                    state.renderer.pan(&state.gpu, &window, previous, position);
+                }
+                state.cursor = Some(position);
+                if let Some(origin) = state.placement_origin() {
+                    state.renderer.move_ghost(&state.gpu, origin);
+                }
+                if let (true, Some(mut selection), Some(cell)) =
+                    (state.dragging, state.selection, state.cursor_cell())
+                {
+                    selection.drag_to(cell);
+                    state.set_selection(Some(selection));
+                }
+                if let (true, Some(cell)) = (state.brushing, state.cursor_cell()) {
+                    state.brush(cell);
+                }
+            }
+            (Some(state), WindowEvent::DroppedFile(path)) => match std::fs::read(&path) {
+                Ok(bytes) => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    state.open_file(&name, &bytes);
+                }
+                Err(e) => state.report(format!("Unable to read {}: {e}", path.display())),
+            },
+            (Some(state), WindowEvent::CursorLeft { .. }) => {
+                state.cursor = None;
+                if state.placement.is_some() {
+                    state.renderer.show_ghost(&state.gpu, None, (0, 0));
+                }
+            }
+            (
+                Some(state),
+                WindowEvent::MouseInput {
+                    state: button_state,
+                    button: MouseButton::Left,
+                    ..
+                },
+            ) => match (button_state, &state.placement, state.cursor_cell()) {
+                (ElementState::Pressed, Some(placement), Some(_)) => {
+                    if let Some(origin) = state.placement_origin() {
+                        let cells = state.painted(placement.grid());
+                        state.stamp(&cells, origin);
+                    }
+                }
+                // Falling sand is painted instead of selected
+                (ElementState::Pressed, None, Some(cell))
+                    if state.renderer.variant() == Variant::Sand =>
+                {
+                    state.brushing = true;
+                    state.brush(cell);
+                }
+                (ElementState::Released, _, _) if state.brushing => state.brushing = false,
+                (ElementState::Pressed, None, Some(cell)) if state.can_select() => {
+                    state.dragging = true;
+                    state.set_selection(Some(Selection::new(cell)));
+                }
+                (ElementState::Released, _, _) if state.dragging => {
+                    state.dragging = false;
+                    // A click without a drag clears the selection
+                    if state.selection.is_some_and(|s| s.is_click()) {
+                        state.set_selection(None);
+                    }
+                }
+                _ => {}
+            },
+            (
+                Some(state),
+                WindowEvent::MouseInput {
+                    state: button_state,
+                    button: MouseButton::Right | MouseButton::Middle,
+                    ..
+                },
+            ) => state.panning = button_state == ElementState::Pressed,
+            (Some(state), WindowEvent::MouseWheel { delta, .. }) => {
+                let steps = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 100.0,
+                };
+                if let Some(cursor) = state.cursor {
+                    let window = state.gpu.surface.window.clone();
+                    state
+                        .renderer
+                        .zoom(&state.gpu, &window, cursor, 1.25f32.powf(steps));
+                    if let Some(origin) = state.placement_origin() {
+                        state.renderer.move_ghost(&state.gpu, origin);
                     }
                 }
             }
@@ -0,0 +1,498 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::apgcode::{self, Cells, UNIDENTIFIED};
+use crate::conway::{ConwayCompute, ConwayConfig};
+use crate::gpu::HeadlessContext;
+use crate::grid::{Boundary, Grid};
+use crate::pattern::Pattern;
+use crate::rule::{Neighbourhood, Rule, Variant};
+use crate::scheme::UpdateScheme;
+
+/// Generations between stability checks. Every period dividing this is detected, which covers
+/// all oscillators common in soup ash (periods 1, 2, 3, 4, 5, 6, 8, 15, ...).
+const CHECK_INTERVAL: u32 = 120;
+
+/// Objects this close to the edge of their tile have probably been shaped by the boundary
+/// rather than the soup, usually an escaping glider that crashed into it.
+const EDGE_MARGIN: u32 = 2;
+
+/// Space left around the ash when it is cropped for CPU analysis, enough for the other phases
+/// of any oscillator common in soups.
+const ANALYSIS_PADDING: u32 = 8;
+
+/// Still lifes, oscillators and spaceships common enough in soups not to count as rare.
+const COMMON_OBJECTS: &[(&str, &str)] = &[
+    ("block", "x = 2, y = 2\n2o$2o!"),
+    ("blinker", "x = 3, y = 1\n3o!"),
+    ("beehive", "x = 4, y = 3\nb2o$o2bo$b2o!"),
+    ("glider", "x = 3, y = 3\nbo$2bo$3o!"),
+    ("loaf", "x = 4, y = 4\nb2o$o2bo$bobo$2bo!"),
+    ("boat", "x = 3, y = 3\n2o$obo$bo!"),
+    ("ship", "x = 3, y = 3\n2o$obo$b2o!"),
+    ("tub", "x = 3, y = 3\nbo$obo$bo!"),
+    ("pond", "x = 4, y = 4\nb2o$o2bo$o2bo$b2o!"),
+    ("long boat", "x = 4, y = 4\n2o$obo$bobo$2bo!"),
+    ("toad", "x = 4, y = 2\nb3o$3o!"),
+    ("beacon", "x = 4, y = 4\n2o$2o$2b2o$2b2o!"),
+    ("barge", "x = 4, y = 4\nbo$obo$bobo$2bo!"),
+    ("mango", "x = 5, y = 4\nb2o$o2bo$bo2bo$2b2o!"),
+    ("long barge", "x = 5, y = 5\nbo$obo$bobo$2bobo$3bo!"),
+    ("eater 1", "x = 4, y = 4\n2o$obo$2bo$2b2o!"),
+    ("snake", "x = 4, y = 2\n2obo$ob2o!"),
+    ("aircraft carrier", "x = 4, y = 3\n2o$o2bo$2b2o!"),
+    ("bi-block", "x = 5, y = 2\n2ob2o$2ob2o!"),
+    ("lightweight spaceship", "x = 5, y = 4\nbo2bo$o$o3bo$4o!"),
+    ("pentadecathlon", "x = 10, y = 3\n2bo4bo2b$2ob4ob2o$2bo4bo!"),
+    (
+        "pulsar",
+        "x = 13, y = 13\n2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$\
+         o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+];
+
+/// Run many random soups to stabilisation and count the objects they leave behind.
+#[derive(Debug, Clone, clap::Args)]
+pub struct CensusOptions {
+    /// Number of soups to run
+    #[arg(long, default_value_t = 1024)]
+    pub soups: u64,
+    /// Seed of the first soup; soup `i` uses seed + i (random if omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Life-like rule, e.g. B3/S23
+    #[arg(long, default_value_t = Rule::CONWAY)]
+    pub rule: Rule,
+    /// Width and height of each random soup
+    #[arg(long, default_value_t = 16)]
+    pub soup_size: u32,
+    /// Width and height of the dead-bounded world each soup evolves in
+    #[arg(long, default_value_t = 128)]
+    pub tile_size: u32,
+    /// Probability that a soup cell starts alive
+    #[arg(long, default_value_t = 0.5)]
+    pub density: f32,
+    /// Give up on soups that have not stabilised after this many generations
+    #[arg(long, default_value_t = 12000)]
+    pub max_generations: u32,
+    /// Where to write the census CSV
+    #[arg(long)]
+    pub output: PathBuf,
+    /// Where to write the seeds that produced rare objects, by default beside the census with
+    /// `-rare` added to its name
+    #[arg(long)]
+    pub rare: Option<PathBuf>,
+}
+
+/// The outcome of a single soup.
+struct Soup {
+    seed: u64,
+    /// The stable ash, or `None` if the soup was still active at the generation limit.
+    ash: Option<Grid>,
+}
+
+#[derive(Default)]
+struct Census {
+    counts: HashMap<String, u64>,
+    /// (seed, apgcode) for every rare object found.
+    rare: Vec<(u64, String)>,
+    unstable: Vec<u64>,
+    edge_objects: u64,
+}
+
+pub fn run(options: &CensusOptions) -> Result<()> {
+    // Object codes and their symmetries are defined for square cells
+    if options.rule.neighbourhood != Neighbourhood::Moore {
+        bail!(
+            "a census needs a rule on square cells, not {}",
+            options.rule
+        );
+    }
+    if options.tile_size == 0 || options.tile_size < options.soup_size {
+        bail!(
+            "a tile of {} cells cannot hold a soup of {}",
+            options.tile_size,
+            options.soup_size
+        );
+    }
+    let ctx = pollster::block_on(HeadlessContext::new())?;
+    let max_size = ctx.device.limits().max_texture_dimension_2d;
+    if options.tile_size > max_size {
+        bail!(
+            "a tile of {} cells is larger than the {max_size} the GPU allows",
+            options.tile_size
+        );
+    }
+    let names = common_names(&options.rule)?;
+    let first_seed = options.seed.unwrap_or_else(rand::random);
+    log::info!(
+        "Running {} soups of {} from seed {first_seed}",
+        options.soups,
+        options.rule
+    );
+
+    let tiles_per_row = u64::from(max_size / options.tile_size);
+    let batch_size = tiles_per_row * tiles_per_row;
+
+    let mut census = Census::default();
+    let mut done = 0;
+    while done < options.soups {
+        let count = batch_size.min(options.soups - done);
+        let seeds: Vec<u64> = (done..done + count)
+            .map(|i| first_seed.wrapping_add(i))
+            .collect();
+        for soup in run_batch(&ctx, options, &seeds)? {
+            census.record(soup, &options.rule, &names);
+        }
+        done += count;
+        log::info!("{done}/{} soups complete", options.soups);
+    }
+
+    census.write(options, &names)?;
+    log::info!(
+        "{} objects in {} types, {} rare, {} unstable soups, {} objects discarded at tile edges",
+        census.counts.values().sum::<u64>(),
+        census.counts.len(),
+        census.rare.len(),
+        census.unstable.len(),
+        census.edge_objects
+    );
+
+    Ok(())
+}
+
+/// Canonical codes of the common objects under `rule`. Objects that do not survive under
+/// other rules are simply left out.
+fn common_names(rule: &Rule) -> Result<HashMap<String, &'static str>> {
+    let mut names = HashMap::new();
+    for &(name, rle) in COMMON_OBJECTS {
+        let pattern = Pattern::from_rle(rle).with_context(|| format!("parsing {name}"))?;
+        let cells: Vec<(i32, i32)> = pattern
+            .grid
+            .live_cells()
+            .map(|(x, y)| (x as i32, y as i32))
+            .collect();
+        let code = apgcode::classify(&cells, rule);
+        if code != UNIDENTIFIED {
+            names.insert(code, name);
+        }
+    }
+    Ok(names)
+}
+
+/// Runs one texture's worth of soups, each in its own tile, until they stabilise.
+fn run_batch(ctx: &HeadlessContext, options: &CensusOptions, seeds: &[u64]) -> Result<Vec<Soup>> {
+    let tile = options.tile_size;
+    let columns = (seeds.len() as f64).sqrt().ceil() as u32;
+    let rows = (seeds.len() as u32).div_ceil(columns);
+    let tile_origin = |i: usize| (i as u32 % columns * tile, i as u32 / columns * tile);
+
+    let mut grid = Grid::new(columns * tile, rows * tile);
+    let offset = (tile - options.soup_size.min(tile)) / 2;
+    for (i, &seed) in seeds.iter().enumerate() {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let soup = Grid::random(
+            options.soup_size,
+            options.soup_size,
+            options.density,
+            &mut rng,
+        );
+        let (x, y) = tile_origin(i);
+        grid.paste(&soup, x + offset, y + offset);
+    }
+
+    let config = ConwayConfig {
+        rule: options.rule,
+        boundary: Boundary::Dead,
+        tile_size: Some((tile, tile)),
+        variant: Variant::Life,
+        scheme: UpdateScheme::Synchronous,
+    };
+    let mut conway = ConwayCompute::new(&ctx.device, &ctx.queue, &grid, config);
+
+    let mut ash: Vec<Option<Grid>> = vec![None; seeds.len()];
+    let mut previous = grid;
+    let mut generation = 0;
+    while generation < options.max_generations && ash.iter().any(Option::is_none) {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Census Encoder"),
+            });
+        for _ in 0..CHECK_INTERVAL {
+            conway.step(&mut encoder);
+        }
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        generation += CHECK_INTERVAL;
+
+        let current = pollster::block_on(conway.read(&ctx.device, &ctx.queue))?;
+        for (i, result) in ash.iter_mut().enumerate() {
+            if result.is_none() {
+                let (x, y) = tile_origin(i);
+                let tile_now = current.crop(x, y, tile, tile);
+                if tile_now == previous.crop(x, y, tile, tile) {
+                    *result = Some(tile_now);
+                }
+            }
+        }
+        previous = current;
+    }
+
+    Ok(seeds
+        .iter()
+        .zip(ash)
+        .map(|(&seed, ash)| Soup { seed, ash })
+        .collect())
+}
+
+/// Splits stable ash into separate objects.
+///
+/// Cells are grouped when they are within two cells of each other in any phase, the distance
+/// at which objects can influence one another, so each group evolves exactly as it would alone.
+/// Groups are then split further where that is consistent with their evolution. Returns the
+/// objects in their current phase and the number discarded for touching the edge.
+fn separate(tile: &Grid, rule: &Rule) -> (Vec<Cells>, u64) {
+    let Some((x, y, width, height)) = tile.bounding_box() else {
+        return (Vec::new(), 0);
+    };
+    let left = x.saturating_sub(ANALYSIS_PADDING);
+    let top = y.saturating_sub(ANALYSIS_PADDING);
+    let right = (x + width + ANALYSIS_PADDING).min(tile.width);
+    let bottom = (y + height + ANALYSIS_PADDING).min(tile.height);
+    let ash = &tile.crop(left, top, right - left, bottom - top);
+
+    let mut envelope = ash.clone();
+    let mut phase = ash.step(rule, Boundary::Dead);
+    for _ in 1..CHECK_INTERVAL {
+        if phase == *ash {
+            break;
+        }
+        for (e, &c) in envelope.cells.iter_mut().zip(&phase.cells) {
+            *e |= c;
+        }
+        phase = phase.step(rule, Boundary::Dead);
+    }
+
+    let (width, height) = (ash.width as i32, ash.height as i32);
+    let mut seen = vec![false; envelope.cells.len()];
+    let mut objects = Vec::new();
+    let mut edge_objects = 0;
+    for (start_x, start_y) in envelope.live_cells() {
+        let start = (start_y * ash.width + start_x) as usize;
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+
+        let mut cells = Vec::new();
+        let mut region = Vec::new();
+        let mut touches_edge = false;
+        let mut queue = VecDeque::from([(start_x as i32, start_y as i32)]);
+        while let Some((x, y)) = queue.pop_front() {
+            let (tile_x, tile_y) = (x as u32 + left, y as u32 + top);
+            touches_edge |= tile_x < EDGE_MARGIN
+                || tile_y < EDGE_MARGIN
+                || tile_x >= tile.width - EDGE_MARGIN
+                || tile_y >= tile.height - EDGE_MARGIN;
+            region.push((x, y));
+            if ash.get(x as u32, y as u32) {
+                cells.push((x, y));
+            }
+            for dy in -2..=2 {
+                for dx in -2..=2 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let i = (ny * width + nx) as usize;
+                    if envelope.cells[i] != 0 && !seen[i] {
+                        seen[i] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        if touches_edge {
+            edge_objects += 1;
+        } else {
+            objects.extend(split_pseudo_object(cells, &region, rule));
+        }
+    }
+
+    (objects, edge_objects)
+}
+
+/// Splits a group into its connected parts when every part evolves exactly as it does within
+/// the group, so a blinker beside a block counts as two objects while an aircraft carrier,
+/// whose halves would die alone, stays whole.
+fn split_pseudo_object(cells: Vec<(i32, i32)>, region: &[(i32, i32)], rule: &Rule) -> Vec<Cells> {
+    let mut component_of: HashMap<(i32, i32), usize> = HashMap::new();
+    let in_region: HashSet<(i32, i32)> = region.iter().copied().collect();
+    let mut components = 0;
+    for &start in region {
+        if component_of.contains_key(&start) {
+            continue;
+        }
+        component_of.insert(start, components);
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let neighbour = (x + dx, y + dy);
+                    if in_region.contains(&neighbour) && !component_of.contains_key(&neighbour) {
+                        component_of.insert(neighbour, components);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+        components += 1;
+    }
+    if components == 1 {
+        return vec![cells];
+    }
+
+    let mut parts = vec![Vec::new(); components];
+    for &cell in &cells {
+        parts[component_of[&cell]].push(cell);
+    }
+    if parts.iter().any(Vec::is_empty) {
+        return vec![cells];
+    }
+
+    let mut whole = cells.clone();
+    let mut evolved = parts.clone();
+    for _ in 0..CHECK_INTERVAL {
+        whole = apgcode::step(&whole, rule);
+        for part in &mut evolved {
+            *part = apgcode::step(part, rule);
+        }
+        let mut union = evolved.concat();
+        union.sort_unstable();
+        if union != whole {
+            return vec![cells];
+        }
+    }
+
+    parts
+}
+
+impl Census {
+    fn record(&mut self, soup: Soup, rule: &Rule, names: &HashMap<String, &str>) {
+        let Some(ash) = soup.ash else {
+            self.unstable.push(soup.seed);
+            return;
+        };
+
+        let (objects, edge_objects) = separate(&ash, rule);
+        self.edge_objects += edge_objects;
+        for cells in objects {
+            let code = apgcode::classify(&cells, rule);
+            if !names.contains_key(&code) {
+                self.rare.push((soup.seed, code.clone()));
+            }
+            *self.counts.entry(code).or_default() += 1;
+        }
+    }
+
+    fn write(&self, options: &CensusOptions, names: &HashMap<String, &str>) -> Result<()> {
+        let mut counts: Vec<_> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut out = BufWriter::new(
+            File::create(&options.output)
+                .with_context(|| format!("creating {}", options.output.display()))?,
+        );
+        writeln!(out, "apgcode,name,count")?;
+        for (code, count) in counts {
+            writeln!(out, "{code},{},{count}", names.get(code).unwrap_or(&""))?;
+        }
+        out.flush()?;
+
+        let rare = options.rare.clone().unwrap_or_else(|| {
+            let stem = options
+                .output
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy();
+            options.output.with_file_name(format!("{stem}-rare.csv"))
+        });
+        let mut out = BufWriter::new(
+            File::create(&rare).with_context(|| format!("creating {}", rare.display()))?,
+        );
+        writeln!(out, "seed,apgcode")?;
+        for (seed, code) in &self.rare {
+            writeln!(out, "{seed},{code}")?;
+        }
+        for seed in &self.unstable {
+            writeln!(out, "{seed},unstable")?;
+        }
+        out.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 32 × 32 tile with the cells drawn as rows of `o` for alive, top-left at `(x, y)`.
+    fn tile(objects: &[((u32, u32), &[&str])]) -> Grid {
+        let mut tile = Grid::new(32, 32);
+        for &((x, y), rows) in objects {
+            for (j, row) in rows.iter().enumerate() {
+                for (i, c) in row.chars().enumerate() {
+                    tile.set(x + i as u32, y + j as u32, c == 'o');
+                }
+            }
+        }
+        tile
+    }
+
+    fn codes(tile: &Grid) -> (Vec<String>, u64) {
+        let (objects, edge_objects) = separate(tile, &Rule::CONWAY);
+        let mut codes: Vec<_> = objects
+            .iter()
+            .map(|cells| apgcode::classify(cells, &Rule::CONWAY))
+            .collect();
+        codes.sort();
+        (codes, edge_objects)
+    }
+
+    #[test]
+    fn objects_apart_are_separate() {
+        let tile = tile(&[((8, 8), &["oo", "oo"]), ((20, 20), &["ooo"])]);
+        assert_eq!(codes(&tile), (vec!["xp2_7".into(), "xs4_33".into()], 0));
+    }
+
+    #[test]
+    fn objects_that_evolve_alone_are_split() {
+        // The blinker comes within two cells of the block's corner without touching it
+        let tile = tile(&[((8, 8), &["oo", "oo"]), ((11, 6), &["ooo"])]);
+        assert_eq!(codes(&tile), (vec!["xp2_7".into(), "xs4_33".into()], 0));
+    }
+
+    #[test]
+    fn objects_whose_parts_need_each_other_stay_whole() {
+        // An aircraft carrier, whose halves would die alone
+        let tile = tile(&[((8, 8), &["oo..", "o..o", "..oo"])]);
+        let (codes, edge_objects) = codes(&tile);
+        assert_eq!(codes.len(), 1, "{codes:?}");
+        assert!(codes[0].starts_with("xs6_"), "{codes:?}");
+        assert_eq!(edge_objects, 0);
+    }
+
+    #[test]
+    fn objects_at_the_edge_are_discarded() {
+        let tile = tile(&[((0, 0), &["oo", "oo"]), ((16, 16), &["oo", "oo"])]);
+        assert_eq!(codes(&tile), (vec!["xs4_33".into()], 1));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::chunks::{CHUNK_SIZE, ChunkCoord, ChunkedWorld};
+use crate::overlay::Overlay;
+use crate::rule::Neighbourhood;
+use crate::viewport::Viewport;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ChunkView {
+    centre: [f32; 2],
+    zoom: f32,
+    slots_per_row: u32,
+    size: [f32; 2],
+    neighbourhood: u32,
+    _padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ChunkInstance {
+    chunk: [i32; 2],
+    slot: u32,
+}
+
+/// Draws the visible chunks of a `ChunkedWorld`.
+pub struct ChunkDisplay {
+    background_pipeline: wgpu::RenderPipeline,
+    chunk_pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    view_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+}
+
+impl ChunkDisplay {
+    pub fn new(device: &wgpu::Device, overlay: &Overlay, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Chunk Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("chunk_display.wgsl").into()),
+        });
+
+        // Atlas texture and the view
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Chunk Display Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chunk Display Pipeline Layout"),
+            bind_group_layouts: &[&layout, overlay.layout()],
+            push_constant_ranges: &[],
+        });
+
+        let instance_attributes = wgpu::vertex_attr_array![0 => Sint32x2, 1 => Uint32];
+        let instance_layout = wgpu::VertexBufferLayout {
+            array_stride: size_of::<ChunkInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &instance_attributes,
+        };
+
+        let pipeline = |label, vertex, fragment, buffers: &[wgpu::VertexBufferLayout]| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some(vertex),
+                    buffers,
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(fragment),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let background_pipeline = pipeline(
+            "Chunk Background Pipeline",
+            "vs_background",
+            "fs_background",
+            &[],
+        );
+        let chunk_pipeline = pipeline(
+            "Chunk Display Pipeline",
+            "vs_main",
+            "fs_main",
+            &[instance_layout],
+        );
+
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk View Buffer"),
+            size: size_of::<ChunkView>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = Self::create_instance_buffer(device, 64);
+
+        Self {
+            background_pipeline,
+            chunk_pipeline,
+            layout,
+            view_buffer,
+            instance_buffer,
+        }
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Instance Buffer"),
+            size: capacity * size_of::<ChunkInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Records a pass drawing the chunks inside the view. At zoom 1 the window shows `size`
+    /// cells.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        world: &ChunkedWorld,
+        overlay: &Overlay,
+        viewport: &Viewport,
+        size: (u32, u32),
+    ) {
+        // Cull chunks outside the view on the CPU, allowing for the cell of margin around each
+        // quad and the lean of hexagonal rows
+        let neighbourhood = world.rule().neighbourhood;
+        let chunk = CHUNK_SIZE as f32;
+        let half_width = size.0 as f32 / viewport.zoom / 2.0;
+        let half_height = size.1 as f32 / viewport.zoom / 2.0;
+        let (left, right) = (
+            viewport.centre.0 - half_width,
+            viewport.centre.0 + half_width,
+        );
+        let (top, bottom) = (
+            viewport.centre.1 - half_height,
+            viewport.centre.1 + half_height,
+        );
+        let visible = |(x, y): ChunkCoord| {
+            let (x0, y0) = (x as f32 * chunk - 1.0, y as f32 * chunk - 1.0);
+            let (x1, y1) = (x0 + chunk + 2.0, y0 + chunk + 2.0);
+            let (x0, x1) = match neighbourhood {
+                Neighbourhood::Hexagonal => (x0 - y1 / 2.0, x1 - y0 / 2.0),
+                _ => (x0, x1),
+            };
+            x1 >= left && x0 <= right && y1 >= top && y0 <= bottom
+        };
+        let instances: Vec<ChunkInstance> = world
+            .chunks()
+            .filter(|&(coord, _)| visible(coord))
+            .map(|((x, y), slot)| ChunkInstance {
+                chunk: [x, y],
+                slot,
+            })
+            .collect();
+
+        let needed = (instances.len() * size_of::<ChunkInstance>()) as u64;
+        if needed > self.instance_buffer.size() {
+            self.instance_buffer =
+                Self::create_instance_buffer(device, instances.len().next_power_of_two() as u64);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+
+        let view = ChunkView {
+            centre: [viewport.centre.0, viewport.centre.1],
+            zoom: viewport.zoom,
+            slots_per_row: world.slots_per_row(),
+            size: [size.0 as f32, size.1 as f32],
+            neighbourhood: neighbourhood.index(),
+            _padding: 0,
+        };
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&view));
+
+        // The atlas is replaced when it grows, so the bind group is made per frame
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chunk Display Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(world.current_texture_view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.view_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Chunk Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_bind_group(1, overlay.bind_group(), &[]);
+
+        render_pass.set_pipeline(&self.background_pipeline);
+        render_pass.draw(0..3, 0..1);
+
+        if !instances.is_empty() {
+            render_pass.set_pipeline(&self.chunk_pipeline);
+            render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+            render_pass.draw(0..6, 0..instances.len() as u32);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// Draws the allocated chunks of an unbounded world that fall inside the view, one instanced
+// quad per chunk, over a background of dead cells that only shows the ghost.
+
+const CHUNK_SIZE: f32 = 64.0;
+
+// Part of the world shown in the window
+struct View {
+    // World position, in cells, at the centre of the window
+    centre: vec2<f32>,
+    // 1 = `size` cells fill the window
+    zoom: f32,
+    // Chunks per row of the atlas
+    slots_per_row: u32,
+    size: vec2<f32>,
+    // Shape of the cells: 0 = square, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+}
+
+@group(0) @binding(0) var atlas: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> view: View;
+
+// Editing aids drawn over the world, as in display.wgsl
+struct Overlay {
+    ghost_origin: vec2<i32>,
+    ghost_mode: u32,
+    selection_visible: u32,
+    selection_min: vec2<u32>,
+    selection_max: vec2<u32>,
+}
+
+@group(1) @binding(0) var ghost_cells: texture_2d<f32>;
+@group(1) @binding(1) var<uniform> overlay: Overlay;
+
+struct ChunkInstance {
+    @location(0) chunk: vec2<i32>,
+    @location(1) slot: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position in the window, in cells
+    @location(0) world: vec2<f32>,
+    @location(1) @interpolate(flat) chunk: vec2<i32>,
+    @location(2) @interpolate(flat) slot: u32,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: ChunkInstance) -> VertexOutput {
+    // Two triangles covering the chunk
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    // Hexagons and triangles poke out of the square of cells, so the quad is a cell larger all
+    // round, and leans with the rows of hexagons
+    let corner = corners[vertex_index];
+    var world = (vec2<f32>(instance.chunk) + corner) * CHUNK_SIZE + (corner * 2.0 - 1.0);
+    if (view.neighbourhood == 1u) {
+        world.x -= world.y / 2.0;
+    }
+    let ndc = (world - view.centre) * view.zoom / view.size * 2.0;
+
+    var out: VertexOutput;
+    // World row 0 is above row 1, matching window coordinates
+    out.clip_position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.world = world;
+    out.chunk = instance.chunk;
+    out.slot = instance.slot;
+    return out;
+}
+
+fn with_ghost(color: vec3<f32>, cell: vec2<i32>) -> vec3<f32> {
+    if (overlay.ghost_mode == 0u) {
+        return color;
+    }
+    let ghost = cell - overlay.ghost_origin;
+    if (any(ghost < vec2<i32>(0)) || any(ghost >= vec2<i32>(textureDimensions(ghost_cells)))) {
+        return color;
+    }
+    if (textureLoad(ghost_cells, ghost, 0).r > 0.5) {
+        return mix(color, vec3<f32>(0.2, 0.9, 0.4), 0.7);  // Green = ghost cell
+    }
+    return mix(color, vec3<f32>(0.2, 0.3, 0.8), 0.3);  // Blue = ghost footprint
+}
+
+// Cell colours of the multi-colour variants: white, red, blue and yellow
+fn palette(cell: f32) -> vec3<f32> {
+    switch (u32(round(cell))) {
+        case 0u: { return vec3<f32>(0.0, 0.0, 0.0); }
+        case 2u: { return vec3<f32>(1.0, 0.35, 0.3); }
+        case 3u: { return vec3<f32>(0.35, 0.55, 1.0); }
+        case 4u: { return vec3<f32>(1.0, 0.85, 0.25); }
+        default: { return vec3<f32>(1.0, 1.0, 1.0); }
+    }
+}
+
+// The cell drawn at a position given in cells, as in display.wgsl
+fn cell_at(pos: vec2<f32>) -> vec2<i32> {
+    let row = floor(pos.y);
+    switch (view.neighbourhood) {
+        // Hexagonal: the nearest cell centre, each row shifted half a cell left of the one above
+        case 1u: {
+            var nearest = vec2<i32>(0);
+            var best = 4.0;
+            for (var r = row - 1.0; r <= row + 1.0; r += 1.0) {
+                let shift = r / 2.0;
+                let column = floor(pos.x + shift);
+                let d = pos - vec2<f32>(column + 0.5 - shift, r + 0.5);
+                if (dot(d, d) < best) {
+                    best = dot(d, d);
+                    nearest = vec2<i32>(i32(column), i32(r));
+                }
+            }
+            return nearest;
+        }
+        // Triangular: between two triangle centres, split by the edge they share
+        case 2u: {
+            let u = pos.x - 0.5;
+            let column = floor(u);
+            let across = u - column;
+            let down = pos.y - row;
+            let cell = vec2<i32>(i32(column), i32(row));
+            let up = ((cell.x + cell.y) % 2 + 2) % 2 == 0;
+            let left = select(across < 1.0 - down, across < down, up);
+            return cell + vec2<i32>(select(1, 0, left), 0);
+        }
+        default: {
+            return vec2<i32>(floor(pos));
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = i32(CHUNK_SIZE);
+    // Cells of the neighbouring chunks are left to their own quads
+    let world_cell = cell_at(in.world);
+    let local = world_cell - in.chunk * size;
+    if (any(local < vec2<i32>(0)) || any(local >= vec2<i32>(size))) {
+        discard;
+    }
+    let slot = vec2<i32>(i32(in.slot % view.slots_per_row), i32(in.slot / view.slots_per_row));
+    let cell = textureLoad(atlas, slot * size + local, 0).r;
+
+    // Live cells in their colour, dead cells = black
+    let color = palette(cell);
+    return vec4<f32>(with_ghost(color, world_cell), 1.0);
+}
+
+struct BackgroundOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_background(@builtin(vertex_index) vertex_index: u32) -> BackgroundOutput {
+    // Create fullscreen triangle (covers whole screen)
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: BackgroundOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+@fragment
+fn fs_background(in: BackgroundOutput) -> @location(0) vec4<f32> {
+    let world = view.centre + (in.uv - 0.5) * view.size / view.zoom;
+    return vec4<f32>(with_ghost(vec3<f32>(0.0), cell_at(world)), 1.0);
+}
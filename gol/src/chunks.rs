@@ -0,0 +1,790 @@
+//! An effectively unbounded world made of fixed-size chunks allocated on demand.
+//!
+//! Chunks live in slots of an atlas texture that grows as needed. Each step reads the cells just
+//! beyond a chunk's edge from its neighbours' slots, so chunks behave as one continuous plane.
+//! The compute shader also flags chunks with live cells within `MARGIN` cells of an edge. The
+//! flags are read back as often as it takes the rule's neighbourhood to spread a change across
+//! the margin, every 8 generations for square and hexagonal cells and every 4 for triangles,
+//! which reach two cells sideways. The neighbours across flagged edges are then allocated and
+//! chunks that have gone empty are freed, so patterns never notice the chunk boundaries.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use futures_channel::oneshot;
+use wgpu::util::DeviceExt;
+
+use crate::grid::Grid;
+use crate::rule::Rule;
+
+/// Width and height of a chunk in cells.
+pub const CHUNK_SIZE: u32 = 64;
+/// Live cells this close to a chunk edge allocate the neighbour across it; matches `chunks.wgsl`.
+const MARGIN: u64 = 8;
+const WORKGROUP_SIZE: u32 = 16;
+const NONE: u32 = u32::MAX;
+/// Chunks per row of the first atlas.
+const INITIAL_SLOTS_PER_ROW: u32 = 16;
+
+/// The position of a chunk, in chunks from the one whose top-left cell is the origin.
+pub type ChunkCoord = (i32, i32);
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    birth: u32,
+    survive: u32,
+    slots_per_row: u32,
+    neighbourhood: u32,
+}
+
+impl Params {
+    fn new(rule: &Rule, slots_per_row: u32) -> Self {
+        Self {
+            birth: rule.birth.into(),
+            survive: rule.survive.into(),
+            slots_per_row,
+            neighbourhood: rule.neighbourhood.index(),
+        }
+    }
+}
+
+/// Generations between reads of the activity flags: the fewest it takes a change to cross the
+/// margin.
+fn check_interval(rule: &Rule) -> u64 {
+    MARGIN / u64::from(rule.neighbourhood.reach())
+}
+
+/// The chunk at a cell position, and the cell's position within it.
+pub fn chunk_of(x: i32, y: i32) -> (ChunkCoord, (u32, u32)) {
+    let size = CHUNK_SIZE as i32;
+    (
+        (x.div_euclid(size), y.div_euclid(size)),
+        (x.rem_euclid(size) as u32, y.rem_euclid(size) as u32),
+    )
+}
+
+/// The GPU storage for a fixed number of chunk slots.
+struct Atlas {
+    slots_per_row: u32,
+    textures: [wgpu::Texture; 2],
+    views: [wgpu::TextureView; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+    active_buffer: wgpu::Buffer,
+    neighbours_buffer: wgpu::Buffer,
+    activity_buffer: wgpu::Buffer,
+}
+
+impl Atlas {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        params_buffer: &wgpu::Buffer,
+        slots_per_row: u32,
+    ) -> Self {
+        let capacity = u64::from(slots_per_row * slots_per_row);
+        let size = slots_per_row * CHUNK_SIZE;
+
+        let texture_descriptor = wgpu::TextureDescriptor {
+            label: Some("Chunk Atlas Texture"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        };
+        let textures = [
+            device.create_texture(&texture_descriptor),
+            device.create_texture(&texture_descriptor),
+        ];
+        let views = textures
+            .each_ref()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+
+        let storage = |label, size: u64, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let active_buffer = storage(
+            "Chunk Active Buffer",
+            capacity * 4,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let neighbours_buffer = storage(
+            "Chunk Neighbours Buffer",
+            capacity * 9 * 4,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let activity_buffer = storage(
+            "Chunk Activity Buffer",
+            capacity * 4,
+            wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let bind_group = |label, input: &wgpu::TextureView, output: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(output),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: active_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: neighbours_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: activity_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let bind_groups = [
+            bind_group("Chunk Bind Group A", &views[0], &views[1]),
+            bind_group("Chunk Bind Group B", &views[1], &views[0]),
+        ];
+
+        Self {
+            slots_per_row,
+            textures,
+            views,
+            bind_groups,
+            active_buffer,
+            neighbours_buffer,
+            activity_buffer,
+        }
+    }
+
+    fn capacity(&self) -> u32 {
+        self.slots_per_row * self.slots_per_row
+    }
+
+    fn slot_origin(&self, slot: u32) -> wgpu::Origin3d {
+        wgpu::Origin3d {
+            x: slot % self.slots_per_row * CHUNK_SIZE,
+            y: slot / self.slots_per_row * CHUNK_SIZE,
+            z: 0,
+        }
+    }
+}
+
+/// Where the activity flags of the latest generation are on their way back to the CPU.
+enum Check {
+    Idle,
+    /// The flags were copied to the readback buffer by the last step.
+    Copied,
+    Mapping(oneshot::Receiver<Result<(), wgpu::BufferAsyncError>>),
+}
+
+pub struct ChunkedWorld {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    atlas: Atlas,
+    current_texture: usize,
+    rule: Rule,
+    generation: u64,
+    chunks: HashMap<ChunkCoord, u32>,
+    free: Vec<u32>,
+    /// Number of entries of the atlas's active buffer in use.
+    active_count: u32,
+    /// Outlives the atlas, so a check can finish while the atlas grows.
+    activity_readback: wgpu::Buffer,
+    check: Check,
+    next_check: u64,
+    /// Chunks allocated since the flags being checked were copied, which have none of their own.
+    fresh: HashSet<ChunkCoord>,
+    /// Set once the atlas has reached the largest texture the device supports.
+    full: bool,
+}
+
+impl ChunkedWorld {
+    /// Creates a world holding `grid` with its top-left corner at `origin`.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rule: Rule,
+        grid: &Grid,
+        origin: (i32, i32),
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Chunk Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("chunks.wgsl").into()),
+        });
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Chunk Bind Group Layout"),
+            entries: &[
+                // Input atlas (read)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                // Output atlas (write)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Rule and atlas layout
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(3, true),
+                storage_entry(4, true),
+                storage_entry(5, false),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chunk Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Chunk Compute Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk Params Buffer"),
+            contents: bytemuck::bytes_of(&Params::new(&rule, INITIAL_SLOTS_PER_ROW)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let atlas = Atlas::new(device, &layout, &params_buffer, INITIAL_SLOTS_PER_ROW);
+        let free = (0..atlas.capacity()).rev().collect();
+
+        let mut world = Self {
+            pipeline,
+            layout,
+            params_buffer,
+            atlas,
+            current_texture: 0,
+            rule,
+            generation: 0,
+            chunks: HashMap::new(),
+            free,
+            active_count: 0,
+            activity_readback: create_readback(device, u64::from(INITIAL_SLOTS_PER_ROW.pow(2)) * 4),
+            check: Check::Idle,
+            next_check: check_interval(&rule),
+            fresh: HashSet::new(),
+            full: false,
+        };
+        world.stamp(device, queue, grid, origin);
+        world
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Number of allocated chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Every allocated chunk and the atlas slot holding it.
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkCoord, u32)> + '_ {
+        self.chunks.iter().map(|(&coord, &slot)| (coord, slot))
+    }
+
+    pub fn slots_per_row(&self) -> u32 {
+        self.atlas.slots_per_row
+    }
+
+    /// The atlas texture holding the latest generation.
+    pub fn current_texture_view(&self) -> &wgpu::TextureView {
+        &self.atlas.views[self.current_texture]
+    }
+
+    /// Advances every allocated chunk one generation. Returns `false` without stepping while
+    /// the activity flags are still on their way back to the CPU.
+    pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder) -> bool {
+        if !matches!(self.check, Check::Idle) {
+            return false;
+        }
+
+        if self.active_count > 0 {
+            encoder.clear_buffer(&self.atlas.activity_buffer, 0, None);
+
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Chunk Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.atlas.bind_groups[self.current_texture], &[]);
+            let workgroups = CHUNK_SIZE / WORKGROUP_SIZE;
+            compute_pass.dispatch_workgroups(workgroups, workgroups, self.active_count);
+            drop(compute_pass);
+
+            self.current_texture = 1 - self.current_texture;
+        }
+        self.generation += 1;
+
+        if self.generation >= self.next_check && self.active_count > 0 {
+            encoder.copy_buffer_to_buffer(
+                &self.atlas.activity_buffer,
+                0,
+                &self.activity_readback,
+                0,
+                self.atlas.activity_buffer.size(),
+            );
+            self.check = Check::Copied;
+            self.next_check = self.generation + check_interval(&self.rule);
+            self.fresh.clear();
+        }
+        true
+    }
+
+    /// Allocates and frees chunks once the activity flags copied by `step` have been submitted
+    /// and read back. On native this waits for the GPU; on the web the flags arrive on a later
+    /// frame, and stepping pauses until then.
+    pub fn maintain(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Check::Copied = self.check {
+            let (sender, receiver) = oneshot::channel();
+            self.activity_readback
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+            self.check = Check::Mapping(receiver);
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Err(e) = device.poll(wgpu::PollType::Wait) {
+                log::error!("Unable to wait for chunk activity: {e}");
+            }
+        }
+
+        let Check::Mapping(receiver) = &mut self.check else {
+            return;
+        };
+        let flags: Vec<u32> = match receiver.try_recv() {
+            Ok(None) => return,
+            Ok(Some(Ok(()))) => {
+                let data = self.activity_readback.slice(..).get_mapped_range();
+                bytemuck::cast_slice(&data).to_vec()
+            }
+            Ok(Some(Err(e))) => {
+                log::error!("Unable to read chunk activity: {e}");
+                self.check = Check::Idle;
+                return;
+            }
+            Err(_) => {
+                self.check = Check::Idle;
+                return;
+            }
+        };
+        self.activity_readback.unmap();
+        self.check = Check::Idle;
+        self.fit_readback(device);
+
+        // Chunks allocated while the flags were on their way have none yet, so they are kept
+        let mut wanted = std::mem::take(&mut self.fresh);
+        for (&(x, y), &slot) in &self.chunks {
+            let flags = flags.get(slot as usize).copied().unwrap_or_default();
+            for direction in 0..9 {
+                if flags & (1 << direction) != 0 {
+                    wanted.insert((x + direction % 3 - 1, y + direction / 3 - 1));
+                }
+            }
+        }
+
+        let free = &mut self.free;
+        self.chunks.retain(|coord, slot| {
+            let keep = wanted.contains(coord);
+            if !keep {
+                free.push(*slot);
+            }
+            keep
+        });
+        for coord in wanted {
+            self.allocate(device, queue, coord);
+        }
+        self.write_tables(queue);
+    }
+
+    /// Writes `grid` into the world with its top-left corner at `origin`, overwriting the cells
+    /// beneath it.
+    pub fn stamp(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        grid: &Grid,
+        origin: (i32, i32),
+    ) {
+        let (x, y) = origin;
+        if grid.width == 0 || grid.height == 0 {
+            return;
+        }
+        let ((left, top), _) = chunk_of(x, y);
+        let ((right, bottom), _) = chunk_of(x + grid.width as i32 - 1, y + grid.height as i32 - 1);
+
+        // Allocate a ring of neighbours too, in case cells were written next to an edge
+        for cy in top - 1..=bottom + 1 {
+            for cx in left - 1..=right + 1 {
+                self.allocate(device, queue, (cx, cy));
+            }
+        }
+
+        let size = CHUNK_SIZE as i32;
+        for cy in top..=bottom {
+            for cx in left..=right {
+                let Some(&slot) = self.chunks.get(&(cx, cy)) else {
+                    continue;
+                };
+                // The part of the grid inside this chunk, in grid coordinates
+                let part_left = (cx * size - x).max(0);
+                let part_top = (cy * size - y).max(0);
+                let part_right = ((cx + 1) * size - x).min(grid.width as i32);
+                let part_bottom = ((cy + 1) * size - y).min(grid.height as i32);
+                let part = grid.crop(
+                    part_left as u32,
+                    part_top as u32,
+                    (part_right - part_left) as u32,
+                    (part_bottom - part_top) as u32,
+                );
+                let (_, (local_x, local_y)) = chunk_of(x + part_left, y + part_top);
+                let origin = self.atlas.slot_origin(slot);
+                self.write_cells(
+                    queue,
+                    &part,
+                    wgpu::Origin3d {
+                        x: origin.x + local_x,
+                        y: origin.y + local_y,
+                        z: 0,
+                    },
+                );
+            }
+        }
+        self.write_tables(queue);
+    }
+
+    /// Frees every chunk.
+    pub fn clear(&mut self, queue: &wgpu::Queue) {
+        self.free.extend(self.chunks.drain().map(|(_, slot)| slot));
+        self.write_tables(queue);
+    }
+
+    /// Copies every live cell back to the CPU, returning the smallest grid holding them and
+    /// the world position of its top-left corner.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<(Grid, (i32, i32))>> + use<> {
+        let chunks: Vec<(ChunkCoord, u32)> = self.chunks().collect();
+        let chunk_bytes = u64::from(CHUNK_SIZE * CHUNK_SIZE * 4);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk Readback Buffer"),
+            size: (chunk_bytes * chunks.len() as u64).max(4),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chunk Readback Encoder"),
+        });
+        for (i, &(_, slot)) in chunks.iter().enumerate() {
+            encoder.copy_texture_to_buffer(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.atlas.textures[self.current_texture],
+                    mip_level: 0,
+                    origin: self.atlas.slot_origin(slot),
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::TexelCopyBufferInfo {
+                    buffer: &buffer,
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset: chunk_bytes * i as u64,
+                        bytes_per_row: Some(CHUNK_SIZE * 4),
+                        rows_per_image: Some(CHUNK_SIZE),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: CHUNK_SIZE,
+                    height: CHUNK_SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        let device = device.clone();
+
+        async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            device.poll(wgpu::PollType::Wait)?;
+            #[cfg(target_arch = "wasm32")]
+            let _ = device;
+            receiver.await??;
+
+            let Some(left) = chunks.iter().map(|((x, _), _)| *x).min() else {
+                return Ok((Grid::new(0, 0), (0, 0)));
+            };
+            let top = chunks
+                .iter()
+                .map(|((_, y), _)| *y)
+                .min()
+                .unwrap_or_default();
+            let right = chunks
+                .iter()
+                .map(|((x, _), _)| *x)
+                .max()
+                .unwrap_or_default();
+            let bottom = chunks
+                .iter()
+                .map(|((_, y), _)| *y)
+                .max()
+                .unwrap_or_default();
+            let mut grid = Grid::new(
+                (right - left + 1) as u32 * CHUNK_SIZE,
+                (bottom - top + 1) as u32 * CHUNK_SIZE,
+            );
+            {
+                let data = buffer.slice(..).get_mapped_range();
+                let texels: &[f32] = bytemuck::cast_slice(&data);
+                for (i, &((cx, cy), _)) in chunks.iter().enumerate() {
+                    let chunk = &texels[i * (CHUNK_SIZE * CHUNK_SIZE) as usize..];
+                    let x0 = (cx - left) as u32 * CHUNK_SIZE;
+                    let y0 = (cy - top) as u32 * CHUNK_SIZE;
+                    for (j, &value) in chunk[..(CHUNK_SIZE * CHUNK_SIZE) as usize]
+                        .iter()
+                        .enumerate()
+                    {
+                        if value > 0.5 {
+                            let j = j as u32;
+                            let colour = value.round() as u8;
+                            grid.set_colour(x0 + j % CHUNK_SIZE, y0 + j / CHUNK_SIZE, colour);
+                        }
+                    }
+                }
+            }
+            buffer.unmap();
+
+            let origin = (left * CHUNK_SIZE as i32, top * CHUNK_SIZE as i32);
+            Ok(match grid.bounding_box() {
+                Some((x, y, width, height)) => (
+                    grid.crop(x, y, width, height),
+                    (origin.0 + x as i32, origin.1 + y as i32),
+                ),
+                None => (Grid::new(0, 0), (0, 0)),
+            })
+        }
+    }
+
+    /// Gives `coord` an empty slot, growing the atlas if it is full. Does nothing if the chunk
+    /// is already allocated.
+    fn allocate(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, coord: ChunkCoord) {
+        if self.chunks.contains_key(&coord) {
+            return;
+        }
+        if self.free.is_empty() && !self.grow(device, queue) {
+            return;
+        }
+        let Some(slot) = self.free.pop() else {
+            return;
+        };
+        let empty = Grid::new(CHUNK_SIZE, CHUNK_SIZE);
+        self.write_cells(queue, &empty, self.atlas.slot_origin(slot));
+        self.chunks.insert(coord, slot);
+        if !matches!(self.check, Check::Idle) {
+            self.fresh.insert(coord);
+        }
+    }
+
+    /// Doubles the atlas in each direction, keeping every slot's contents and index.
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let max_slots_per_row = device.limits().max_texture_dimension_2d / CHUNK_SIZE;
+        let slots_per_row = (self.atlas.slots_per_row * 2).min(max_slots_per_row);
+        if slots_per_row <= self.atlas.slots_per_row {
+            if !self.full {
+                log::warn!(
+                    "The chunk atlas is full at {} chunks; patterns crossing into new chunks \
+                     will be lost",
+                    self.atlas.capacity()
+                );
+                self.full = true;
+            }
+            return false;
+        }
+
+        let atlas = Atlas::new(device, &self.layout, &self.params_buffer, slots_per_row);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chunk Atlas Grow Encoder"),
+        });
+        for &slot in self.chunks.values() {
+            encoder.copy_texture_to_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.atlas.textures[self.current_texture],
+                    mip_level: 0,
+                    origin: self.atlas.slot_origin(slot),
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::TexelCopyTextureInfo {
+                    texture: &atlas.textures[self.current_texture],
+                    mip_level: 0,
+                    origin: atlas.slot_origin(slot),
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: CHUNK_SIZE,
+                    height: CHUNK_SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.free = (self.atlas.capacity()..atlas.capacity()).rev().collect();
+        log::info!("Grew the chunk atlas to {} chunks", atlas.capacity());
+        self.atlas = atlas;
+        if let Check::Idle = self.check {
+            self.fit_readback(device);
+        }
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Params::new(&self.rule, slots_per_row)),
+        );
+        true
+    }
+
+    /// Makes the activity readback buffer large enough for the atlas; only while it is unmapped.
+    fn fit_readback(&mut self, device: &wgpu::Device) {
+        let size = self.atlas.activity_buffer.size();
+        if self.activity_readback.size() < size {
+            self.activity_readback = create_readback(device, size);
+        }
+    }
+
+    /// Uploads the list of allocated slots and their neighbour table.
+    fn write_tables(&mut self, queue: &wgpu::Queue) {
+        let mut active = Vec::with_capacity(self.chunks.len());
+        let mut neighbours = vec![NONE; self.atlas.capacity() as usize * 9];
+        for (&(x, y), &slot) in &self.chunks {
+            active.push(slot);
+            for (i, entry) in neighbours[slot as usize * 9..][..9].iter_mut().enumerate() {
+                let neighbour = (x + i as i32 % 3 - 1, y + i as i32 / 3 - 1);
+                *entry = self.chunks.get(&neighbour).copied().unwrap_or(NONE);
+            }
+        }
+        if !active.is_empty() {
+            queue.write_buffer(&self.atlas.active_buffer, 0, bytemuck::cast_slice(&active));
+        }
+        queue.write_buffer(
+            &self.atlas.neighbours_buffer,
+            0,
+            bytemuck::cast_slice(&neighbours),
+        );
+        self.active_count = active.len() as u32;
+    }
+
+    fn write_cells(&self, queue: &wgpu::Queue, grid: &Grid, origin: wgpu::Origin3d) {
+        let data: Vec<f32> = grid.cells.iter().map(|&c| f32::from(c)).collect();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.atlas.textures[self.current_texture],
+                mip_level: 0,
+                origin,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&data),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(grid.width * 4),
+                rows_per_image: Some(grid.height),
+            },
+            wgpu::Extent3d {
+                width: grid.width,
+                height: grid.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+fn create_readback(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Chunk Activity Readback Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
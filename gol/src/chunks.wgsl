@@ -0,0 +1,168 @@
+// One generation of an unbounded world stored as fixed-size chunks in an atlas texture.
+// Each workgroup layer (z) steps one allocated chunk; cells beyond a chunk's edge are read from
+// the neighbouring chunk's slot, or are dead if that chunk is not allocated.
+
+const CHUNK_SIZE: i32 = 64;
+// Chunks with live cells closer than this to an edge ask for the neighbour across it; the flags
+// are read back before anything could have crossed the rest of the margin, at up to two cells
+// a generation for triangles
+const MARGIN: i32 = 8;
+const NONE: u32 = 0xffffffffu;
+// Bit 4 of the activity flags (the centre of the 3x3 neighbourhood) = the chunk has live cells
+const ALIVE: u32 = 16u;
+
+struct Params {
+    // Bit n set = born/survives with n neighbours
+    birth: u32,
+    survive: u32,
+    // Chunks per row of the atlas
+    slots_per_row: u32,
+    // 0 = Moore, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+}
+
+@group(0) @binding(0) var current_state: texture_2d<f32>;
+@group(0) @binding(1) var next_state: texture_storage_2d<r32float, write>;
+@group(0) @binding(2) var<uniform> params: Params;
+// Slots of the allocated chunks
+@group(0) @binding(3) var<storage, read> active_slots: array<u32>;
+// For each slot, the slots of its 3x3 neighbourhood in row-major order (index 4 is itself)
+@group(0) @binding(4) var<storage, read> neighbours: array<u32>;
+// For each slot, bit (dy + 1) * 3 + (dx + 1) set = live cells near the edge towards (dx, dy)
+@group(0) @binding(5) var<storage, read_write> activity: array<atomic<u32>>;
+
+var<workgroup> workgroup_activity: atomic<u32>;
+
+fn slot_origin(slot: u32) -> vec2<i32> {
+    let row = params.slots_per_row;
+    return vec2<i32>(i32(slot % row), i32(slot / row)) * CHUNK_SIZE;
+}
+
+// 0 = dead, otherwise the colour of a live cell (1 to 4)
+fn cell_colour(slot: u32, local: vec2<i32>) -> u32 {
+    // Which neighbour the cell lies in: 0, 1 or 2 along each axis
+    let side = vec2<i32>(local >= vec2<i32>(0)) + vec2<i32>(local >= vec2<i32>(CHUNK_SIZE));
+    let owner = neighbours[slot * 9u + u32(side.y * 3 + side.x)];
+    if (owner == NONE) {
+        return 0u;
+    }
+    let wrapped = (local + CHUNK_SIZE) % CHUNK_SIZE;
+    let value = textureLoad(current_state, slot_origin(owner) + wrapped, 0).r;
+    return min(u32(round(value)), 4u);
+}
+
+// The most common colour among the parents, or the missing one when three parents all differ,
+// as in conway.wgsl
+fn birth_colour(counts: array<u32, 4>) -> u32 {
+    var best = 0u;
+    for (var colour = 1u; colour < 4u; colour++) {
+        if (counts[colour] > counts[best]) {
+            best = colour;
+        }
+    }
+    if (counts[best] == 1u && counts[0] + counts[1] + counts[2] + counts[3] == 3u) {
+        for (var colour = 0u; colour < 4u; colour++) {
+            if (counts[colour] == 0u) {
+                return colour + 1u;
+            }
+        }
+    }
+    return best + 1u;
+}
+
+// Whether the cell `d` away is a neighbour, with cells laid out as in display.wgsl. `up` is
+// whether a triangular cell points up.
+fn is_neighbour(d: vec2<i32>, up: bool) -> bool {
+    if (all(d == vec2<i32>(0))) {
+        return false;
+    }
+    switch (params.neighbourhood) {
+        // Hexagonal: no top-right or bottom-left neighbour
+        case 1u: {
+            return abs(d.x) <= 1 && d.x * d.y != -1;
+        }
+        // Triangular: three cells touch the point of the triangle and five its base
+        case 2u: {
+            return d.y != select(1, -1, up) || abs(d.x) <= 1;
+        }
+        default: {
+            return abs(d.x) <= 1;
+        }
+    }
+}
+
+// Which way (-1, 0 or 1) a cell is close to the edge of its chunk
+fn edge_direction(local: i32) -> i32 {
+    if (local < MARGIN) {
+        return -1;
+    }
+    if (local >= CHUNK_SIZE - MARGIN) {
+        return 1;
+    }
+    return 0;
+}
+
+fn direction_bit(d: vec2<i32>) -> u32 {
+    return 1u << u32((d.y + 1) * 3 + (d.x + 1));
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (local_index == 0u) {
+        atomicStore(&workgroup_activity, 0u);
+    }
+    workgroupBarrier();
+
+    let slot = active_slots[global_id.z];
+    let local = vec2<i32>(global_id.xy);
+
+    // Count living neighbors of each colour
+    var counts = array<u32, 4>(0u, 0u, 0u, 0u);
+    // Chunks start on even cells, so local positions tell which way triangles point
+    let up = (local.x + local.y) % 2 == 0;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -2; dx <= 2; dx++) {
+            if (!is_neighbour(vec2<i32>(dx, dy), up)) {
+                continue;
+            }
+            let colour = cell_colour(slot, local + vec2<i32>(dx, dy));
+            if (colour != 0u) {
+                counts[colour - 1u] += 1u;
+            }
+        }
+    }
+    let neighbors = counts[0] + counts[1] + counts[2] + counts[3];
+
+    // Life-like rules: look up the neighbour count in the birth or survival mask
+    let current = cell_colour(slot, local);
+    var mask = params.birth;
+    if (current != 0u) {
+        mask = params.survive;
+    }
+    let alive = (mask & (1u << neighbors)) != 0u;
+
+    var next = 0u;
+    if (alive) {
+        next = select(birth_colour(counts), current, current != 0u);
+    }
+    textureStore(next_state, slot_origin(slot) + local, vec4<f32>(f32(next), 0.0, 0.0, 1.0));
+
+    if (alive) {
+        let d = vec2<i32>(edge_direction(local.x), edge_direction(local.y));
+        var flags = ALIVE | direction_bit(vec2<i32>(d.x, 0)) | direction_bit(vec2<i32>(0, d.y));
+        flags |= direction_bit(d);
+        atomicOr(&workgroup_activity, flags);
+    }
+
+    // One global atomic per workgroup rather than per cell
+    workgroupBarrier();
+    if (local_index == 0u) {
+        let flags = atomicLoad(&workgroup_activity);
+        if (flags != 0u) {
+            atomicOr(&activity[slot], flags);
+        }
+    }
+}
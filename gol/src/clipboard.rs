@@ -0,0 +1,40 @@
+//! The system clipboard, which holds patterns as RLE text.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_text(text: String) {
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        log::warn!("Unable to copy to the system clipboard: {e}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn read_text() -> Option<String> {
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            log::debug!("Unable to read the system clipboard: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_text(text: String) {
+    if let Some(window) = web_sys::window() {
+        // The returned promise only reports whether the page had permission
+        let _ = window.navigator().clipboard().write_text(&text);
+    }
+}
+
+/// Browsers ask for permission the first time a page reads the clipboard.
+#[cfg(target_arch = "wasm32")]
+pub async fn read_text() -> Option<String> {
+    let promise = web_sys::window()?.navigator().clipboard().read_text();
+    match wasm_bindgen_futures::JsFuture::from(promise).await {
+        Ok(text) => text.as_string(),
+        Err(e) => {
+            log::debug!("Unable to read the system clipboard: {e:?}");
+            None
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// Counts generations on the GPU, so that shaders stepped many times in one submission each see
+// their own generation
+
+@group(0) @binding(0) var<storage, read_write> generation: u32;
+
+@compute @workgroup_size(1)
+fn tick() {
+    generation += 1u;
+}
@@ -1,19 +1,99 @@
-const GRID_SIZE: u32 = 64; // Start small for debugging
+use anyhow::Result;
+use wgpu::util::DeviceExt;
+
+use crate::grid::{Boundary, Grid, Rect};
+use crate::rule::{Rule, Variant};
+use crate::sand;
+use crate::scheme::UpdateScheme;
+
+pub const GRID_SIZE: u32 = 64; // Start small for debugging
 const WORKGROUP_SIZE: u32 = 16;
 
+/// How the compute shader advances the state texture.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConwayConfig {
+    pub rule: Rule,
+    pub boundary: Boundary,
+    /// Split the texture into independent worlds of this size. `None` treats the whole texture
+    /// as a single world.
+    pub tile_size: Option<(u32, u32)>,
+    /// Falling sand replaces the rule with `sand.wgsl`, which ignores `tile_size`. The other
+    /// variants all run `conway.wgsl`.
+    pub variant: Variant,
+    /// Which cells change each generation. Falling sand ignores it.
+    pub scheme: UpdateScheme,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    tile_size: [u32; 2],
+    birth: u32,
+    survive: u32,
+    boundary: u32,
+    neighbourhood: u32,
+    scheme: u32,
+    birth_chance: f32,
+    death_chance: f32,
+    _padding: [u32; 3],
+}
+
+impl Params {
+    fn new(config: &ConwayConfig, width: u32, height: u32) -> Self {
+        let (tile_width, tile_height) = config.tile_size.unwrap_or((width, height));
+        let (scheme, [birth_chance, death_chance]) = config.scheme.params();
+        Self {
+            tile_size: [tile_width, tile_height],
+            birth: config.rule.birth.into(),
+            survive: config.rule.survive.into(),
+            boundary: match config.boundary {
+                Boundary::Torus => 0,
+                Boundary::Dead | Boundary::Unbounded => 1,
+                Boundary::Cube => 2,
+            },
+            neighbourhood: config.rule.neighbourhood.index(),
+            scheme,
+            birth_chance,
+            death_chance,
+            _padding: [0; 3],
+        }
+    }
+}
+
 pub struct ConwayCompute {
     compute_pipeline: wgpu::ComputePipeline,
+    /// Counts generations on the GPU for the shaders that need them.
+    tick_pipeline: wgpu::ComputePipeline,
+    tick_bind_group: wgpu::BindGroup,
+    clock_buffer: wgpu::Buffer,
+    state_textures: [wgpu::Texture; 2],
     state_views: [wgpu::TextureView; 2],
     current_texture: usize,
+    generation: u64,
     bind_groups: [wgpu::BindGroup; 2],
+    params_buffer: wgpu::Buffer,
+    config: ConwayConfig,
+    width: u32,
+    height: u32,
 }
 
 impl ConwayCompute {
-    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        initial_state: &Grid,
+        config: ConwayConfig,
+    ) -> Self {
+        let (width, height) = (initial_state.width, initial_state.height);
+
         // Create compute shader
+        let source = match config.variant {
+            Variant::Sand => include_str!("sand.wgsl"),
+            Variant::Life | Variant::Immigration | Variant::QuadLife => include_str!("conway.wgsl"),
+        };
         let compute_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Conway Compute Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("conway.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
         });
 
         // Create bind group layout for textures
@@ -42,6 +122,39 @@ impl ConwayCompute {
                     },
                     count: None,
                 },
+                // Rule, boundary and tiling parameters
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Generation counted by the clock
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Falling sand's materials
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -62,17 +175,31 @@ impl ConwayCompute {
             cache: None,
         });
 
-        // Create initial state data as floats
-        let initial_state: Vec<f32> = (0..GRID_SIZE * GRID_SIZE)
-            .map(|_| if rand::random::<f32>() > 0.7 { 1.0 } else { 0.0 })
-            .collect();
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Conway Params Buffer"),
+            contents: bytemuck::bytes_of(&Params::new(&config, width, height)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let materials_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sand Materials Buffer"),
+            contents: bytemuck::cast_slice(&sand::material_table()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let clock_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Conway Clock Buffer"),
+            contents: bytemuck::bytes_of(&0u32),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        let (tick_pipeline, tick_bind_group) = tick_pipeline(device, &clock_buffer);
 
         // Create texture descriptor
         let texture_descriptor = wgpu::TextureDescriptor {
             label: Some("Conway State Texture"),
             size: wgpu::Extent3d {
-                width: GRID_SIZE,
-                height: GRID_SIZE,
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -81,7 +208,8 @@ impl ConwayCompute {
             format: wgpu::TextureFormat::R32Float,
             usage: wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::STORAGE_BINDING
-                | wgpu::TextureUsages::COPY_DST,
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         };
 
@@ -91,70 +219,90 @@ impl ConwayCompute {
             device.create_texture(&texture_descriptor),
         ];
 
-        // Initialize first texture with data
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &state_textures[0],
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            bytemuck::cast_slice(&initial_state),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(GRID_SIZE * 4), // 4 bytes per f32
-                rows_per_image: Some(GRID_SIZE),
-            },
-            wgpu::Extent3d {
-                width: GRID_SIZE,
-                height: GRID_SIZE,
-                depth_or_array_layers: 1,
-            },
-        );
-
         let state_views = [
             state_textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
             state_textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
         ];
 
         // Create bind groups (ping-pong)
-        let bind_groups = [
+        let bind_group = |label, input: &wgpu::TextureView, output: &wgpu::TextureView| {
             device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Conway Texture Bind Group A"),
+                label: Some(label),
                 layout: &bind_group_layout,
                 entries: &[
                     wgpu::BindGroupEntry {
                         binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&state_views[0]),
+                        resource: wgpu::BindingResource::TextureView(input),
                     },
                     wgpu::BindGroupEntry {
                         binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&state_views[1]),
+                        resource: wgpu::BindingResource::TextureView(output),
                     },
-                ],
-            }),
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Conway Texture Bind Group B"),
-                layout: &bind_group_layout,
-                entries: &[
                     wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&state_views[1]),
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&state_views[0]),
+                        binding: 3,
+                        resource: clock_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: materials_buffer.as_entire_binding(),
                     },
                 ],
-            }),
+            })
+        };
+        let bind_groups = [
+            bind_group(
+                "Conway Texture Bind Group A",
+                &state_views[0],
+                &state_views[1],
+            ),
+            bind_group(
+                "Conway Texture Bind Group B",
+                &state_views[1],
+                &state_views[0],
+            ),
         ];
 
-        Self {
+        let conway = Self {
             compute_pipeline,
+            tick_pipeline,
+            tick_bind_group,
+            clock_buffer,
+            state_textures,
             state_views,
             current_texture: 0,
+            generation: 0,
             bind_groups,
-        }
+            params_buffer,
+            config,
+            width,
+            height,
+        };
+
+        // Initialize first texture with data
+        conway.write(queue, initial_state);
+
+        conway
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn config(&self) -> &ConwayConfig {
+        &self.config
+    }
+
+    /// Changes the rule, boundary or update scheme. The variant picks the shader, so it must stay
+    /// the same.
+    pub fn set_config(&mut self, queue: &wgpu::Queue, config: ConwayConfig) {
+        debug_assert_eq!(config.variant, self.config.variant);
+        self.config = config;
+        let params = Params::new(&config, self.width, self.height);
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
     }
 
     pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder) {
@@ -166,18 +314,279 @@ impl ConwayCompute {
         compute_pass.set_pipeline(&self.compute_pipeline);
         compute_pass.set_bind_group(0, &self.bind_groups[self.current_texture], &[]);
 
-        let workgroups_x = (GRID_SIZE + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
-        let workgroups_y = (GRID_SIZE + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+        // Falling sand takes a 2×2 block per invocation, with a block more for the offset
+        // generations
+        let (width, height) = match self.config.variant {
+            Variant::Sand => (self.width / 2 + 1, self.height / 2 + 1),
+            Variant::Life | Variant::Immigration | Variant::QuadLife => (self.width, self.height),
+        };
+        let workgroups_x = width.div_ceil(WORKGROUP_SIZE);
+        let workgroups_y = height.div_ceil(WORKGROUP_SIZE);
 
         compute_pass.dispatch_workgroups(workgroups_x, workgroups_y, 1);
 
+        compute_pass.set_pipeline(&self.tick_pipeline);
+        compute_pass.set_bind_group(0, &self.tick_bind_group, &[]);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+
         drop(compute_pass);
 
         // Swap textures for next iteration
         self.current_texture = 1 - self.current_texture;
+        self.generation += 1;
+    }
+
+    /// Number of steps taken since the state was created.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, queue: &wgpu::Queue, generation: u64) {
+        self.generation = generation;
+        queue.write_buffer(
+            &self.clock_buffer,
+            0,
+            bytemuck::bytes_of(&(generation as u32)),
+        );
+    }
+
+    /// Index of the texture holding the latest generation, for callers that keep one bind group
+    /// per ping-pong texture.
+    pub fn current_index(&self) -> usize {
+        self.current_texture
+    }
+
+    pub fn texture_views(&self) -> &[wgpu::TextureView; 2] {
+        &self.state_views
     }
 
     pub fn get_current_texture_view(&self) -> &wgpu::TextureView {
-        &self.state_views[1 - self.current_texture] // The one we just wrote to
+        &self.state_views[self.current_texture] // The one we just wrote to
+    }
+
+    /// Replaces the latest generation with `grid`, which must match the texture size.
+    pub fn write(&self, queue: &wgpu::Queue, grid: &Grid) {
+        let data: Vec<f32> = grid.cells.iter().map(|&c| f32::from(c)).collect();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.state_textures[self.current_texture],
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&data),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(self.width * 4), // 4 bytes per f32
+                rows_per_image: Some(self.height),
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
     }
+
+    /// Overwrites the cells under `grid` with its top-left corner at (`x`, `y`). On a torus the
+    /// pattern wraps around the edges, otherwise it is clipped.
+    pub fn stamp(&self, queue: &wgpu::Queue, grid: &Grid, x: i32, y: i32) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let wraps = self.config.boundary == Boundary::Torus && self.config.tile_size.is_none();
+        let (x, y) = if wraps {
+            (x.rem_euclid(width), y.rem_euclid(height))
+        } else {
+            (x, y)
+        };
+
+        let mut origins = vec![(x, y)];
+        if wraps {
+            origins.extend([(x - width, y), (x, y - height), (x - width, y - height)]);
+        }
+
+        for (x, y) in origins {
+            // Clip the pattern to the part that lands inside the texture
+            let left = (-x).max(0);
+            let top = (-y).max(0);
+            let right = (width - x).min(grid.width as i32);
+            let bottom = (height - y).min(grid.height as i32);
+            if left >= right || top >= bottom {
+                continue;
+            }
+            let part = grid.crop(
+                left as u32,
+                top as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            );
+            let data: Vec<f32> = part.cells.iter().map(|&c| f32::from(c)).collect();
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.state_textures[self.current_texture],
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: (x + left) as u32,
+                        y: (y + top) as u32,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytemuck::cast_slice(&data),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(part.width * 4),
+                    rows_per_image: Some(part.height),
+                },
+                wgpu::Extent3d {
+                    width: part.width,
+                    height: part.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+    }
+
+    /// Copies the latest generation back to the CPU.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<Grid>> + use<> {
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        };
+        self.read_region(device, queue, rect)
+    }
+
+    /// Copies a rectangle of the latest generation back to the CPU.
+    ///
+    /// The copy is submitted immediately, so the returned future does not borrow `self`. On
+    /// native it blocks on the device when polled; on the web it resolves from the browser's
+    /// event loop.
+    pub fn read_region(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: Rect,
+    ) -> impl Future<Output = Result<Grid>> + use<> {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = rect;
+        let bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Conway Readback Buffer"),
+            size: u64::from(bytes_per_row * height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Conway Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.state_textures[self.current_texture],
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        let device = device.clone();
+
+        async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            device.poll(wgpu::PollType::Wait)?;
+            #[cfg(target_arch = "wasm32")]
+            let _ = device;
+            receiver.await??;
+
+            let mut grid = Grid::new(width, height);
+            {
+                let data = buffer.slice(..).get_mapped_range();
+                for (row_index, row) in data.chunks(bytes_per_row as usize).enumerate() {
+                    let texels: &[f32] = bytemuck::cast_slice(&row[..width as usize * 4]);
+                    let start = row_index * width as usize;
+                    for (cell, &value) in grid.cells[start..].iter_mut().zip(texels) {
+                        *cell = value.round() as u8;
+                    }
+                }
+            }
+            buffer.unmap();
+
+            Ok(grid)
+        }
+    }
+}
+
+/// The pipeline advancing the clock the compute shaders read their generation from.
+fn tick_pipeline(
+    device: &wgpu::Device,
+    clock_buffer: &wgpu::Buffer,
+) -> (wgpu::ComputePipeline, wgpu::BindGroup) {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Clock Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("clock.wgsl").into()),
+    });
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Clock Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Clock Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Clock Pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point: Some("tick"),
+        compilation_options: Default::default(),
+        cache: None,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Clock Bind Group"),
+        layout: &bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: clock_buffer.as_entire_binding(),
+        }],
+    });
+    (pipeline, bind_group)
 }
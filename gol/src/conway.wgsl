@@ -1,57 +1,221 @@
+// 0 = dead, otherwise the colour of a live cell (1 to 4)
 @group(0) @binding(0) var current_state: texture_2d<f32>;
 @group(0) @binding(1) var next_state: texture_storage_2d<r32float, write>;
 
-const GRID_SIZE: u32 = 64u;
+struct Params {
+    // The texture is split into independent worlds of this size
+    tile_size: vec2<u32>,
+    // Bit n set = born/survives with n neighbours
+    birth: u32,
+    survive: u32,
+    // 0 = toroidal wrap within the tile, 1 = dead cells beyond the tile edge, 2 = the tile is the
+    // six faces of a cube side by side, as `CUBE_FACES` in grid.rs lays them out
+    boundary: u32,
+    // 0 = Moore, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+    // 0 = synchronous, 1 = probabilistic, 2 = α-asynchronous, 3 = checkerboard, 4 = sweep, as
+    // `UpdateScheme` in scheme.rs
+    scheme: u32,
+    // Probabilistic: the chances of a birth and of a death. α-asynchronous: α twice.
+    birth_chance: f32,
+    death_chance: f32,
+}
+
+@group(0) @binding(2) var<uniform> params: Params;
+// Generations since the start, counted by clock.wgsl
+@group(0) @binding(3) var<storage, read> generation: u32;
+
+// The PCG hash `sand::hash` also uses
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// A number in [0, 1) drawn for a cell this generation. Matches `chance` in scheme.rs.
+fn chance(cell: vec2<u32>) -> f32 {
+    return f32(hash(cell.x ^ hash(cell.y ^ hash(generation))) >> 8u) / 16777216.0;
+}
+
+// Whether a cell follows the rule this generation. Matches `UpdateScheme::updates`.
+fn updates(cell: vec2<u32>, height: u32, alive: bool) -> bool {
+    switch (params.scheme) {
+        case 1u: {
+            return chance(cell) < select(params.birth_chance, params.death_chance, alive);
+        }
+        case 2u: {
+            return chance(cell) < params.birth_chance;
+        }
+        case 3u: {
+            return (cell.x + cell.y + generation) % 2u == 0u;
+        }
+        case 4u: {
+            return cell.y == generation % height;
+        }
+        default: {
+            return true;
+        }
+    }
+}
+
+fn cell_colour(coord: vec2<i32>) -> u32 {
+    return min(u32(round(textureLoad(current_state, coord, 0).r)), 4u);
+}
+
+// The most common colour among the parents, or the missing one when three parents all differ
+fn birth_colour(counts: array<u32, 4>) -> u32 {
+    var best = 0u;
+    for (var colour = 1u; colour < 4u; colour++) {
+        if (counts[colour] > counts[best]) {
+            best = colour;
+        }
+    }
+    if (counts[best] == 1u && counts[0] + counts[1] + counts[2] + counts[3] == 3u) {
+        for (var colour = 0u; colour < 4u; colour++) {
+            if (counts[colour] == 0u) {
+                return colour + 1u;
+            }
+        }
+    }
+    return best + 1u;
+}
+
+// Whether the cell `d` away is a neighbour, with cells laid out as in display.wgsl. `up` is
+// whether a triangular cell points up.
+fn is_neighbour(d: vec2<i32>, up: bool) -> bool {
+    if (all(d == vec2<i32>(0))) {
+        return false;
+    }
+    switch (params.neighbourhood) {
+        // Hexagonal: no top-right or bottom-left neighbour
+        case 1u: {
+            return abs(d.x) <= 1 && d.x * d.y != -1;
+        }
+        // Triangular: three cells touch the point of the triangle and five its base
+        case 2u: {
+            return d.y != select(1, -1, up) || abs(d.x) <= 1;
+        }
+        default: {
+            return abs(d.x) <= 1;
+        }
+    }
+}
+
+// Outward normal, then the directions x and y increase across the face
+const CUBE_FACES = array<mat3x3<f32>, 6>(
+    mat3x3<f32>(1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, -1.0, 0.0),
+    mat3x3<f32>(-1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0),
+    mat3x3<f32>(0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0),
+    mat3x3<f32>(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, -1.0),
+    mat3x3<f32>(0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.0),
+    mat3x3<f32>(0.0, 0.0, -1.0, -1.0, 0.0, 0.0, 0.0, -1.0, 0.0),
+);
+
+// The cell `d` away from `cell` on a cube whose faces are `size` cells across, folding over the
+// edge of the face, or -1 off a corner. Matches `cube_neighbour` in grid.rs.
+fn cube_neighbour(size: i32, cell: vec2<i32>, d: vec2<i32>) -> vec2<i32> {
+    let face = cell.x / size;
+    let f = vec2<i32>(cell.x % size, cell.y) + d;
+    if (all(f >= vec2<i32>(0)) && all(f < vec2<i32>(size))) {
+        return vec2<i32>(face * size + f.x, f.y);
+    }
+    // Positions from the centre of the face in half cells, so cell centres are odd
+    let p = 2 * f + 1 - size;
+    let over = abs(p) > vec2<i32>(size);
+    if (all(over)) {
+        return vec2<i32>(-1);
+    }
+    let axes = CUBE_FACES[face];
+    let n = f32(size);
+    // Fold the part beyond the edge down over it, onto the next face
+    var point: vec3<f32>;
+    var towards: vec3<f32>;
+    if (over.x) {
+        let s = f32(sign(p.x));
+        point = (n - (f32(abs(p.x)) - n)) * axes[0] + n * s * axes[1] + f32(p.y) * axes[2];
+        towards = s * axes[1];
+    } else {
+        let s = f32(sign(p.y));
+        point = (n - (f32(abs(p.y)) - n)) * axes[0] + f32(p.x) * axes[1] + n * s * axes[2];
+        towards = s * axes[2];
+    }
+    for (var next = 0; next < 6; next++) {
+        let next_axes = CUBE_FACES[next];
+        if (all(next_axes[0] == towards)) {
+            let q = vec2<i32>(i32(dot(point, next_axes[1])), i32(dot(point, next_axes[2])));
+            return vec2<i32>(next * size, 0) + (q + size - 1) / 2;
+        }
+    }
+    return vec2<i32>(-1);
+}
 
 @compute @workgroup_size(16, 16, 1)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let grid_size = textureDimensions(current_state);
     let x = global_id.x;
     let y = global_id.y;
-    
+
     // Bounds check
-    if (x >= GRID_SIZE || y >= GRID_SIZE) {
+    if (x >= grid_size.x || y >= grid_size.y) {
         return;
     }
-    
+
     let coord = vec2<i32>(i32(x), i32(y));
-    
-    // Count living neighbors
-    var neighbors = 0.0;
-    
+    let tile = vec2<i32>(params.tile_size);
+    let local = coord % tile;
+    let origin = coord - local;
+
+    // Count living neighbors of each colour
+    var counts = array<u32, 4>(0u, 0u, 0u, 0u);
+    let up = (coord.x + coord.y) % 2 == 0;
+
     for (var dy = -1; dy <= 1; dy++) {
-        for (var dx = -1; dx <= 1; dx++) {
-            if (dx == 0 && dy == 0) {
+        for (var dx = -2; dx <= 2; dx++) {
+            if (!is_neighbour(vec2<i32>(dx, dy), up)) {
                 continue;
             }
-            
-            // Wrap around edges (toroidal topology)
-            let nx = (i32(x) + dx + i32(GRID_SIZE)) % i32(GRID_SIZE);
-            let ny = (i32(y) + dy + i32(GRID_SIZE)) % i32(GRID_SIZE);
-            let neighbor_coord = vec2<i32>(nx, ny);
-            
-            neighbors += textureLoad(current_state, neighbor_coord, 0).r;
-        }
-    }
-    
-    let current_cell = textureLoad(current_state, coord, 0).r;
-    
-    // Conway's Game of Life rules:
-    // 1. Any live cell with 2-3 neighbors survives
-    // 2. Any dead cell with exactly 3 neighbors becomes alive
-    // 3. All other cells die or stay dead
-    
-    var next_value = 0.0;
-    if (current_cell > 0.5) {
-        // Live cell
-        if (neighbors >= 1.5 && neighbors <= 3.5) {
-            next_value = 1.0;  // Survives
+
+            var n = local + vec2<i32>(dx, dy);
+            if (params.boundary == 2u) {
+                n = cube_neighbour(tile.y, local, vec2<i32>(dx, dy));
+                if (n.x < 0) {
+                    continue;
+                }
+            } else if (params.boundary == 0u) {
+                // Wrap around edges (toroidal topology)
+                n = (n + tile) % tile;
+            } else if (any(n < vec2<i32>(0)) || any(n >= tile)) {
+                continue;
+            }
+
+            let colour = cell_colour(origin + n);
+            if (colour != 0u) {
+                counts[colour - 1u] += 1u;
+            }
         }
-    } else {
-        // Dead cell
-        if (neighbors >= 2.5 && neighbors <= 3.5) {
-            next_value = 1.0;  // Birth
+    }
+
+    let neighbors = counts[0] + counts[1] + counts[2] + counts[3];
+    let current_cell = cell_colour(coord);
+
+    // Life-like rules: look up the neighbour count in the birth or survival mask
+    var mask = params.birth;
+    if (current_cell != 0u) {
+        mask = params.survive;
+    }
+
+    var next_value = 0.0;
+    if ((mask & (1u << neighbors)) != 0u) {
+        if (current_cell != 0u) {
+            next_value = f32(current_cell);
+        } else {
+            next_value = f32(birth_colour(counts));
         }
     }
-    
+    // Cells the update scheme leaves out this generation keep their state
+    if (!updates(vec2<u32>(local), u32(tile.y), current_cell != 0u)) {
+        next_value = f32(current_cell);
+    }
+
     textureStore(next_state, coord, vec4<f32>(next_value, 0.0, 0.0, 1.0));
-}
\ No newline at end of file
+}
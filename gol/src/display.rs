@@ -0,0 +1,211 @@
+use wgpu::util::DeviceExt;
+
+use crate::conway::ConwayCompute;
+use crate::overlay::Overlay;
+use crate::rule::Neighbourhood;
+use crate::viewport::Viewport;
+
+/// Draws the world with `display.wgsl` into any render target, such as the window surface or an
+/// offscreen texture for recording.
+pub struct Display {
+    pipeline: wgpu::RenderPipeline,
+    bind_groups: [wgpu::BindGroup; 2],
+    view_buffer: wgpu::Buffer,
+    neighbourhood: Neighbourhood,
+    palette: u32,
+}
+
+impl Display {
+    pub fn new(
+        device: &wgpu::Device,
+        conway: &ConwayCompute,
+        overlay: &Overlay,
+        format: wgpu::TextureFormat,
+        viewport: &Viewport,
+    ) -> Self {
+        let neighbourhood = conway.config().rule.neighbourhood;
+        let palette = conway.config().variant.palette();
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("View Uniform Buffer"),
+            contents: bytemuck::bytes_of(&viewport.uniform(neighbourhood, palette)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create display shader
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("display.wgsl").into()),
+        });
+
+        // Create bind group layout for Conway state buffer and the viewport
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Create display bind groups, one for each ping-pong texture
+        let bind_groups = conway.texture_views().each_ref().map(|view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Display Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: view_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        // Create display pipeline
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, overlay.layout()],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[], // No vertex buffers - we generate fullscreen triangle in shader
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None, // No culling for fullscreen triangle
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_groups,
+            view_buffer,
+            neighbourhood,
+            palette,
+        }
+    }
+
+    pub fn set_viewport(&self, queue: &wgpu::Queue, viewport: &Viewport) {
+        queue.write_buffer(
+            &self.view_buffer,
+            0,
+            bytemuck::bytes_of(&viewport.uniform(self.neighbourhood, self.palette)),
+        );
+    }
+
+    /// Records a pass drawing the latest generation over the whole of `target`.
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        conway: &ConwayCompute,
+        overlay: &Overlay,
+    ) {
+        self.record(encoder, target, conway, overlay, None);
+    }
+
+    /// Records a pass drawing the latest generation into `area` of `target`, given in pixels
+    /// as `(x, y, width, height)`, over what is already there.
+    pub fn draw_inset(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        conway: &ConwayCompute,
+        overlay: &Overlay,
+        area: [f32; 4],
+    ) {
+        self.record(encoder, target, conway, overlay, Some(area));
+    }
+
+    fn record(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        conway: &ConwayCompute,
+        overlay: &Overlay,
+        area: Option<[f32; 4]>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Conway Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: match area {
+                        Some(_) => wgpu::LoadOp::Load,
+                        None => wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.1,
+                            g: 0.1,
+                            b: 0.1,
+                            a: 1.0,
+                        }),
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if let Some([x, y, width, height]) = area {
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
+
+        // Draw fullscreen triangle with Conway's state
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_groups[conway.current_index()], &[]);
+        render_pass.set_bind_group(1, overlay.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1); // 3 vertices for fullscreen triangle
+    }
+}
@@ -8,39 +8,175 @@ struct VertexOutput {
 fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     // Create fullscreen triangle (covers whole screen)
     var uv = vec2<f32>(
-        f32((vertex_index << 1u) & 2u), 
+        f32((vertex_index << 1u) & 2u),
         f32(vertex_index & 2u)
     );
-    
+
     var out: VertexOutput;
     out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
-    out.uv = uv;
+    // Texture row 0 is the top of the screen, matching window coordinates
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
     return out;
 }
 
 // Conway state texture
 @group(0) @binding(0) var conway_state: texture_2d<f32>;
 
-const GRID_SIZE: u32 = 64u;
+// Part of the grid shown in the window
+struct View {
+    // Grid position, in cells, at the centre of the window
+    centre: vec2<f32>,
+    // 1 = the grid fills the window
+    zoom: f32,
+    // Shape of the cells: 0 = square, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+    // 0 = colours of live cells, 1 = falling sand's materials
+    palette: u32,
+}
+
+@group(0) @binding(1) var<uniform> view: View;
+
+// Editing aids drawn over the world
+struct Overlay {
+    // Top-left corner of the pattern previewed under the cursor
+    ghost_origin: vec2<i32>,
+    // 0 = hidden, 1 = clipped to the grid, 2 = wrapped around the edges
+    ghost_mode: u32,
+    selection_visible: u32,
+    // Selected cells, max exclusive
+    selection_min: vec2<u32>,
+    selection_max: vec2<u32>,
+}
+
+@group(1) @binding(0) var ghost_cells: texture_2d<f32>;
+@group(1) @binding(1) var<uniform> overlay: Overlay;
+
+// Cell colours of the multi-colour variants: white, red, blue and yellow, or of falling sand's
+// materials: sand, water, stone and fire
+fn palette(cell: f32) -> vec3<f32> {
+    if (view.palette == 1u) {
+        switch (u32(round(cell))) {
+            case 0u: { return vec3<f32>(0.0, 0.0, 0.0); }
+            case 1u: { return vec3<f32>(0.87, 0.75, 0.45); }
+            case 2u: { return vec3<f32>(0.2, 0.45, 0.95); }
+            case 3u: { return vec3<f32>(0.5, 0.5, 0.55); }
+            default: { return vec3<f32>(1.0, 0.45, 0.1); }
+        }
+    }
+    switch (u32(round(cell))) {
+        case 0u: { return vec3<f32>(0.0, 0.0, 0.0); }
+        case 2u: { return vec3<f32>(1.0, 0.35, 0.3); }
+        case 3u: { return vec3<f32>(0.35, 0.55, 1.0); }
+        case 4u: { return vec3<f32>(1.0, 0.85, 0.25); }
+        default: { return vec3<f32>(1.0, 1.0, 1.0); }
+    }
+}
+
+// The cell drawn at a position given in cells, as `Neighbourhood::cell_at` finds it
+fn cell_at(pos: vec2<f32>) -> vec2<i32> {
+    let row = floor(pos.y);
+    switch (view.neighbourhood) {
+        // Hexagonal: the nearest cell centre, each row shifted half a cell left of the one above
+        case 1u: {
+            var nearest = vec2<i32>(0);
+            var best = 4.0;
+            for (var r = row - 1.0; r <= row + 1.0; r += 1.0) {
+                let shift = r / 2.0;
+                let column = floor(pos.x + shift);
+                let d = pos - vec2<f32>(column + 0.5 - shift, r + 0.5);
+                if (dot(d, d) < best) {
+                    best = dot(d, d);
+                    nearest = vec2<i32>(i32(column), i32(r));
+                }
+            }
+            return nearest;
+        }
+        // Triangular: between two triangle centres, split by the edge they share
+        case 2u: {
+            let u = pos.x - 0.5;
+            let column = floor(u);
+            let across = u - column;
+            let down = pos.y - row;
+            let cell = vec2<i32>(i32(column), i32(row));
+            let up = ((cell.x + cell.y) % 2 + 2) % 2 == 0;
+            let left = select(across < 1.0 - down, across < down, up);
+            return cell + vec2<i32>(select(1, 0, left), 0);
+        }
+        default: {
+            return vec2<i32>(floor(pos));
+        }
+    }
+}
+
+// The colour of the cell at a position given in cells: live cells in their colour, dead cells
+// and those beyond the grid black
+fn colour_at(pos: vec2<f32>) -> vec3<f32> {
+    let coord = cell_at(pos);
+    if (any(coord < vec2<i32>(0)) || any(coord >= vec2<i32>(textureDimensions(conway_state)))) {
+        return vec3<f32>(0.0);
+    }
+    return palette(textureLoad(conway_state, coord, 0).r);
+}
+
+// The cells a pixel `footprint` cells across covers, averaged over up to 4 × 4 samples so that
+// zoomed-out views shimmer no more than a scaled-down picture
+fn average_colour(pos: vec2<f32>, footprint: vec2<f32>) -> vec3<f32> {
+    let samples = vec2<i32>(clamp(ceil(footprint), vec2<f32>(1.0), vec2<f32>(4.0)));
+    var sum = vec3<f32>(0.0);
+    for (var y = 0; y < samples.y; y++) {
+        for (var x = 0; x < samples.x; x++) {
+            let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / vec2<f32>(samples) - 0.5;
+            sum += colour_at(pos + offset * footprint);
+        }
+    }
+    return sum / f32(samples.x * samples.y);
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let grid_size = textureDimensions(conway_state);
+
     // Convert UV to grid coordinates
-    let grid_pos = vec2<u32>(in.uv * f32(GRID_SIZE));
-    
+    let pos = view.centre + (in.uv - 0.5) * vec2<f32>(grid_size) / view.zoom;
+    // Taken before any branch, as derivatives must be
+    let footprint = fwidth(pos);
+
+    let coord = cell_at(pos);
+
     // Bounds check
-    if (grid_pos.x >= GRID_SIZE || grid_pos.y >= GRID_SIZE) {
+    if (any(coord < vec2<i32>(0)) || any(coord >= vec2<i32>(grid_size))) {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);  // Black for out of bounds
     }
-    
-    // Sample Conway state from texture
-    let coord = vec2<i32>(i32(grid_pos.x), i32(grid_pos.y));
-    let cell = textureLoad(conway_state, coord, 0).r;
-    
-    // Live cells = white, dead cells = black
-    if (cell > 0.5) {
-        return vec4<f32>(1.0, 1.0, 1.0, 1.0);  // White = alive
-    } else {
-        return vec4<f32>(0.0, 0.0, 0.0, 1.0);  // Black = dead
-    }
-}
\ No newline at end of file
+    let grid_pos = vec2<u32>(coord);
+
+    var color = average_colour(pos, footprint);
+
+    if (overlay.selection_visible != 0u
+        && all(grid_pos >= overlay.selection_min) && all(grid_pos < overlay.selection_max)) {
+        let on_edge = any(grid_pos == overlay.selection_min)
+            || any(grid_pos + vec2<u32>(1u) == overlay.selection_max);
+        if (on_edge) {
+            color = mix(color, vec3<f32>(1.0, 0.8, 0.2), 0.5);  // Yellow = marquee
+        } else {
+            color = mix(color, vec3<f32>(1.0, 0.8, 0.2), 0.15);
+        }
+    }
+
+    if (overlay.ghost_mode != 0u) {
+        let ghost_size = vec2<i32>(textureDimensions(ghost_cells));
+        var local = coord - overlay.ghost_origin;
+        if (overlay.ghost_mode == 2u) {
+            let size = vec2<i32>(grid_size);
+            local = (local % size + size) % size;
+        }
+        if (all(local >= vec2<i32>(0)) && all(local < ghost_size)) {
+            if (textureLoad(ghost_cells, local, 0).r > 0.5) {
+                color = mix(color, vec3<f32>(0.2, 0.9, 0.4), 0.7);  // Green = ghost cell
+            } else {
+                color = mix(color, vec3<f32>(0.2, 0.3, 0.8), 0.3);  // Blue = ghost footprint
+            }
+        }
+    }
+
+    return vec4<f32>(color, 1.0);
+}
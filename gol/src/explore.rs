@@ -0,0 +1,641 @@
+//! Surveys of rule space: random life-like rules, or mutations of one, are each run on the same
+//! batch of soups and sorted into rough Wolfram-style classes by how the soups turn out.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::conway::{ConwayCompute, ConwayConfig};
+use crate::gpu::HeadlessContext;
+use crate::grid::{Boundary, Grid};
+use crate::rule::{Neighbourhood, Rule, Variant};
+use crate::scheme::UpdateScheme;
+
+/// The generation activity is first measured at, once the soup's first burst is over.
+const EARLY_GENERATION: u32 = 32;
+
+/// The longest period looked for, in oscillating soups and in objects moving across them.
+const MAX_PERIOD: u32 = 30;
+
+/// Cells this close together belong to the same object, as they can still affect each other.
+const GROUP_DISTANCE: i32 = 2;
+
+/// Objects of this many cells are small enough to be spaceships rather than chaotic debris.
+const OBJECT_CELLS: std::ops::RangeInclusive<usize> = 3..=64;
+
+/// An object's cells, relative to its first cell in reading order.
+type Shape = Vec<(i32, i32)>;
+
+/// A soup whose activity falls below this fraction of its early activity is settling down.
+const DECAY_THRESHOLD: f64 = 0.5;
+
+/// Sample random life-like rules, or mutations of one, and classify how their soups behave.
+#[derive(Debug, Clone, clap::Args)]
+pub struct ExploreOptions {
+    /// Number of rules to try
+    #[arg(long, default_value_t = 64)]
+    pub rules: usize,
+    /// Mutate this rule, flipping one or two of its birth and survival counts, instead of
+    /// sampling rules at random. The rule itself is included for comparison.
+    #[arg(long)]
+    pub mutate: Option<Rule>,
+    /// Seed for picking rules; soup `i` of every rule uses seed + i (random if omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Number of soups each rule is run on
+    #[arg(long, default_value_t = 16)]
+    pub soups: u32,
+    /// Width and height of each random soup
+    #[arg(long, default_value_t = 32)]
+    pub soup_size: u32,
+    /// Width and height of the torus each soup evolves on
+    #[arg(long, default_value_t = 64)]
+    pub tile_size: u32,
+    /// Probability that a soup cell starts alive
+    #[arg(long, default_value_t = 0.35)]
+    pub density: f32,
+    /// Generations each soup is run for
+    #[arg(long, default_value_t = 1000)]
+    pub generations: u32,
+    /// Where to write the ranked rules: JSON if the name ends in `.json`, otherwise CSV
+    #[arg(long, default_value = "explore.csv")]
+    pub output: PathBuf,
+    /// Ask for one of the ranked rules afterwards and open it in a window
+    #[arg(long)]
+    pub open: bool,
+}
+
+/// A rough Wolfram-style class, in the order rules are ranked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Class {
+    /// Activity dies down, leaving objects that move across a settled background.
+    Complex,
+    /// Activity carries on everywhere.
+    Chaotic,
+    /// Every soup ends up oscillating.
+    Periodic,
+    /// Every soup freezes or dies out.
+    Static,
+    /// Every soup dies out.
+    Dies,
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Class::Complex => "complex",
+            Class::Chaotic => "chaotic",
+            Class::Periodic => "periodic",
+            Class::Static => "static",
+            Class::Dies => "dies",
+        })
+    }
+}
+
+/// How a soup ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fate {
+    Dead,
+    /// Repeating with this period, 1 for still lifes.
+    Periodic(u32),
+    Active,
+}
+
+/// Measurements of a single soup.
+struct Soup {
+    fate: Fate,
+    density: f64,
+    /// Fractions of the cells that changed in a generation, early on and at the end.
+    early_activity: f64,
+    activity: f64,
+    /// Small objects found in another place `MAX_PERIOD` generations or fewer ago.
+    moving: u32,
+}
+
+/// Measurements of a rule, averaged over its soups.
+struct Report {
+    rule: Rule,
+    class: Class,
+    density: f64,
+    activity: f64,
+    /// Final activity as a fraction of the early activity.
+    decay: f64,
+    /// Fraction of the soups that died out, froze or oscillate.
+    settled: f64,
+    /// The most common period above 1 among oscillating soups, or 0 if there is none.
+    period: u32,
+    /// Moving objects per soup.
+    moving: f64,
+}
+
+/// Explores rules as `options` asks, writing them ranked to the output file. Returns the rule
+/// picked to be opened, if any.
+pub fn run(options: &ExploreOptions) -> Result<Option<Rule>> {
+    if options.generations < EARLY_GENERATION + 1 + MAX_PERIOD {
+        bail!(
+            "soups need at least {} generations to be classified",
+            EARLY_GENERATION + 1 + MAX_PERIOD
+        );
+    }
+    if options.soups == 0 {
+        bail!("there must be at least one soup to explore a rule with");
+    }
+    if options.tile_size == 0 || options.tile_size < options.soup_size {
+        bail!(
+            "a tile of {} cells cannot hold a soup of {}",
+            options.tile_size,
+            options.soup_size
+        );
+    }
+    if let Some(rule) = options.mutate
+        && rule.neighbourhood != Neighbourhood::Moore
+    {
+        bail!("only rules on square cells can be explored, not {rule}");
+    }
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let rules = pick_rules(options, &mut StdRng::seed_from_u64(seed));
+    log::info!(
+        "Exploring {} rules on {} soups each from seed {seed}",
+        rules.len(),
+        options.soups
+    );
+
+    let ctx = pollster::block_on(HeadlessContext::new())?;
+    let size = u64::from(columns(options)) * u64::from(options.tile_size);
+    let max_size = ctx.device.limits().max_texture_dimension_2d;
+    if size > u64::from(max_size) {
+        bail!(
+            "{} soups in tiles of {} cells need a texture {size} cells across, more than the \
+             {max_size} the GPU allows",
+            options.soups,
+            options.tile_size
+        );
+    }
+    let soups = soup_grid(options, seed);
+    let config = ConwayConfig {
+        rule: Rule::default(),
+        boundary: Boundary::Torus,
+        tile_size: Some((options.tile_size, options.tile_size)),
+        variant: Variant::Life,
+        scheme: UpdateScheme::Synchronous,
+    };
+    let mut conway = ConwayCompute::new(&ctx.device, &ctx.queue, &soups, config);
+
+    let mut reports = Vec::with_capacity(rules.len());
+    for (i, &rule) in rules.iter().enumerate() {
+        conway.set_config(&ctx.queue, ConwayConfig { rule, ..config });
+        conway.write(&ctx.queue, &soups);
+        conway.set_generation(&ctx.queue, 0);
+        let soups = run_soups(&ctx, &mut conway, options)?;
+        let report = Report::new(rule, &soups);
+        log::info!("{}/{}: {rule} is {}", i + 1, rules.len(), report.class);
+        reports.push(report);
+    }
+
+    reports.sort_by(|a, b| {
+        a.class
+            .cmp(&b.class)
+            .then(b.moving.total_cmp(&a.moving))
+            .then(b.activity.total_cmp(&a.activity))
+            .then(a.rule.to_string().cmp(&b.rule.to_string()))
+    });
+    write_reports(&reports, options)?;
+    log::info!(
+        "Wrote {} rules to {}",
+        reports.len(),
+        options.output.display()
+    );
+
+    if !options.open {
+        return Ok(None);
+    }
+    ask_for_rule(&reports)
+}
+
+/// The rules to explore: `options.mutate` and its mutations, or random rules without birth on
+/// zero neighbours, none twice.
+fn pick_rules(options: &ExploreOptions, rng: &mut StdRng) -> Vec<Rule> {
+    let mut rules: Vec<Rule> = options.mutate.into_iter().collect();
+    // Mutations run out quickly, so stop trying once new rules stop turning up
+    let mut attempts = 0;
+    while rules.len() < options.rules && attempts < 100 * options.rules {
+        attempts += 1;
+        let rule = match options.mutate {
+            Some(mut rule) => {
+                for _ in 0..rng.random_range(1..=2) {
+                    // Birth on 1 to 8 neighbours, then survival on 0 to 8
+                    match rng.random_range(0..17) {
+                        bit @ 0..8 => rule.birth ^= 1 << (bit + 1),
+                        bit => rule.survive ^= 1 << (bit - 8),
+                    }
+                }
+                rule
+            }
+            None => Rule {
+                birth: rng.random_range(1..256u16) << 1,
+                survive: rng.random_range(0..512),
+                neighbourhood: Neighbourhood::Moore,
+            },
+        };
+        if rule.birth != 0 && !rules.contains(&rule) {
+            rules.push(rule);
+        }
+    }
+    rules
+}
+
+/// Columns of tiles the soups are laid out in.
+fn columns(options: &ExploreOptions) -> u32 {
+    (f64::from(options.soups)).sqrt().ceil() as u32
+}
+
+/// The area of tile `i`, as (x, y, size).
+fn tile(options: &ExploreOptions, i: u32) -> (u32, u32, u32) {
+    let (size, columns) = (options.tile_size, columns(options));
+    (i % columns * size, i / columns * size, size)
+}
+
+/// Every soup, each centred in its own tile.
+fn soup_grid(options: &ExploreOptions, seed: u64) -> Grid {
+    let columns = columns(options);
+    let rows = options.soups.div_ceil(columns);
+    let mut grid = Grid::new(columns * options.tile_size, rows * options.tile_size);
+    let offset = (options.tile_size - options.soup_size.min(options.tile_size)) / 2;
+    for i in 0..options.soups {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(u64::from(i)));
+        let soup = Grid::random(
+            options.soup_size,
+            options.soup_size,
+            options.density,
+            &mut rng,
+        );
+        let (x, y, _) = tile(options, i);
+        grid.paste(&soup, x + offset, y + offset);
+    }
+    grid
+}
+
+/// Runs the soups for `options.generations`, reading them back early on and for the last
+/// `MAX_PERIOD` generations.
+fn run_soups(
+    ctx: &HeadlessContext,
+    conway: &mut ConwayCompute,
+    options: &ExploreOptions,
+) -> Result<Vec<Soup>> {
+    let mut advance = |generations| -> Result<Grid> {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Explore Encoder"),
+            });
+        for _ in 0..generations {
+            conway.step(&mut encoder);
+        }
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        pollster::block_on(conway.read(&ctx.device, &ctx.queue))
+    };
+
+    let early = [advance(EARLY_GENERATION)?, advance(1)?];
+    let mut history = vec![advance(
+        options.generations - EARLY_GENERATION - 1 - MAX_PERIOD,
+    )?];
+    for _ in 0..MAX_PERIOD {
+        history.push(advance(1)?);
+    }
+
+    Ok((0..options.soups)
+        .map(|i| {
+            let (x, y, size) = tile(options, i);
+            let crop = |grid: &Grid| grid.crop(x, y, size, size);
+            let history: Vec<Grid> = history.iter().map(crop).collect();
+            Soup::new(&history, [&crop(&early[0]), &crop(&early[1])])
+        })
+        .collect())
+}
+
+impl Soup {
+    /// Measures a soup from its last `MAX_PERIOD` + 1 generations, oldest first, and two early
+    /// ones in a row.
+    fn new(history: &[Grid], early: [&Grid; 2]) -> Self {
+        let last = &history[history.len() - 1];
+        let area = f64::from(last.width * last.height);
+        let changed = |a: &Grid, b: &Grid| {
+            let changes = a
+                .cells
+                .iter()
+                .zip(&b.cells)
+                .filter(|(a, b)| (**a != 0) != (**b != 0))
+                .count();
+            changes as f64 / area
+        };
+        let fate = if last.population() == 0 {
+            Fate::Dead
+        } else {
+            (1..=MAX_PERIOD)
+                .find(|&p| history[history.len() - 1 - p as usize] == *last)
+                .map_or(Fate::Active, Fate::Periodic)
+        };
+        let early_activity = changed(early[0], early[1]);
+        let activity = changed(&history[history.len() - 2], last);
+        // Amid activity that has not died down, small objects are debris that only happens to
+        // repeat a shape nearby
+        let settling = activity < early_activity * DECAY_THRESHOLD;
+        Self {
+            fate,
+            density: last.population() as f64 / area,
+            early_activity,
+            activity,
+            moving: if fate == Fate::Active && settling {
+                moving_objects(history)
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Counts the small objects in the last of `history` that were the same shape somewhere nearby
+/// in an earlier generation, but not where they are now.
+fn moving_objects(history: &[Grid]) -> u32 {
+    let last = history.len() - 1;
+    let objects: Vec<_> = objects(&history[last])
+        .into_iter()
+        .filter(|(_, shape)| OBJECT_CELLS.contains(&shape.len()))
+        .collect();
+    if objects.is_empty() {
+        return 0;
+    }
+
+    // Where each shape was, going back one generation at a time
+    let earlier: Vec<HashMap<Shape, Vec<(i32, i32)>>> = (1..=MAX_PERIOD as usize)
+        .map(|p| {
+            let mut places: HashMap<_, Vec<_>> = HashMap::new();
+            for (place, shape) in self::objects(&history[last - p]) {
+                places.entry(shape).or_default().push(place);
+            }
+            places
+        })
+        .collect();
+
+    let mut moving = 0;
+    for (place, shape) in &objects {
+        for (p, places) in (1..).zip(&earlier) {
+            let Some(places) = places.get(shape) else {
+                continue;
+            };
+            // Back where it was: a still life or an oscillator
+            if places.contains(place) {
+                break;
+            }
+            let nearby = |&(x, y): &(i32, i32)| (x - place.0).abs().max((y - place.1).abs()) <= p;
+            if places.iter().any(nearby) {
+                moving += 1;
+                break;
+            }
+        }
+    }
+    moving
+}
+
+/// Splits a grid into groups of cells within `GROUP_DISTANCE` of each other, each as the
+/// position of its first cell and its cells relative to that.
+fn objects(grid: &Grid) -> Vec<((i32, i32), Shape)> {
+    let (width, height) = (grid.width as i32, grid.height as i32);
+    let mut seen = vec![false; grid.cells.len()];
+    let mut objects = Vec::new();
+    for (x, y) in grid.live_cells() {
+        let start = (y * grid.width + x) as usize;
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut cells = vec![(x as i32, y as i32)];
+        let mut next = 0;
+        while let Some(&(x, y)) = cells.get(next) {
+            next += 1;
+            for dy in -GROUP_DISTANCE..=GROUP_DISTANCE {
+                for dx in -GROUP_DISTANCE..=GROUP_DISTANCE {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let i = (ny * width + nx) as usize;
+                    if grid.cells[i] != 0 && !seen[i] {
+                        seen[i] = true;
+                        cells.push((nx, ny));
+                    }
+                }
+            }
+        }
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        let first = cells[0];
+        let shape = cells
+            .iter()
+            .map(|&(x, y)| (x - first.0, y - first.1))
+            .collect();
+        objects.push((first, shape));
+    }
+    objects
+}
+
+impl Report {
+    fn new(rule: Rule, soups: &[Soup]) -> Self {
+        let count = soups.len() as f64;
+        let mean = |value: fn(&Soup) -> f64| soups.iter().map(value).sum::<f64>() / count;
+        let early_activity = mean(|soup| soup.early_activity);
+        let activity = mean(|soup| soup.activity);
+        let decay = if early_activity > 0.0 {
+            activity / early_activity
+        } else {
+            0.0
+        };
+        let moving = mean(|soup| f64::from(soup.moving));
+
+        let mut periods: HashMap<u32, u32> = HashMap::new();
+        for soup in soups {
+            if let Fate::Periodic(p @ 2..) = soup.fate {
+                *periods.entry(p).or_default() += 1;
+            }
+        }
+        let period = periods
+            .into_iter()
+            .max_by_key(|&(p, count)| (count, std::cmp::Reverse(p)))
+            .map_or(0, |(p, _)| p);
+
+        let all = |fate: fn(Fate) -> bool| soups.iter().all(|soup| fate(soup.fate));
+        let class = if all(|fate| fate == Fate::Dead) {
+            Class::Dies
+        } else if all(|fate| matches!(fate, Fate::Dead | Fate::Periodic(1))) {
+            Class::Static
+        } else if all(|fate| fate != Fate::Active) {
+            Class::Periodic
+        } else if moving > 0.0 || decay < DECAY_THRESHOLD {
+            Class::Complex
+        } else {
+            Class::Chaotic
+        };
+
+        Self {
+            rule,
+            class,
+            density: mean(|soup| soup.density),
+            activity,
+            decay,
+            settled: mean(|soup| f64::from(u8::from(soup.fate != Fate::Active))),
+            period,
+            moving,
+        }
+    }
+}
+
+fn write_reports(reports: &[Report], options: &ExploreOptions) -> Result<()> {
+    let path = &options.output;
+    let mut out =
+        BufWriter::new(File::create(path).with_context(|| format!("creating {}", path.display()))?);
+    let json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    if json {
+        writeln!(out, "[")?;
+    } else {
+        writeln!(
+            out,
+            "rank,rule,class,density,activity,decay,settled,period,moving"
+        )?;
+    }
+    for (i, report) in reports.iter().enumerate() {
+        let Report {
+            rule,
+            class,
+            density,
+            activity,
+            decay,
+            settled,
+            period,
+            moving,
+        } = report;
+        let rank = i + 1;
+        if json {
+            let comma = if rank < reports.len() { "," } else { "" };
+            writeln!(
+                out,
+                "  {{\"rank\": {rank}, \"rule\": \"{rule}\", \"class\": \"{class}\", \
+                 \"density\": {density:.4}, \"activity\": {activity:.4}, \"decay\": {decay:.4}, \
+                 \"settled\": {settled:.4}, \"period\": {period}, \"moving\": {moving:.4}}}{comma}"
+            )?;
+        } else {
+            writeln!(
+                out,
+                "{rank},{rule},{class},{density:.4},{activity:.4},{decay:.4},{settled:.4},\
+                 {period},{moving:.4}"
+            )?;
+        }
+    }
+    if json {
+        writeln!(out, "]")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Lists the best-ranked rules and asks for one by rank, or any rule by name.
+fn ask_for_rule(reports: &[Report]) -> Result<Option<Rule>> {
+    let mut out = io::stdout().lock();
+    for (i, report) in reports.iter().take(20).enumerate() {
+        writeln!(
+            out,
+            "{:>3}. {:<20} {:<8} {:.2} moving objects a soup",
+            i + 1,
+            report.rule.to_string(),
+            report.class,
+            report.moving
+        )?;
+    }
+    let stdin = io::stdin();
+    loop {
+        write!(out, "Rank or rule to open (blank to quit): ")?;
+        out.flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 || line.trim().is_empty() {
+            return Ok(None);
+        }
+        let line = line.trim();
+        let rule = match line.parse::<usize>() {
+            Ok(rank) => match reports.get(rank.wrapping_sub(1)) {
+                Some(report) => Ok(report.rule),
+                None => Err(anyhow::anyhow!("there is no rank {rank}")),
+            },
+            Err(_) => line.parse::<Rule>(),
+        };
+        match rule {
+            Ok(rule) => return Ok(Some(rule)),
+            Err(e) => writeln!(out, "{e:#}")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Classifies `rule` from a few soups stepped on the CPU, measured as `run_soups` measures
+    /// them on the GPU.
+    fn classify(rule: &str) -> Class {
+        let rule: Rule = rule.parse().unwrap();
+        let options = ExploreOptions {
+            rules: 1,
+            mutate: None,
+            seed: None,
+            soups: 1,
+            soup_size: 32,
+            tile_size: 64,
+            density: 0.35,
+            generations: 300,
+            output: PathBuf::new(),
+            open: false,
+        };
+        let soups: Vec<Soup> = (0..4)
+            .map(|seed| {
+                let mut grid = soup_grid(&options, seed);
+                let mut advance = |generations| {
+                    for _ in 0..generations {
+                        grid = grid.step(&rule, Boundary::Torus);
+                    }
+                    grid.clone()
+                };
+                let early = [advance(EARLY_GENERATION), advance(1)];
+                let mut history = vec![advance(
+                    options.generations - EARLY_GENERATION - 1 - MAX_PERIOD,
+                )];
+                for _ in 0..MAX_PERIOD {
+                    history.push(advance(1));
+                }
+                Soup::new(&history, [&early[0], &early[1]])
+            })
+            .collect();
+        Report::new(rule, &soups).class
+    }
+
+    #[test]
+    fn life_is_complex() {
+        assert_eq!(classify("B3/S23"), Class::Complex);
+    }
+
+    #[test]
+    fn rules_without_birth_are_static() {
+        assert_eq!(classify("B/S012345678"), Class::Static);
+        assert_eq!(classify("B/S"), Class::Dies);
+    }
+
+    #[test]
+    fn rules_that_explode_are_chaotic() {
+        assert_eq!(classify("B1/S1"), Class::Chaotic);
+        assert_eq!(classify("B2/S"), Class::Chaotic);
+    }
+}
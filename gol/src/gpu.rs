@@ -1,10 +1,21 @@
 use std::cmp;
-use std::{sync::Arc};
+use std::sync::Arc;
 
-use anyhow::Result;
-use wgpu::{Surface, Device, Queue, SurfaceConfiguration};
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Context;
+use anyhow::{Result, bail};
+use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
 use winit::window::Window;
 
+/// Which GPU to use and how frames are presented; wgpu chooses whatever is left as `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuOptions {
+    pub backends: Option<wgpu::Backends>,
+    /// Part of the adapter's name, matched ignoring case.
+    pub adapter: Option<String>,
+    pub present_mode: Option<wgpu::PresentMode>,
+}
+
 pub struct GpuContext {
     pub device: Device,
     pub queue: Queue,
@@ -22,7 +33,7 @@ impl SurfaceManager {
     pub fn is_configured(&self) -> bool {
         self.is_configured
     }
-    
+
     pub fn format(&self) -> wgpu::TextureFormat {
         self.config.format
     }
@@ -38,12 +49,14 @@ impl SurfaceManager {
 }
 
 impl GpuContext {
-    pub async fn new(window: Arc<Window>) -> Result<Self> {
+    #[allow(unused_variables)]
+    pub async fn new(window: Arc<Window>, options: &GpuOptions) -> Result<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let backends = options.backends.unwrap_or(wgpu::Backends::PRIMARY);
+        #[cfg(target_arch = "wasm32")]
+        let backends = wgpu::Backends::BROWSER_WEBGPU | wgpu::Backends::GL;
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::Backends::PRIMARY,
-            #[cfg(target_arch = "wasm32")]
-            backends: wgpu::Backends::BROWSER_WEBGPU | wgpu::Backends::GL,
+            backends,
             ..Default::default()
         });
 
@@ -51,13 +64,25 @@ impl GpuContext {
 
         let surface = instance.create_surface(window.clone())?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let named = match &options.adapter {
+            Some(name) => Some(find_adapter(&instance, backends, name, Some(&surface))?),
+            None => None,
+        };
+        #[cfg(target_arch = "wasm32")]
+        let named = None;
+        let adapter = match named {
+            Some(adapter) => adapter,
+            None => {
+                instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::PowerPreference::default(),
+                        compatible_surface: Some(&surface),
+                        force_fallback_adapter: false,
+                    })
+                    .await?
+            }
+        };
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -71,25 +96,13 @@ impl GpuContext {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
+            present_mode: present_mode(options.present_mode, &surface_caps.present_modes),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
 
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: if cfg!(target_arch = "wasm32") {
-                    wgpu::Limits::downlevel_defaults()
-                } else {
-                    wgpu::Limits::default()
-                },
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off, // Trace path
-            })
-            .await?;
+        let (device, queue) = request_device(&adapter).await?;
 
         Ok(Self {
             device,
@@ -112,3 +125,140 @@ impl GpuContext {
         }
     }
 }
+
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(Device, Queue)> {
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_defaults()
+            } else {
+                wgpu::Limits::default()
+            },
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off, // Trace path
+        })
+        .await?;
+
+    Ok((device, queue))
+}
+
+/// A device without a window, for batch work such as soup searches.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct HeadlessContext {
+    pub device: Device,
+    pub queue: Queue,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HeadlessContext {
+    /// Picks any adapter, including software ones, so batch runs work on machines without a
+    /// GPU. `WGPU_BACKEND` and `WGPU_ADAPTER_NAME` narrow the choice.
+    pub async fn new() -> Result<Self> {
+        Self::with_options(&GpuOptions::default()).await
+    }
+
+    /// Like `new`, but the options' backends and adapter take precedence over the environment.
+    pub async fn with_options(options: &GpuOptions) -> Result<Self> {
+        let instance = wgpu::Instance::new(
+            &wgpu::InstanceDescriptor {
+                backends: wgpu::Backends::all(),
+                ..Default::default()
+            }
+            .with_env(),
+        );
+        let backends = options.backends.unwrap_or(wgpu::Backends::all());
+
+        let adapter = match &options.adapter {
+            Some(name) => find_adapter(&instance, backends, name, None)?,
+            None if options.backends.is_some() => {
+                let adapter = instance.enumerate_adapters(backends).into_iter().next();
+                adapter.context("no adapter for the chosen backends")?
+            }
+            None => wgpu::util::initialize_adapter_from_env_or_default(&instance, None).await?,
+        };
+        log::info!("Using adapter {:?}", adapter.get_info());
+
+        let (device, queue) = request_device(&adapter).await?;
+
+        Ok(Self { device, queue })
+    }
+}
+
+/// The first adapter of `backends` whose name contains `name`, ignoring case, that can draw to
+/// `surface` if there is one.
+#[cfg(not(target_arch = "wasm32"))]
+fn find_adapter(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    name: &str,
+    surface: Option<&Surface>,
+) -> Result<wgpu::Adapter> {
+    let adapters = instance.enumerate_adapters(backends);
+    let names: Vec<String> = adapters
+        .iter()
+        .map(|adapter| adapter.get_info().name)
+        .collect();
+    adapters
+        .into_iter()
+        .find(|adapter| {
+            let info = adapter.get_info();
+            info.name.to_lowercase().contains(&name.to_lowercase())
+                && surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+        })
+        .with_context(|| format!("no adapter named {name:?}; there are {names:?}"))
+}
+
+/// `wanted` if the surface supports it, or else the first mode it supports.
+fn present_mode(
+    wanted: Option<wgpu::PresentMode>,
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    use wgpu::PresentMode::{AutoNoVsync, AutoVsync};
+    match wanted {
+        Some(mode @ (AutoVsync | AutoNoVsync)) => mode,
+        Some(mode) if supported.contains(&mode) => mode,
+        Some(mode) => {
+            log::warn!(
+                "The {mode:?} present mode is not supported; using {:?}",
+                supported[0]
+            );
+            supported[0]
+        }
+        None => supported[0],
+    }
+}
+
+/// Parses a present mode written in kebab case, such as `auto-vsync` or `mailbox`.
+pub fn parse_present_mode(value: &str) -> Result<wgpu::PresentMode> {
+    use wgpu::PresentMode;
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "auto-vsync" => PresentMode::AutoVsync,
+        "auto-no-vsync" => PresentMode::AutoNoVsync,
+        "fifo" => PresentMode::Fifo,
+        "fifo-relaxed" => PresentMode::FifoRelaxed,
+        "immediate" => PresentMode::Immediate,
+        "mailbox" => PresentMode::Mailbox,
+        _ => bail!(
+            "invalid present mode {value:?}, expected auto-vsync, auto-no-vsync, fifo, \
+             fifo-relaxed, immediate or mailbox"
+        ),
+    })
+}
+
+/// Parses a comma-separated list of backends, named as in `WGPU_BACKEND`.
+pub fn parse_backends(value: &str) -> Result<wgpu::Backends> {
+    let mut backends = wgpu::Backends::empty();
+    for name in value.split(',') {
+        backends |= match name.trim().to_ascii_lowercase().as_str() {
+            "vulkan" | "vk" => wgpu::Backends::VULKAN,
+            "dx12" | "d3d12" => wgpu::Backends::DX12,
+            "metal" | "mtl" => wgpu::Backends::METAL,
+            "opengl" | "gles" | "gl" => wgpu::Backends::GL,
+            "webgpu" => wgpu::Backends::BROWSER_WEBGPU,
+            _ => bail!("invalid backend {name:?}, expected vulkan, dx12, metal, gl or webgpu"),
+        };
+    }
+    Ok(backends)
+}
@@ -0,0 +1,265 @@
+use rand::Rng;
+
+use crate::rule::{Rule, birth_colour};
+
+/// What lies beyond the edge of the grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(clap::ValueEnum))]
+pub enum Boundary {
+    /// Opposite edges are joined (toroidal topology).
+    #[default]
+    Torus,
+    /// Every cell outside the grid is permanently dead.
+    Dead,
+    /// The world extends forever; a grid on its own treats the cells beyond it as dead.
+    Unbounded,
+    /// The six faces of a cube side by side, each edge joined to the face it meets on the cube.
+    /// The grid is six times as wide as it is high; see `CUBE_FACES`.
+    Cube,
+}
+
+/// Where each face of a `Boundary::Cube` world sits on the cube, in the order the faces lie
+/// side by side in its grid: the outward normal, then the directions x and y increase across
+/// the face, as seen from outside.
+pub const CUBE_FACES: [[[i32; 3]; 3]; 6] = [
+    [[1, 0, 0], [0, 0, -1], [0, -1, 0]],
+    [[-1, 0, 0], [0, 0, 1], [0, -1, 0]],
+    [[0, 1, 0], [1, 0, 0], [0, 0, 1]],
+    [[0, -1, 0], [1, 0, 0], [0, 0, -1]],
+    [[0, 0, 1], [1, 0, 0], [0, -1, 0]],
+    [[0, 0, -1], [-1, 0, 0], [0, -1, 0]],
+];
+
+/// The cell (`dx`, `dy`) away from (`x`, `y`) in a cube world whose faces are `size` cells
+/// across, folding over the edge of the face where necessary. Stepping diagonally off a corner
+/// leads nowhere, as three faces meet there with no cell between them. This is the CPU reference
+/// for `cube_neighbour` in `conway.wgsl`.
+pub fn cube_neighbour(size: i32, (x, y): (i32, i32), (dx, dy): (i32, i32)) -> Option<(i32, i32)> {
+    let face = x / size;
+    let (fx, fy) = (x % size + dx, y + dy);
+    if (0..size).contains(&fx) && (0..size).contains(&fy) {
+        return Some((face * size + fx, fy));
+    }
+    // Positions from the centre of the face in half cells, so cell centres are odd
+    let (a, b) = (2 * fx + 1 - size, 2 * fy + 1 - size);
+    if a.abs() > size && b.abs() > size {
+        return None;
+    }
+    let [normal, right, down] = CUBE_FACES[face as usize];
+    // Fold the part beyond the edge down over it, onto the next face
+    let (weights, towards) = if a.abs() > size {
+        (
+            [size - (a.abs() - size), size * a.signum(), b],
+            right.map(|c| c * a.signum()),
+        )
+    } else {
+        (
+            [size - (b.abs() - size), a, size * b.signum()],
+            down.map(|c| c * b.signum()),
+        )
+    };
+    let point: [i32; 3] = std::array::from_fn(|i| {
+        weights[0] * normal[i] + weights[1] * right[i] + weights[2] * down[i]
+    });
+    let next = CUBE_FACES.iter().position(|f| f[0] == towards)? as i32;
+    let [_, right, down] = CUBE_FACES[next as usize];
+    let dot = |v: [i32; 3]| (0..3).map(|i| point[i] * v[i]).sum::<i32>();
+    Some((
+        next * size + (dot(right) + size - 1) / 2,
+        (dot(down) + size - 1) / 2,
+    ))
+}
+
+/// A rectangle of cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A rectangular block of cells held on the CPU, one byte per cell.
+///
+/// This is the exchange format between the GPU state textures, pattern files and the CPU
+/// reference stepper.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<u8>,
+}
+
+impl Grid {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; (width * height) as usize],
+        }
+    }
+
+    pub fn random(width: u32, height: u32, density: f32, rng: &mut impl Rng) -> Self {
+        let cells = (0..width * height)
+            .map(|_| u8::from(rng.random::<f32>() < density))
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.cells[self.index(x, y)] != 0
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, alive: bool) {
+        let i = self.index(x, y);
+        self.cells[i] = u8::from(alive);
+    }
+
+    /// The colour of a cell, from 1, or 0 if it is dead.
+    pub fn colour(&self, x: u32, y: u32) -> u8 {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn set_colour(&mut self, x: u32, y: u32, colour: u8) {
+        let i = self.index(x, y);
+        self.cells[i] = colour;
+    }
+
+    /// Gives every live cell `colour`, or a random one of the first `colours` colours if
+    /// `colour` is `None`.
+    pub fn recolour(&mut self, colour: Option<u8>, colours: u8, rng: &mut impl Rng) {
+        for cell in self.cells.iter_mut().filter(|c| **c != 0) {
+            *cell = colour.unwrap_or_else(|| rng.random_range(1..=colours.max(1)));
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&c| c != 0).count()
+    }
+
+    /// Coordinates of every live cell, row by row.
+    pub fn live_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c != 0)
+            .map(|(i, _)| (i as u32 % self.width, i as u32 / self.width))
+    }
+
+    /// The smallest rectangle containing every live cell, as (x, y, width, height).
+    pub fn bounding_box(&self) -> Option<(u32, u32, u32, u32)> {
+        let mut cells = self.live_cells();
+        // Cells come row by row, so the first one is on the top row.
+        let (x, top) = cells.next()?;
+        let (mut left, mut right, mut bottom) = (x, x, top);
+        for (x, y) in cells {
+            left = left.min(x);
+            right = right.max(x);
+            bottom = y;
+        }
+        Some((left, top, right - left + 1, bottom - top + 1))
+    }
+
+    /// Copies a `width` by `height` region starting at (`x`, `y`).
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Grid {
+        let mut out = Grid::new(width, height);
+        for row in 0..height {
+            let src = self.index(x, y + row);
+            let dst = out.index(0, row);
+            out.cells[dst..dst + width as usize]
+                .copy_from_slice(&self.cells[src..src + width as usize]);
+        }
+        out
+    }
+
+    /// Writes `other` into this grid with its top-left corner at (`x`, `y`), clipping to the
+    /// edges.
+    pub fn paste(&mut self, other: &Grid, x: u32, y: u32) {
+        for (ox, oy) in other.live_cells() {
+            let (tx, ty) = (x + ox, y + oy);
+            if tx < self.width && ty < self.height {
+                self.set_colour(tx, ty, other.colour(ox, oy));
+            }
+        }
+    }
+
+    /// Every cell switched between alive and dead.
+    pub fn invert(&self) -> Grid {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(|&c| u8::from(c == 0)).collect(),
+        }
+    }
+
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotate_clockwise(&self) -> Grid {
+        let mut out = Grid::new(self.height, self.width);
+        for (x, y) in self.live_cells() {
+            out.set_colour(self.height - 1 - y, x, self.colour(x, y));
+        }
+        out
+    }
+
+    /// The grid mirrored left to right.
+    pub fn flip_horizontal(&self) -> Grid {
+        let mut out = Grid::new(self.width, self.height);
+        for (x, y) in self.live_cells() {
+            out.set_colour(self.width - 1 - x, y, self.colour(x, y));
+        }
+        out
+    }
+
+    /// Advances the grid by one generation, colouring newborn cells after their parents. This
+    /// is the CPU reference for the compute shader.
+    pub fn step(&self, rule: &Rule, boundary: Boundary) -> Grid {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut next = Grid::new(self.width, self.height);
+        for y in 0..h {
+            for x in 0..w {
+                // Live neighbours of each colour
+                let mut counts = [0; 4];
+                for &(dx, dy) in rule.neighbourhood.offsets(x.into(), y.into()) {
+                    let (mut nx, mut ny) = (x + dx, y + dy);
+                    match boundary {
+                        Boundary::Cube => match cube_neighbour(h, (x, y), (dx, dy)) {
+                            Some(n) => (nx, ny) = n,
+                            None => continue,
+                        },
+                        Boundary::Torus => {
+                            nx = nx.rem_euclid(w);
+                            ny = ny.rem_euclid(h);
+                        }
+                        Boundary::Dead | Boundary::Unbounded
+                            if nx < 0 || ny < 0 || nx >= w || ny >= h =>
+                        {
+                            continue;
+                        }
+                        Boundary::Dead | Boundary::Unbounded => {}
+                    }
+                    let colour = self.colour(nx as u32, ny as u32);
+                    if colour != 0 {
+                        counts[usize::from(colour.min(4)) - 1] += 1;
+                    }
+                }
+                let current = self.colour(x as u32, y as u32);
+                let alive = rule.next(current != 0, counts.iter().sum());
+                let colour = match (alive, current) {
+                    (false, _) => 0,
+                    (true, 0) => birth_colour(counts),
+                    (true, current) => current,
+                };
+                next.set_colour(x as u32, y as u32, colour);
+            }
+        }
+        next
+    }
+}
@@ -0,0 +1,311 @@
+use crate::font;
+use crate::viewport::Viewport;
+
+/// Drawing aids shown over a flat world, each toggled by a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Guides {
+    /// Lines between square cells once they are large enough, thicker every 10 cells.
+    pub grid_lines: bool,
+    /// The whole of a bounded world in a corner, with the part in view outlined.
+    pub minimap: bool,
+}
+
+/// Longest side of the minimap as a fraction of the window's shorter side.
+const MINIMAP_FRACTION: f32 = 0.25;
+/// Pixels between the minimap and the corner of the window, and between the message and the
+/// top-left corner.
+const MINIMAP_MARGIN: f32 = 12.0;
+/// Screen pixels to each pixel of the font.
+const MESSAGE_SCALE: u32 = 2;
+/// Pixels of background around the message's text.
+const MESSAGE_PADDING: u32 = 6;
+/// The most characters on a line of the message, and the most lines; the texture holding the
+/// text is made this big.
+const MESSAGE_COLUMNS: u32 = 160;
+const MESSAGE_LINES: u32 = 4;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct GuidesUniform {
+    origin: [f32; 2],
+    cells_per_pixel: [f32; 2],
+    size: [f32; 2],
+    grid_lines: u32,
+    minimap: u32,
+    minimap_min: [f32; 2],
+    minimap_max: [f32; 2],
+    view_min: [f32; 2],
+    view_max: [f32; 2],
+    message_min: [f32; 2],
+    message_max: [f32; 2],
+    message_scale: f32,
+    message: u32,
+    _padding: [u32; 2],
+}
+
+/// The part of the window the minimap takes, in pixels: `(x, y, width, height)` in the
+/// bottom-right corner, shaped like the world.
+pub fn minimap_area(target: (u32, u32), world: (u32, u32)) -> [f32; 4] {
+    let longest = target.0.min(target.1) as f32 * MINIMAP_FRACTION;
+    let scale = longest / world.0.max(world.1).max(1) as f32;
+    let (width, height) = (world.0 as f32 * scale, world.1 as f32 * scale);
+    [
+        target.0 as f32 - MINIMAP_MARGIN - width,
+        target.1 as f32 - MINIMAP_MARGIN - height,
+        width,
+        height,
+    ]
+}
+
+/// Draws the guides over whatever is already in the target, with `guides.wgsl`, along with a
+/// message in the top-left corner, away from the minimap.
+pub struct GuideDisplay {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    message_texture: wgpu::Texture,
+    /// The text in the texture and the characters it was wrapped at, with its size in pixels.
+    message: Option<(String, usize)>,
+    message_size: (u32, u32),
+}
+
+impl GuideDisplay {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Guides Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("guides.wgsl").into()),
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Guides Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // The message's text
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Guides Uniform Buffer"),
+            size: size_of::<GuidesUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let message_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Message Texture"),
+            size: wgpu::Extent3d {
+                width: MESSAGE_COLUMNS * font::ADVANCE.0,
+                height: MESSAGE_LINES * font::ADVANCE.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Guides Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &message_texture.create_view(&Default::default()),
+                    ),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Guides Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Guides Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            message_texture,
+            message: None,
+            message_size: (0, 0),
+        }
+    }
+
+    /// Wraps `message` to fit a target `width` pixels across and writes it into the texture,
+    /// unless it is there already.
+    fn set_message(&mut self, queue: &wgpu::Queue, message: &str, width: u32) {
+        let room = width.saturating_sub(2 * (MINIMAP_MARGIN as u32 + MESSAGE_PADDING));
+        let columns = (room / (font::ADVANCE.0 * MESSAGE_SCALE)).clamp(1, MESSAGE_COLUMNS) as usize;
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(text, wrapped)| text == message && *wrapped == columns)
+        {
+            return;
+        }
+        let lines = font::wrap(message, columns, MESSAGE_LINES as usize);
+        let (pixels, width, height) = font::rasterize(&lines);
+        if width > 0 && height > 0 {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.message_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        self.message = Some((message.to_string(), columns));
+        self.message_size = (width, height);
+    }
+
+    /// Records a pass drawing the guides over a target `target_size` pixels across, showing
+    /// `viewport` of a world that fills it at zoom 1 with `grid_size` cells. `world` is the
+    /// size of a bounded world, which the grid lines stop at, and `minimap` is the view of it
+    /// drawn in `minimap_area`. `message` is drawn over everything else.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        target_size: (u32, u32),
+        grid_lines: bool,
+        viewport: &Viewport,
+        grid_size: (u32, u32),
+        world: Option<(u32, u32)>,
+        minimap: Option<&Viewport>,
+        message: Option<&str>,
+    ) {
+        let (width, height) = (target_size.0 as f32, target_size.1 as f32);
+        // Cells across the window
+        let span = (
+            grid_size.0 as f32 / viewport.zoom,
+            grid_size.1 as f32 / viewport.zoom,
+        );
+        let mut uniform = GuidesUniform {
+            origin: [
+                viewport.centre.0 - span.0 / 2.0,
+                viewport.centre.1 - span.1 / 2.0,
+            ],
+            cells_per_pixel: [span.0 / width, span.1 / height],
+            size: world.map_or([0.0; 2], |(x, y)| [x as f32, y as f32]),
+            grid_lines: grid_lines.into(),
+            minimap: 0,
+            minimap_min: [0.0; 2],
+            minimap_max: [0.0; 2],
+            view_min: [0.0; 2],
+            view_max: [0.0; 2],
+            message_min: [0.0; 2],
+            message_max: [0.0; 2],
+            message_scale: MESSAGE_SCALE as f32,
+            message: 0,
+            _padding: [0; 2],
+        };
+        if let (Some(minimap), Some(world)) = (minimap, world) {
+            let [x, y, w, h] = minimap_area(target_size, world);
+            // Where a world position is drawn in the minimap, which shows `world` at its zoom
+            let to_minimap = |(cx, cy): (f32, f32)| {
+                [
+                    x + ((cx - minimap.centre.0) * minimap.zoom / world.0 as f32 + 0.5) * w,
+                    y + ((cy - minimap.centre.1) * minimap.zoom / world.1 as f32 + 0.5) * h,
+                ]
+            };
+            uniform.minimap = 1;
+            uniform.minimap_min = [x, y];
+            uniform.minimap_max = [x + w, y + h];
+            uniform.view_min = to_minimap((uniform.origin[0], uniform.origin[1]));
+            uniform.view_max = to_minimap((uniform.origin[0] + span.0, uniform.origin[1] + span.1));
+        }
+        if let Some(message) = message {
+            self.set_message(queue, message, target_size.0);
+            let (text_width, text_height) = self.message_size;
+            let corner = MINIMAP_MARGIN + MESSAGE_PADDING as f32;
+            uniform.message = 1;
+            uniform.message_min = [corner; 2];
+            uniform.message_max = [
+                corner + (text_width * MESSAGE_SCALE) as f32,
+                corner + (text_height * MESSAGE_SCALE) as f32,
+            ];
+        }
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Guides Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
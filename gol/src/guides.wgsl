@@ -0,0 +1,109 @@
+// Drawing aids blended over a flat world: lines between square cells, and the frame of the
+// minimap with the part of the world in view outlined. Lines are shaded by their distance from
+// each pixel, so they stay smooth at any zoom. A message goes over them all.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // Create fullscreen triangle (covers whole screen)
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+struct Guides {
+    // World position, in cells, at the top-left corner of the window
+    origin: vec2<f32>,
+    // Cells across and down each pixel
+    cells_per_pixel: vec2<f32>,
+    // Cells across and down a bounded world, or 0 for an unbounded one
+    size: vec2<f32>,
+    // 1 = lines between cells
+    grid_lines: u32,
+    // 1 = the minimap's frame and outline
+    minimap: u32,
+    // Corners of the minimap and of the view outlined in it, in pixels
+    minimap_min: vec2<f32>,
+    minimap_max: vec2<f32>,
+    view_min: vec2<f32>,
+    view_max: vec2<f32>,
+    // Corners of the message's text, in pixels, and pixels to each texel of it
+    message_min: vec2<f32>,
+    message_max: vec2<f32>,
+    message_scale: f32,
+    // 1 = the message and its background
+    message: u32,
+    _padding: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> guides: Guides;
+// The message's text, 1 where a glyph covers a texel
+@group(0) @binding(1) var message_text: texture_2d<f32>;
+
+// Pixels of background around the message; matches `MESSAGE_PADDING` in guides.rs
+const MESSAGE_PADDING: f32 = 6.0;
+
+// How much of a pixel a line `width` pixels wide covers, `distance` pixels from its middle
+fn coverage(distance: f32, width: f32) -> f32 {
+    return clamp(width / 2.0 + 0.5 - distance, 0.0, 1.0);
+}
+
+// How much of a pixel the lines every `spacing` cells cover, along both axes
+fn lines(pos: vec2<f32>, spacing: f32, width: f32) -> f32 {
+    let distance = abs(fract(pos / spacing + 0.5) - 0.5) * spacing / guides.cells_per_pixel;
+    return max(coverage(distance.x, width), coverage(distance.y, width));
+}
+
+// How much of a pixel the outline of a rectangle covers
+fn outline(pixel: vec2<f32>, corner_min: vec2<f32>, corner_max: vec2<f32>, width: f32) -> f32 {
+    let inside = all(pixel >= corner_min - width) && all(pixel <= corner_max + width);
+    let edges = min(abs(pixel - corner_min), abs(pixel - corner_max));
+    return select(0.0, coverage(min(edges.x, edges.y), width), inside);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = in.clip_position.xy;
+
+    if (guides.message != 0u
+        && all(pixel >= guides.message_min - MESSAGE_PADDING)
+        && all(pixel < guides.message_max + MESSAGE_PADDING)) {
+        let scaled = floor((pixel - guides.message_min) / guides.message_scale);
+        let texel = vec2<i32>(max(scaled, vec2<f32>(0.0)));
+        let inside = all(pixel >= guides.message_min) && all(pixel < guides.message_max);
+        let text = select(0.0, textureLoad(message_text, texel, 0).r, inside);
+        return mix(vec4<f32>(0.35, 0.05, 0.05, 0.85), vec4<f32>(1.0, 1.0, 1.0, 1.0), text);
+    }
+
+    if (guides.minimap != 0u
+        && all(pixel >= guides.minimap_min - 1.0) && all(pixel <= guides.minimap_max + 1.0)) {
+        // The view, clipped to the minimap, then the minimap's frame
+        let view = outline(pixel, guides.view_min, guides.view_max, 1.5);
+        var color = vec4<f32>(1.0, 0.8, 0.2, 0.9 * view);
+        let frame = outline(pixel, guides.minimap_min, guides.minimap_max, 1.0);
+        if (frame > color.a) {
+            color = vec4<f32>(0.6, 0.6, 0.6, frame);
+        }
+        return color;
+    }
+
+    if (guides.grid_lines == 0u) {
+        return vec4<f32>(0.0);
+    }
+    let pos = guides.origin + pixel * guides.cells_per_pixel;
+    let outside = any(pos < vec2<f32>(0.0)) || any(pos > guides.size);
+    if (any(guides.size > vec2<f32>(0.0)) && outside) {
+        return vec4<f32>(0.0);
+    }
+    // Lines between cells fade in once cells are 4 pixels across, and every tenth line once
+    // ten cells are 20 pixels across, so that lines never crowd together into a moiré
+    let pixels_per_cell = 1.0 / max(guides.cells_per_pixel.x, guides.cells_per_pixel.y);
+    let fine = lines(pos, 1.0, 1.0) * 0.3 * smoothstep(4.0, 8.0, pixels_per_cell);
+    let coarse = lines(pos, 10.0, 2.0) * 0.5 * smoothstep(2.0, 4.0, pixels_per_cell);
+    return vec4<f32>(vec3<f32>(0.5), max(fine, coarse));
+}
@@ -0,0 +1,613 @@
+//! HashLife: Gosper's algorithm for jumping a pattern forward 2^N generations at a time.
+//!
+//! The universe is a quadtree whose nodes are canonical: each distinct block of cells is stored
+//! once, identified by its four quadrants. A node of level `k` covers 2^k × 2^k cells and
+//! remembers its result, the centre half of the block advanced by 2^step generations, so a
+//! pattern that repeats itself in space or time is only ever computed once. The memo and the
+//! nodes it refers to are dropped by a mark-and-compact collection once the table grows past
+//! its limit.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+
+use crate::conway::ConwayCompute;
+use crate::grid::Grid;
+use crate::pattern::Pattern;
+use crate::rule::{Neighbourhood, Rule};
+
+pub(crate) type NodeId = u32;
+
+pub(crate) const DEAD: NodeId = 0;
+pub(crate) const ALIVE: NodeId = 1;
+const NONE: NodeId = NodeId::MAX;
+
+/// Largest step exponent, leaving room for the universe to grow around a pattern spreading for
+/// that long while positions still fit in an `i64`.
+pub const MAX_STEP: u32 = 48;
+/// Nodes kept before garbage is collected, unless changed with `set_node_limit`.
+const DEFAULT_NODE_LIMIT: usize = 1 << 22;
+/// Level of the smallest root, an 8 × 8 block.
+const MIN_LEVEL: u8 = 3;
+/// Level of the largest root, the deepest whose cell positions all fit an `i64`.
+pub const MAX_LEVEL: u8 = 62;
+/// Largest grid `to_grid` will build.
+const MAX_EXPORT_CELLS: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// North-west, north-east, south-west and south-east quadrants; unused by the two leaves.
+    children: [NodeId; 4],
+    level: u8,
+    /// Saturates rather than overflowing, so only ever compared with zero.
+    population: u64,
+    /// The centre half advanced by 2^min(step, level - 2) generations, once computed.
+    result: NodeId,
+}
+
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    table: HashMap<[NodeId; 4], NodeId>,
+    /// The empty node of each level.
+    empty: Vec<NodeId>,
+    root: NodeId,
+    /// World position of the root's top-left cell.
+    origin: (i64, i64),
+    step: u32,
+    generation: u64,
+    node_limit: usize,
+}
+
+impl HashLife {
+    /// Creates an empty universe. Rules where cells are born with no neighbours (B0) would fill
+    /// infinite space, so they are rejected.
+    pub fn new(rule: Rule) -> Result<Self> {
+        if rule.birth & 1 != 0 {
+            bail!("HashLife cannot run B0 rules such as {rule}");
+        }
+        // Triangular cells reach two columns away, beyond the cells a 4 × 4 node surrounds its
+        // centre with
+        if rule.neighbourhood == Neighbourhood::Triangular {
+            bail!("HashLife cannot run triangular rules such as {rule}");
+        }
+        let leaf = |population| Node {
+            children: [NONE; 4],
+            level: 0,
+            population,
+            result: NONE,
+        };
+        let mut life = Self {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            table: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (0, 0),
+            step: 0,
+            generation: 0,
+            node_limit: DEFAULT_NODE_LIMIT,
+        };
+        life.root = life.empty(MIN_LEVEL);
+        Ok(life)
+    }
+
+    /// Creates a universe holding `grid` with its top-left corner at `origin`.
+    pub fn from_grid(rule: Rule, grid: &Grid, origin: (i64, i64)) -> Result<Self> {
+        let mut life = Self::new(rule)?;
+        let size = grid.width.max(grid.height).max(1);
+        let level = (size.next_power_of_two().trailing_zeros() as u8).max(MIN_LEVEL);
+        life.root = life.build(grid, 0, 0, level);
+        life.origin = origin;
+        Ok(life)
+    }
+
+    /// Creates a universe holding the pattern, in its own rule or Conway's Life.
+    pub fn from_pattern(pattern: &Pattern, origin: (i64, i64)) -> Result<Self> {
+        Self::from_grid(pattern.rule.unwrap_or_default(), &pattern.grid, origin)
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Each `step` advances 2^`step_exponent` generations.
+    pub fn step_exponent(&self) -> u32 {
+        self.step
+    }
+
+    /// Changes how far each `step` jumps, clamped to `MAX_STEP`. Memoised results are for the
+    /// old step, so they are forgotten.
+    pub fn set_step_exponent(&mut self, step: u32) {
+        let step = step.min(MAX_STEP);
+        if step != self.step {
+            self.step = step;
+            for node in &mut self.nodes {
+                node.result = NONE;
+            }
+        }
+    }
+
+    /// Number of nodes in the table, live or not yet collected.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Collects garbage whenever a step leaves more than `limit` nodes.
+    pub fn set_node_limit(&mut self, limit: usize) {
+        self.node_limit = limit;
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Advances the universe 2^`step_exponent` generations, unless the pattern has spread too
+    /// far for the universe to grow around it.
+    pub fn step(&mut self) -> Result<()> {
+        // A result only holds the centre half of the root, and nothing travels faster than a
+        // cell per generation, so the pattern must be at least 2^step cells inside that half:
+        // centred in a root of level step + 2, then surrounded by as much space again
+        while self.level(self.root) < self.step as u8 + 2 || !self.centred(self.root) {
+            self.expand()?;
+        }
+        self.expand()?;
+
+        let quarter = 1i64 << (self.level(self.root) - 2);
+        self.root = self.result(self.root);
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+        self.generation = self.generation.saturating_add(1 << self.step);
+
+        if self.nodes.len() > self.node_limit {
+            self.collect_garbage();
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let size = 1i64 << self.level(self.root);
+        let (Some(x), Some(y)) = (x.checked_sub(self.origin.0), y.checked_sub(self.origin.1))
+        else {
+            return false;
+        };
+        if x < 0 || y < 0 || x >= size || y >= size {
+            return false;
+        }
+        self.cell(self.root, x, y)
+    }
+
+    /// Sets a cell, growing the universe to hold it if need be. Fails for live cells too far
+    /// out for it to grow to.
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) -> Result<()> {
+        loop {
+            let size = 1i64 << self.level(self.root);
+            if let (Some(local_x), Some(local_y)) =
+                (x.checked_sub(self.origin.0), y.checked_sub(self.origin.1))
+                && (0..size).contains(&local_x)
+                && (0..size).contains(&local_y)
+            {
+                self.root = self.set_cell(self.root, local_x, local_y, alive);
+                return Ok(());
+            }
+            if !alive {
+                return Ok(());
+            }
+            self.expand()?;
+        }
+    }
+
+    /// Writes `grid` into the universe with its top-left corner at `origin`, overwriting the
+    /// cells beneath it.
+    pub fn stamp(&mut self, grid: &Grid, origin: (i64, i64)) -> Result<()> {
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let (cx, cy) = (origin.0 + i64::from(x), origin.1 + i64::from(y));
+                let alive = grid.get(x, y);
+                if alive != self.get(cx, cy) {
+                    self.set(cx, cy, alive)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the cells of a window of the universe, whose top-left corner is at `origin`.
+    pub fn window(&self, origin: (i64, i64), width: u32, height: u32) -> Grid {
+        let mut grid = Grid::new(width, height);
+        let window = (origin, (i64::from(width), i64::from(height)));
+        self.fill(&mut grid, window, self.root, self.origin);
+        grid
+    }
+
+    /// Writes the window at `origin` the size of `conway`'s grid into its current texture,
+    /// for the display to draw.
+    pub fn upload_window(&self, queue: &wgpu::Queue, conway: &ConwayCompute, origin: (i64, i64)) {
+        let (width, height) = conway.size();
+        conway.write(queue, &self.window(origin, width, height));
+    }
+
+    /// The world position of the top-left corner of the smallest rectangle holding every live
+    /// cell, and its size, or `None` if there are none.
+    pub fn extent(&self) -> Option<((i64, i64), (u64, u64))> {
+        let (left, top, right, bottom) = self.bounds(self.root, self.origin)?;
+        let size = ((right - left) as u64 + 1, (bottom - top) as u64 + 1);
+        Some(((left, top), size))
+    }
+
+    /// The smallest grid holding every live cell, and the world position of its top-left
+    /// corner.
+    pub fn to_grid(&self) -> Result<(Grid, (i64, i64))> {
+        let Some(((left, top), (width, height))) = self.extent() else {
+            return Ok((Grid::new(0, 0), (0, 0)));
+        };
+        if width.saturating_mul(height) > MAX_EXPORT_CELLS {
+            bail!("the pattern is too large to export ({width} × {height} cells)");
+        }
+        Ok((
+            self.window((left, top), width as u32, height as u32),
+            (left, top),
+        ))
+    }
+
+    /// The live cells as a pattern in this universe's rule, and the world position of its
+    /// top-left corner.
+    pub fn to_pattern(&self) -> Result<(Pattern, (i64, i64))> {
+        let (grid, origin) = self.to_grid()?;
+        let pattern = Pattern {
+            name: None,
+            rule: Some(self.rule),
+            grid,
+        };
+        Ok((pattern, origin))
+    }
+
+    /// Drops every node unreachable from the root, keeping memoised results where possible.
+    pub fn collect_garbage(&mut self) {
+        self.compact(true);
+        if self.nodes.len() > self.node_limit / 2 {
+            // The results alone are holding too much; start the memo again
+            self.compact(false);
+        }
+    }
+
+    pub(crate) fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Replaces the universe with the node `root`, its top-left cell at `origin`, growing it
+    /// to the smallest root level if need be.
+    pub(crate) fn set_root(&mut self, root: NodeId, origin: (i64, i64)) -> Result<()> {
+        self.root = root;
+        self.origin = origin;
+        while self.level(self.root) < MIN_LEVEL {
+            self.expand()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    pub(crate) fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    pub(crate) fn node_population(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    /// The canonical node with the given quadrants.
+    pub(crate) fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        if let Some(&id) = self.table.get(&children) {
+            return id;
+        }
+        let population = children.iter().fold(0u64, |total, &child| {
+            total.saturating_add(self.nodes[child as usize].population)
+        });
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.level(nw) + 1,
+            population,
+            result: NONE,
+        });
+        self.table.insert(children, id);
+        id
+    }
+
+    pub(crate) fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = self.empty[self.empty.len() - 1];
+            let bigger = self.join(e, e, e, e);
+            self.empty.push(bigger);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Surrounds the root with empty space, doubling its size.
+    fn expand(&mut self) -> Result<()> {
+        let level = self.level(self.root);
+        if level >= MAX_LEVEL {
+            bail!("the universe cannot grow beyond 2^{MAX_LEVEL} cells across");
+        }
+        let e = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(self.root);
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+        let half = 1i64 << (level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+        Ok(())
+    }
+
+    /// Whether every live cell is in the centre half of the node.
+    fn centred(&self, id: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.children(id);
+        // Index of the grandchild each quadrant shares with the centre
+        [(nw, 3), (ne, 2), (sw, 1), (se, 0)]
+            .into_iter()
+            .all(|(quadrant, inner)| {
+                self.children(quadrant)
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &g)| i == inner || self.nodes[g as usize].population == 0)
+            })
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join(
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        )
+    }
+
+    /// The node straddling the boundary between two side-by-side nodes.
+    fn centre_horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let ([_, wne, _, wse], [enw, _, esw, _]) = (self.children(w), self.children(e));
+        self.join(wne, enw, wse, esw)
+    }
+
+    /// The node straddling the boundary between two stacked nodes.
+    fn centre_vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let ([_, _, nsw, nse], [snw, sne, _, _]) = (self.children(n), self.children(s));
+        self.join(nsw, nse, snw, sne)
+    }
+
+    /// The centre half of a node of level 2 or more, advanced 2^min(step, level - 2)
+    /// generations.
+    fn result(&mut self, id: NodeId) -> NodeId {
+        let node = self.nodes[id as usize];
+        if node.result != NONE {
+            return node.result;
+        }
+
+        let result = if node.population == 0 {
+            self.empty(node.level - 1)
+        } else if node.level == 2 {
+            self.base_case(id)
+        } else {
+            let [nw, ne, sw, se] = node.children;
+            let n01 = self.centre_horizontal(nw, ne);
+            let n10 = self.centre_vertical(nw, sw);
+            let n11 = self.centre(id);
+            let n12 = self.centre_vertical(ne, se);
+            let n21 = self.centre_horizontal(sw, se);
+
+            // Nine overlapping nodes of level - 1, advanced by half the step if the whole step
+            // is wanted, otherwise not at all
+            let full = self.step >= u32::from(node.level - 2);
+            let mut r = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+            for n in &mut r {
+                *n = if full {
+                    self.result(*n)
+                } else {
+                    self.centre(*n)
+                };
+            }
+
+            // Four overlapping nodes of level - 1, each advanced by the remaining half
+            let nw = self.join(r[0], r[1], r[3], r[4]);
+            let ne = self.join(r[1], r[2], r[4], r[5]);
+            let sw = self.join(r[3], r[4], r[6], r[7]);
+            let se = self.join(r[4], r[5], r[7], r[8]);
+            let nw = self.result(nw);
+            let ne = self.result(ne);
+            let sw = self.result(sw);
+            let se = self.result(se);
+            self.join(nw, ne, sw, se)
+        };
+
+        self.nodes[id as usize].result = result;
+        result
+    }
+
+    /// One generation of the centre 2 × 2 cells of a 4 × 4 node.
+    fn base_case(&mut self, id: NodeId) -> NodeId {
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + i as i64 % 2, 1 + i as i64 / 2);
+            let mut neighbours = 0;
+            for &(dx, dy) in self.rule.neighbourhood.offsets(x, y) {
+                if self.cell(id, x + i64::from(dx), y + i64::from(dy)) {
+                    neighbours += 1;
+                }
+            }
+            if self.rule.next(self.cell(id, x, y), neighbours) {
+                *cell = ALIVE;
+            }
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    /// The cell at a position within a node.
+    pub(crate) fn cell(&self, mut id: NodeId, mut x: i64, mut y: i64) -> bool {
+        loop {
+            let node = &self.nodes[id as usize];
+            if node.population == 0 {
+                return false;
+            }
+            if node.level == 0 {
+                return true;
+            }
+            let half = 1i64 << (node.level - 1);
+            let quadrant = usize::from(x >= half) + 2 * usize::from(y >= half);
+            id = node.children[quadrant];
+            x %= half;
+            y %= half;
+        }
+    }
+
+    /// A copy of the node with one cell changed.
+    fn set_cell(&mut self, id: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1i64 << (level - 1);
+        let quadrant = usize::from(x >= half) + 2 * usize::from(y >= half);
+        let mut children = self.children(id);
+        children[quadrant] = self.set_cell(children[quadrant], x % half, y % half, alive);
+        self.join(children[0], children[1], children[2], children[3])
+    }
+
+    /// The node holding the cells of `grid` in a square of the given level at (`x`, `y`).
+    fn build(&mut self, grid: &Grid, x: u32, y: u32, level: u8) -> NodeId {
+        if x >= grid.width || y >= grid.height {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if grid.get(x, y) { ALIVE } else { DEAD };
+        }
+        let half = 1u32 << (level - 1);
+        let nw = self.build(grid, x, y, level - 1);
+        let ne = self.build(grid, x.saturating_add(half), y, level - 1);
+        let sw = self.build(grid, x, y.saturating_add(half), level - 1);
+        let se = self.build(
+            grid,
+            x.saturating_add(half),
+            y.saturating_add(half),
+            level - 1,
+        );
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Copies the live cells of a node at `position` that fall inside `window` into `grid`.
+    fn fill(
+        &self,
+        grid: &mut Grid,
+        window: ((i64, i64), (i64, i64)),
+        id: NodeId,
+        position: (i64, i64),
+    ) {
+        let node = &self.nodes[id as usize];
+        let size = 1i64 << node.level;
+        let ((left, top), (width, height)) = window;
+        if node.population == 0
+            || position.0 >= left + width
+            || position.1 >= top + height
+            || position.0 + size <= left
+            || position.1 + size <= top
+        {
+            return;
+        }
+        if node.level == 0 {
+            grid.set((position.0 - left) as u32, (position.1 - top) as u32, true);
+            return;
+        }
+        let half = size / 2;
+        for (i, &child) in node.children.iter().enumerate() {
+            let offset = (half * (i as i64 % 2), half * (i as i64 / 2));
+            let child_position = (position.0 + offset.0, position.1 + offset.1);
+            self.fill(grid, window, child, child_position);
+        }
+    }
+
+    /// The leftmost, topmost, rightmost and bottommost live cells of a node at `position`.
+    fn bounds(&self, id: NodeId, position: (i64, i64)) -> Option<(i64, i64, i64, i64)> {
+        let node = &self.nodes[id as usize];
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some((position.0, position.1, position.0, position.1));
+        }
+        let half = 1i64 << (node.level - 1);
+        node.children
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &child)| {
+                let offset = (half * (i as i64 % 2), half * (i as i64 / 2));
+                self.bounds(child, (position.0 + offset.0, position.1 + offset.1))
+            })
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    }
+
+    /// Rebuilds the table with only the nodes reachable from the root, and from their results
+    /// if `keep_results` is set. Children are always created before their parents, so one pass
+    /// in index order renumbers everything.
+    fn compact(&mut self, keep_results: bool) {
+        let mut marked = vec![false; self.nodes.len()];
+        marked[DEAD as usize] = true;
+        marked[ALIVE as usize] = true;
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if marked[id as usize] {
+                continue;
+            }
+            marked[id as usize] = true;
+            let node = &self.nodes[id as usize];
+            stack.extend(node.children);
+            if keep_results && node.result != NONE {
+                stack.push(node.result);
+            }
+        }
+
+        let mut renumbered = vec![NONE; self.nodes.len()];
+        let mut nodes = Vec::with_capacity(marked.iter().filter(|&&m| m).count());
+        for (id, node) in self.nodes.iter().enumerate() {
+            if !marked[id] {
+                continue;
+            }
+            renumbered[id] = nodes.len() as NodeId;
+            let mut node = *node;
+            if node.level > 0 {
+                node.children = node.children.map(|c| renumbered[c as usize]);
+            }
+            nodes.push(node);
+        }
+        for node in &mut nodes {
+            if node.result != NONE {
+                node.result = if keep_results {
+                    renumbered[node.result as usize]
+                } else {
+                    NONE
+                };
+            }
+        }
+
+        self.table = nodes
+            .iter()
+            .enumerate()
+            .skip(2)
+            .map(|(id, node)| (node.children, id as NodeId))
+            .collect();
+        self.nodes = nodes;
+        self.root = renumbered[self.root as usize];
+        self.empty.truncate(1);
+    }
+}
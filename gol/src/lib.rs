@@ -1,9 +1,53 @@
+mod ants;
+pub mod apgcode;
 mod app;
-mod conway;
-mod gpu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod census;
+mod chunk_display;
+pub mod chunks;
+mod clipboard;
+pub mod conway;
+mod display;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod explore;
+mod font;
+pub mod gpu;
+pub mod grid;
+mod guides;
+pub mod hashlife;
+pub mod library;
+pub mod life3d;
+pub mod macrocell;
+pub mod options;
+mod overlay;
+pub mod pattern;
+pub mod picture;
+mod placement;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod recording;
+mod remote;
 mod renderer;
+pub mod rule;
+pub mod sand;
+pub mod scheme;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script;
+mod selection;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serve;
+pub mod session;
+pub mod shared;
+mod surface;
+pub mod tiled;
+mod tiled_display;
+pub mod turmite;
+pub mod viewport;
+mod voxels;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
 use app::App;
+use options::Options;
 use winit::event_loop::EventLoop;
 
 #[cfg(target_arch = "wasm32")]
@@ -28,12 +72,29 @@ pub fn run() -> anyhow::Result<()> {
         console_log::init_with_level(log::Level::Info).unwrap_throw();
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    let options = Options::default();
+    #[cfg(target_arch = "wasm32")]
+    let options = web::location_options();
+    open(options)
+}
+
+/// Opens the window as `options` say, once logging is set up.
+pub fn open(options: Options) -> anyhow::Result<()> {
     let event_loop = EventLoop::with_user_event().build()?;
-    let mut app = App::new(
-        #[cfg(target_arch = "wasm32")]
-        &event_loop,
-    );
-    event_loop.run_app(&mut app)?;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut app = App::new(&event_loop, options);
+        event_loop.run_app(&mut app)?;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        // Returns to the page straight away so that its script can drive the simulator.
+        use winit::platform::web::EventLoopExtWebSys;
+        web::set_proxy(event_loop.create_proxy());
+        let app = App::new(&event_loop, options);
+        event_loop.spawn_app(app);
+    }
 
     Ok(())
 }
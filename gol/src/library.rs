@@ -0,0 +1,23 @@
+use crate::pattern::Pattern;
+
+/// Classic patterns compiled into the binary, in the order the number keys select them.
+const LIBRARY: &[&str] = &[
+    include_str!("../patterns/glider.rle"),
+    include_str!("../patterns/lwss.rle"),
+    include_str!("../patterns/mwss.rle"),
+    include_str!("../patterns/hwss.rle"),
+    include_str!("../patterns/pulsar.rle"),
+    include_str!("../patterns/pentadecathlon.rle"),
+    include_str!("../patterns/gosper-glider-gun.rle"),
+    include_str!("../patterns/r-pentomino.rle"),
+    include_str!("../patterns/acorn.rle"),
+    include_str!("../patterns/diehard.rle"),
+];
+
+/// Parses the built-in pattern catalogue.
+pub fn patterns() -> Vec<Pattern> {
+    LIBRARY
+        .iter()
+        .map(|rle| Pattern::from_rle(rle).expect("built-in patterns are valid RLE"))
+        .collect()
+}
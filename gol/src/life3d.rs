@@ -0,0 +1,442 @@
+//! Life-like automata on a cube of cells, each with the 26 neighbours that share a face, an edge
+//! or a corner with it. Opposite faces of the cube are joined, as a torus is in 2D.
+//!
+//! Cells are kept in storage buffers rather than 3D storage textures, which not every backend
+//! can write.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use rand::Rng;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 4;
+
+/// The rules the app cycles through, each with the density of the soup that grows into
+/// something worth watching.
+pub const RULES_3D: [(Rule3d, f32); 2] = [(Rule3d::BAYS_4555, 0.2), (Rule3d::CLOUDS, 0.5)];
+
+/// Which neighbour counts, 0 to 26, give birth to a cell or keep it alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule3d {
+    /// Bit n set = born with n neighbours
+    pub birth: u32,
+    /// Bit n set = survives with n neighbours
+    pub survive: u32,
+}
+
+impl Rule3d {
+    /// Carter Bays' 4555: born with 5 neighbours, survives with 4 or 5.
+    pub const BAYS_4555: Rule3d = Rule3d {
+        birth: 1 << 5,
+        survive: (1 << 4) | (1 << 5),
+    };
+    /// B13-26/S13-26, where dense soups settle into blobs.
+    pub const CLOUDS: Rule3d = Rule3d {
+        birth: (1 << 27) - (1 << 13),
+        survive: (1 << 27) - (1 << 13),
+    };
+
+    pub fn next(&self, alive: bool, neighbours: u32) -> bool {
+        let mask = if alive { self.survive } else { self.birth };
+        mask & (1 << neighbours) != 0
+    }
+}
+
+/// A mask of neighbour counts written as numbers and ranges separated by commas, as `4,6-8`.
+fn parse_counts(counts: &str) -> Result<u32> {
+    let mut mask = 0;
+    for part in counts.split(',').filter(|part| !part.is_empty()) {
+        let (low, high) = part.split_once('-').unwrap_or((part, part));
+        let (low, high): (u32, u32) = match (low.parse(), high.parse()) {
+            (Ok(low), Ok(high)) if low <= high && high <= 26 => (low, high),
+            _ => bail!("invalid neighbour counts {part:?} in rule"),
+        };
+        for n in low..=high {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for Rule3d {
+    type Err = anyhow::Error;
+
+    /// Parses `B13-26/S13-26` (any case, `/` optional), or Bays' four digits such as `4555`: the
+    /// fewest and most neighbours a cell survives with, then the fewest and most it is born with.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+        if let Some(rest) = lower.strip_prefix('b') {
+            let Some((birth, survive)) = rest.split_once('s') else {
+                bail!("rule {s:?} is missing the S part");
+            };
+            return Ok(Rule3d {
+                birth: parse_counts(birth.trim_end_matches('/'))?,
+                survive: parse_counts(survive)?,
+            });
+        }
+        match lower
+            .chars()
+            .map(|c| c.to_digit(10))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(digits) if digits.len() == 4 => {
+                let range = |low: u32, high: u32| (low..=high).fold(0, |mask, n| mask | 1 << n);
+                Ok(Rule3d {
+                    birth: range(digits[2], digits[3]),
+                    survive: range(digits[0], digits[1]),
+                })
+            }
+            _ => bail!("unrecognised 3D rule {s:?}, expected e.g. 4555 or B13-26/S13-26"),
+        }
+    }
+}
+
+impl fmt::Display for Rule3d {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Runs of counts as ranges, so clouds reads B13-26/S13-26
+        let counts = |mask: u32| -> String {
+            let mut parts = Vec::new();
+            let mut n = 0;
+            while n <= 26 {
+                if mask & (1 << n) == 0 {
+                    n += 1;
+                    continue;
+                }
+                let start = n;
+                while n < 26 && mask & (1 << (n + 1)) != 0 {
+                    n += 1;
+                }
+                parts.push(if start == n {
+                    start.to_string()
+                } else {
+                    format!("{start}-{n}")
+                });
+                n += 1;
+            }
+            parts.join(",")
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survive))
+    }
+}
+
+/// A cube of cells held on the CPU, one byte per cell, x fastest then y then z.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid3d {
+    pub size: u32,
+    pub cells: Vec<u8>,
+}
+
+impl Grid3d {
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            cells: vec![0; (size * size * size) as usize],
+        }
+    }
+
+    /// A random soup filling a cube `soup` cells across in the middle of an empty grid.
+    pub fn soup(size: u32, soup: u32, density: f32, rng: &mut impl Rng) -> Self {
+        let mut grid = Self::new(size);
+        let soup = soup.min(size);
+        let start = (size - soup) / 2;
+        for z in start..start + soup {
+            for y in start..start + soup {
+                for x in start..start + soup {
+                    grid.set(x, y, z, rng.random::<f32>() < density);
+                }
+            }
+        }
+        grid
+    }
+
+    fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        ((z * self.size + y) * self.size + x) as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> bool {
+        self.cells[self.index(x, y, z)] != 0
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, z: u32, alive: bool) {
+        let i = self.index(x, y, z);
+        self.cells[i] = u8::from(alive);
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&c| c != 0).count()
+    }
+
+    /// Advances the grid by one generation. This is the CPU reference for `life3d.wgsl`.
+    pub fn step(&self, rule: &Rule3d) -> Grid3d {
+        let size = self.size as i32;
+        let mut next = Grid3d::new(self.size);
+        for z in 0..size {
+            for y in 0..size {
+                for x in 0..size {
+                    let mut neighbours = 0;
+                    for dz in -1..=1 {
+                        for dy in -1..=1 {
+                            for dx in -1..=1 {
+                                if (dx, dy, dz) == (0, 0, 0) {
+                                    continue;
+                                }
+                                let (nx, ny, nz) = (
+                                    (x + dx).rem_euclid(size),
+                                    (y + dy).rem_euclid(size),
+                                    (z + dz).rem_euclid(size),
+                                );
+                                neighbours += u32::from(self.get(nx as u32, ny as u32, nz as u32));
+                            }
+                        }
+                    }
+                    let alive = self.get(x as u32, y as u32, z as u32);
+                    next.set(x as u32, y as u32, z as u32, rule.next(alive, neighbours));
+                }
+            }
+        }
+        next
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    size: u32,
+    birth: u32,
+    survive: u32,
+    _padding: u32,
+}
+
+/// A 3D world stepped by `life3d.wgsl`, with a ping-pong pair of cell buffers.
+pub struct Life3dCompute {
+    compute_pipeline: wgpu::ComputePipeline,
+    state_buffers: [wgpu::Buffer; 2],
+    current_buffer: usize,
+    generation: u64,
+    bind_groups: [wgpu::BindGroup; 2],
+    rule: Rule3d,
+    size: u32,
+}
+
+impl Life3dCompute {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        initial_state: &Grid3d,
+        rule: Rule3d,
+    ) -> Self {
+        let size = initial_state.size;
+
+        let compute_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Life 3D Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("life3d.wgsl").into()),
+        });
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Life 3D Bind Group Layout"),
+            entries: &[
+                // Current cells (read)
+                storage(0, true),
+                // Next cells (write)
+                storage(1, false),
+                // Size and rule
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Life 3D Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Life 3D Compute Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &compute_shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params = Params {
+            size,
+            birth: rule.birth,
+            survive: rule.survive,
+            _padding: 0,
+        };
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Life 3D Params Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // One u32 per cell
+        let buffer_descriptor = wgpu::BufferDescriptor {
+            label: Some("Life 3D State Buffer"),
+            size: u64::from(size * size * size) * 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        };
+        let state_buffers = [
+            device.create_buffer(&buffer_descriptor),
+            device.create_buffer(&buffer_descriptor),
+        ];
+
+        let bind_group = |label, input: &wgpu::Buffer, output: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: input.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: output.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let bind_groups = [
+            bind_group("Life 3D Bind Group A", &state_buffers[0], &state_buffers[1]),
+            bind_group("Life 3D Bind Group B", &state_buffers[1], &state_buffers[0]),
+        ];
+
+        let life = Self {
+            compute_pipeline,
+            state_buffers,
+            current_buffer: 0,
+            generation: 0,
+            bind_groups,
+            rule,
+            size,
+        };
+        life.write(queue, initial_state);
+        life
+    }
+
+    /// Cells along each edge of the cube.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn rule(&self) -> Rule3d {
+        self.rule
+    }
+
+    pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Life 3D Compute Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &self.bind_groups[self.current_buffer], &[]);
+        let workgroups = self.size.div_ceil(WORKGROUP_SIZE);
+        compute_pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+        drop(compute_pass);
+
+        self.current_buffer = 1 - self.current_buffer;
+        self.generation += 1;
+    }
+
+    /// Number of steps taken since the world was created.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Index of the buffer holding the latest generation, for callers that keep one bind group
+    /// per ping-pong buffer.
+    pub fn current_index(&self) -> usize {
+        self.current_buffer
+    }
+
+    pub fn state_buffers(&self) -> &[wgpu::Buffer; 2] {
+        &self.state_buffers
+    }
+
+    /// Replaces the latest generation with `grid`, which must match the size.
+    pub fn write(&self, queue: &wgpu::Queue, grid: &Grid3d) {
+        let data: Vec<u32> = grid.cells.iter().map(|&c| u32::from(c)).collect();
+        queue.write_buffer(
+            &self.state_buffers[self.current_buffer],
+            0,
+            bytemuck::cast_slice(&data),
+        );
+    }
+
+    /// Copies the latest generation back to the CPU, as `ConwayCompute::read` does.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<Grid3d>> + use<> {
+        let state = &self.state_buffers[self.current_buffer];
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Life 3D Readback Buffer"),
+            size: state.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Life 3D Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(state, 0, &buffer, 0, state.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        let device = device.clone();
+        let size = self.size;
+
+        async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            device.poll(wgpu::PollType::Wait)?;
+            #[cfg(target_arch = "wasm32")]
+            let _ = device;
+            receiver.await??;
+
+            let cells = {
+                let data = buffer.slice(..).get_mapped_range();
+                let cells: &[u32] = bytemuck::cast_slice(&data);
+                cells.iter().map(|&c| u8::from(c != 0)).collect()
+            };
+            buffer.unmap();
+
+            Ok(Grid3d { size, cells })
+        }
+    }
+}
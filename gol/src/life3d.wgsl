@@ -0,0 +1,49 @@
+// One generation of a life-like automaton on a cube of cells with 26 neighbours each; opposite
+// faces of the cube are joined
+
+struct Params {
+    // Cells along each edge
+    size: u32,
+    // Bit n set = born/survives with n neighbours
+    birth: u32,
+    survive: u32,
+    _padding: u32,
+}
+
+// 0 = dead, 1 = alive; x fastest, then y, then z
+@group(0) @binding(0) var<storage, read> current_state: array<u32>;
+@group(0) @binding(1) var<storage, read_write> next_state: array<u32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+fn index(cell: vec3<u32>) -> u32 {
+    return (cell.z * params.size + cell.y) * params.size + cell.x;
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = params.size;
+    if (any(global_id >= vec3<u32>(size))) {
+        return;
+    }
+
+    var neighbours = 0u;
+    for (var dz = 0u; dz < 3u; dz++) {
+        for (var dy = 0u; dy < 3u; dy++) {
+            for (var dx = 0u; dx < 3u; dx++) {
+                if (dx == 1u && dy == 1u && dz == 1u) {
+                    continue;
+                }
+                // Offsets of -1 are added as size - 1 to stay unsigned while wrapping
+                let n = (global_id + vec3<u32>(dx, dy, dz) + vec3<u32>(size - 1u)) % size;
+                neighbours += current_state[index(n)];
+            }
+        }
+    }
+
+    let current = current_state[index(global_id)];
+    var mask = params.birth;
+    if (current != 0u) {
+        mask = params.survive;
+    }
+    next_state[index(global_id)] = select(0u, 1u, (mask & (1u << neighbours)) != 0u);
+}
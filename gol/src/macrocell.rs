@@ -0,0 +1,220 @@
+//! Golly's macrocell format, which stores a HashLife quadtree with every distinct node written
+//! once, so enormous patterns take little space.
+//!
+//! After the `[M2]` header and `#` lines such as `#R B3/S23`, each line is a node, numbered from
+//! 1 in order. An 8 × 8 leaf is written as rows of `.` and `*` ended by `$`, leaving out trailing
+//! dead cells and rows. A larger node is its level followed by the numbers of its north-west,
+//! north-east, south-west and south-east quadrants, 0 standing for an empty one. The last node
+//! is the root, centred on the origin.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{Context, Result, bail};
+
+use crate::hashlife::{self, ALIVE, DEAD, HashLife, NodeId};
+use crate::rule::Rule;
+
+/// Level of the leaves, 8 × 8 blocks.
+const LEAF_LEVEL: u8 = 3;
+/// Deepest tree read, leaving the two levels a step may grow the universe by within
+/// `hashlife::MAX_LEVEL`.
+const MAX_LEVEL: u8 = hashlife::MAX_LEVEL - 2;
+
+/// Reads a macrocell pattern into a universe, in its rule or Conway's Life and at its
+/// generation.
+pub fn read(text: &str) -> Result<HashLife> {
+    let mut lines = text.lines().map(str::trim);
+    if !lines
+        .next()
+        .is_some_and(|header| header.starts_with("[M2]"))
+    {
+        bail!("a macrocell pattern must start with [M2]");
+    }
+    let mut rule = Rule::default();
+    let mut generation = 0;
+    let mut node_lines = Vec::new();
+    for line in lines.filter(|line| !line.is_empty()) {
+        if let Some(directive) = line.strip_prefix('#') {
+            if let Some(r) = directive.strip_prefix('R') {
+                rule = r.trim().parse()?;
+            } else if let Some(g) = directive.strip_prefix('G') {
+                generation = g
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid generation {g:?}"))?;
+            }
+        } else {
+            node_lines.push(line);
+        }
+    }
+
+    let mut life = HashLife::new(rule)?;
+    life.set_generation(generation);
+    // Nodes by their number; 0 is an empty node of whatever level its parent needs
+    let mut nodes: Vec<Option<NodeId>> = vec![None];
+    for line in node_lines {
+        let node = if line.starts_with(['.', '*', '$']) {
+            leaf(&mut life, line)?
+        } else {
+            branch(&mut life, line, &nodes)?
+        };
+        nodes.push(Some(node));
+    }
+    let Some(&Some(root)) = nodes.last() else {
+        bail!("the macrocell pattern has no nodes");
+    };
+    let half = 1i64 << (life.level(root) - 1);
+    life.set_root(root, (-half, -half))?;
+    Ok(life)
+}
+
+/// An 8 × 8 leaf from its rows of `.` and `*`.
+fn leaf(life: &mut HashLife, line: &str) -> Result<NodeId> {
+    let mut cells = [[false; 8]; 8];
+    let (mut x, mut y) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '$' => (x, y) = (0, y + 1),
+            '.' | '*' => {
+                if x >= 8 || y >= 8 {
+                    bail!("leaf {line:?} is larger than 8 × 8 cells");
+                }
+                cells[y][x] = c == '*';
+                x += 1;
+            }
+            _ => bail!("unexpected character {c:?} in leaf {line:?}"),
+        }
+    }
+    Ok(block(life, &cells, 0, 0, LEAF_LEVEL))
+}
+
+/// The node for the square of `cells` of the given level at (`x`, `y`).
+fn block(life: &mut HashLife, cells: &[[bool; 8]; 8], x: usize, y: usize, level: u8) -> NodeId {
+    if level == 0 {
+        return if cells[y][x] { ALIVE } else { DEAD };
+    }
+    let half = 1 << (level - 1);
+    let nw = block(life, cells, x, y, level - 1);
+    let ne = block(life, cells, x + half, y, level - 1);
+    let sw = block(life, cells, x, y + half, level - 1);
+    let se = block(life, cells, x + half, y + half, level - 1);
+    life.join(nw, ne, sw, se)
+}
+
+/// A node from its level and the numbers of its quadrants among the nodes read so far. Golly
+/// writes level 1 nodes, whose quadrants are cell states, for rules with more than two states;
+/// every live state reads as alive.
+fn branch(life: &mut HashLife, line: &str, nodes: &[Option<NodeId>]) -> Result<NodeId> {
+    let numbers = line
+        .split_whitespace()
+        .map(str::parse::<usize>)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("malformed macrocell node {line:?}"))?;
+    let &[level, nw, ne, sw, se] = &numbers[..] else {
+        bail!("malformed macrocell node {line:?}");
+    };
+    let level = u8::try_from(level)
+        .ok()
+        .filter(|level| (1..=MAX_LEVEL).contains(level))
+        .with_context(|| {
+            format!("node {line:?} has level {level}, not between 1 and {MAX_LEVEL}")
+        })?;
+    let mut children = [DEAD; 4];
+    for (child, number) in children.iter_mut().zip([nw, ne, sw, se]) {
+        *child = if level == 1 {
+            if number == 0 { DEAD } else { ALIVE }
+        } else if number == 0 {
+            life.empty(level - 1)
+        } else {
+            let node = nodes
+                .get(number)
+                .copied()
+                .flatten()
+                .with_context(|| format!("node {line:?} refers to node {number} before it"))?;
+            if life.level(node) != level - 1 {
+                bail!("node {line:?} has a quadrant of level {}", life.level(node));
+            }
+            node
+        };
+    }
+    Ok(life.join(children[0], children[1], children[2], children[3]))
+}
+
+/// Writes the universe as a macrocell pattern. The root is centred on the origin, as Golly
+/// expects, so cells move by the same amount relative to the universe's own origin.
+pub fn write(life: &HashLife) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "[M2] (gol {})", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(out, "#R {}", life.rule());
+    if life.generation() != 0 {
+        let _ = writeln!(out, "#G {}", life.generation());
+    }
+    let mut writer = Writer {
+        life,
+        numbers: HashMap::new(),
+        lines: Vec::new(),
+    };
+    let root = life.root();
+    let number = writer.node(root);
+    if life.level(root) == LEAF_LEVEL {
+        // Other readers may expect the root to be a branch
+        writer
+            .lines
+            .push(format!("{} {number} 0 0 0", LEAF_LEVEL + 1));
+    }
+    if writer.lines.is_empty() {
+        writer.lines.push("$".to_string());
+    }
+    for line in writer.lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+struct Writer<'a> {
+    life: &'a HashLife,
+    /// The number each node written so far was given.
+    numbers: HashMap<NodeId, usize>,
+    lines: Vec<String>,
+}
+
+impl Writer<'_> {
+    /// Writes a node after its quadrants, unless it is empty or already written, and returns
+    /// its number.
+    fn node(&mut self, id: NodeId) -> usize {
+        if self.life.node_population(id) == 0 {
+            return 0;
+        }
+        if let Some(&number) = self.numbers.get(&id) {
+            return number;
+        }
+        let level = self.life.level(id);
+        let line = if level == LEAF_LEVEL {
+            self.leaf(id)
+        } else {
+            let [nw, ne, sw, se] = self.life.children(id).map(|child| self.node(child));
+            format!("{level} {nw} {ne} {sw} {se}")
+        };
+        self.lines.push(line);
+        let number = self.lines.len();
+        self.numbers.insert(id, number);
+        number
+    }
+
+    fn leaf(&self, id: NodeId) -> String {
+        let mut line = String::new();
+        for y in 0..8 {
+            let row: String = (0..8)
+                .map(|x| if self.life.cell(id, x, y) { '*' } else { '.' })
+                .collect();
+            line.push_str(row.trim_end_matches('.'));
+            line.push('$');
+        }
+        let rows = line.trim_end_matches('$').len();
+        line.truncate(rows);
+        line.push('$');
+        line
+    }
+}
@@ -1,5 +1,79 @@
+#[cfg(target_arch = "wasm32")]
 use gol::run;
 
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    clap::{Parser, Subcommand},
+    gol::census::{self, CensusOptions},
+    gol::explore::{self, ExploreOptions},
+    gol::options::{OptionArgs, Options, RULE_FORMATS},
+    gol::recording::{self, RecordOptions},
+    gol::script::{self, ScriptOptions},
+    gol::serve::{self, ServeOptions},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Parser)]
+#[command(version, about = "Conway's Game of Life on the GPU", after_help = RULE_FORMATS)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    options: OptionArgs,
+    /// Join the world shared by `gol serve` at this URL, e.g. ws://localhost:7878
+    #[arg(long)]
+    connect: Option<String>,
+    /// Rhai script to run on the world with F5
+    #[arg(long)]
+    script: Option<std::path::PathBuf>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Subcommand)]
+enum Command {
+    Census(CensusOptions),
+    Explore(ExploreOptions),
+    Record(RecordOptions),
+    Script(ScriptOptions),
+    Serve(ServeOptions),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        None => {
+            env_logger::init();
+            let mut options = cli.options.options()?;
+            options.connect = cli.connect.or(options.connect);
+            options.script = cli.script.or(options.script);
+            gol::open(options)?;
+        }
+        Some(command) => {
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+                .init();
+            match command {
+                Command::Census(options) => census::run(&options)?,
+                Command::Explore(options) => {
+                    // Read before exploring, so that a mistake in them is not found only after
+                    let window = cli.options.options()?;
+                    if let Some(rule) = explore::run(&options)? {
+                        gol::open(Options {
+                            rule: Some(rule),
+                            ..window
+                        })?;
+                    }
+                }
+                Command::Record(options) => recording::run(&options)?,
+                Command::Script(options) => script::run(&options)?,
+                Command::Serve(options) => serve::run(&options)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
 fn main() {
     run().unwrap();
 }
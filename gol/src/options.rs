@@ -0,0 +1,410 @@
+//! How the simulator starts. The web build reads these from the page URL; the native binary from
+//! its command line and a TOML config file, through `OptionArgs`.
+
+use anyhow::{Context, Result, bail};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::conway::GRID_SIZE;
+use crate::gpu::{GpuOptions, parse_backends, parse_present_mode};
+use crate::grid::{Boundary, Grid};
+use crate::pattern::Pattern;
+use crate::rule::{Rule, Variant};
+use crate::scheme::UpdateScheme;
+use crate::session::Session;
+use crate::viewport::Viewport;
+
+/// The largest world that fits the textures of every backend, WebGL included.
+pub const MAX_SIZE: u32 = 2048;
+/// The largest bounded world the window runs, split into tiles across several textures when it
+/// is larger than `MAX_SIZE`.
+pub const MAX_TILED_SIZE: u32 = 65536;
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 10_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Defaults to the pattern's rule, then Conway's.
+    pub rule: Option<Rule>,
+    /// Cells along each side of a bounded world, enlarged to fit the pattern if necessary.
+    pub size: u32,
+    pub boundary: Boundary,
+    pub variant: Variant,
+    pub scheme: UpdateScheme,
+    /// Seed of the first soup, random if `None`.
+    pub seed: Option<u64>,
+    /// Chance of each cell of the first soup being alive.
+    pub density: f32,
+    /// Started from, centred, in place of a soup.
+    pub pattern: Option<Pattern>,
+    /// Generations a second, or one a frame if `None`.
+    pub speed: Option<f32>,
+    /// Inner size of the window in pixels. The web build ignores it and the GPU options, as the
+    /// page and the browser decide.
+    pub window_size: Option<(u32, u32)>,
+    pub gpu: GpuOptions,
+    /// URL of a world shared by `gol serve` to join in place of running one.
+    pub connect: Option<String>,
+    /// Rhai script the window runs with F5.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub script: Option<std::path::PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            rule: None,
+            size: GRID_SIZE,
+            boundary: Boundary::default(),
+            variant: Variant::default(),
+            scheme: UpdateScheme::default(),
+            seed: None,
+            density: 0.3,
+            pattern: None,
+            speed: None,
+            window_size: None,
+            gpu: GpuOptions::default(),
+            connect: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            script: None,
+        }
+    }
+}
+
+impl Options {
+    /// Sets the option called `key` from its text, leaving the options unchanged if either is
+    /// invalid. A pattern is given as RLE text.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "rule" => self.rule = Some(value.parse()?),
+            "size" => self.size = parse_size(value)?,
+            "boundary" => self.boundary = parse_boundary(value)?,
+            "variant" => self.variant = parse_variant(value)?,
+            "scheme" => self.scheme = value.parse()?,
+            "seed" => {
+                self.seed = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("invalid seed {value:?}"))?,
+                )
+            }
+            "density" => self.density = parse_density(value)?,
+            "pattern" => self.pattern = Some(Pattern::from_rle(value)?),
+            "speed" => self.speed = Some(parse_speed(value)?),
+            "window-size" => self.window_size = Some(parse_window_size(value)?),
+            "present-mode" => self.gpu.present_mode = Some(parse_present_mode(value)?),
+            "backend" => self.gpu.backends = Some(parse_backends(value)?),
+            "adapter" => self.gpu.adapter = Some(value.to_string()),
+            "connect" => self.connect = Some(value.to_string()),
+            _ => bail!("unknown option {key:?}"),
+        }
+        Ok(())
+    }
+
+    /// The options that describe the world as `key=value` pairs for `set`, leaving out defaults.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        let defaults = Options::default();
+        let mut pairs = Vec::new();
+        if let Some(pattern) = &self.pattern {
+            pairs.push(("pattern", pattern.to_rle()));
+        }
+        if let Some(rule) = self.rule {
+            pairs.push(("rule", rule.to_string()));
+        }
+        pairs.push(("size", self.size.to_string()));
+        if self.boundary != defaults.boundary {
+            pairs.push(("boundary", boundary_name(self.boundary).to_string()));
+        }
+        if self.variant != defaults.variant {
+            pairs.push(("variant", self.variant.to_string().to_ascii_lowercase()));
+        }
+        if self.scheme != defaults.scheme {
+            pairs.push(("scheme", self.scheme.to_string()));
+        }
+        if let Some(seed) = self.seed {
+            pairs.push(("seed", seed.to_string()));
+        }
+        if self.density != defaults.density {
+            pairs.push(("density", self.density.to_string()));
+        }
+        if let Some(speed) = self.speed {
+            pairs.push(("speed", speed.to_string()));
+        }
+        if let Some(url) = &self.connect {
+            pairs.push(("connect", url.clone()));
+        }
+        pairs
+    }
+
+    /// Halves or doubles the speed. Slowing down from one generation a frame starts at 30 a
+    /// second.
+    pub fn adjust_speed(&mut self, faster: bool) {
+        self.speed = match (self.speed, faster) {
+            (None, false) => Some(30.0),
+            (None, true) => None,
+            (Some(speed), true) => Some((speed * 2.0).min(MAX_SPEED)),
+            (Some(speed), false) => Some((speed / 2.0).max(MIN_SPEED)),
+        };
+    }
+
+    /// The world the options start: the pattern centred in a world of at least `size`, or else
+    /// a random soup coloured at random for multi-colour variants. Falling sand only runs in a
+    /// flat bounded world, synchronously, as does any update scheme.
+    /// Worlds larger than `MAX_SIZE` are only split into tiles by the window, so are shrunk.
+    pub fn session(&self) -> Session {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rule = self.starting_rule();
+        if self.size > MAX_SIZE {
+            log::warn!(
+                "Only Life worlds in the window are split into tiles; shrinking the world to \
+                 {MAX_SIZE} cells"
+            );
+        }
+        let size = self.size.min(MAX_SIZE);
+        let mut grid = Grid::random(size, size, self.density, &mut rng);
+        if self.variant != Variant::Life {
+            grid.recolour(None, self.variant.colours(), &mut rng);
+        }
+        let mut boundary = self.boundary;
+        if self.variant == Variant::Sand && matches!(boundary, Boundary::Unbounded | Boundary::Cube)
+        {
+            log::warn!("Falling sand needs a flat bounded world; walling it in");
+            boundary = Boundary::Dead;
+        }
+        let mut scheme = self.scheme;
+        if scheme != UpdateScheme::Synchronous
+            && (self.variant == Variant::Sand || boundary == Boundary::Unbounded)
+        {
+            log::warn!("The {scheme} update scheme needs a bounded Life world; ignoring it");
+            scheme = UpdateScheme::Synchronous;
+        }
+        let session = Session {
+            viewport: Viewport::new((grid.width, grid.height), rule.neighbourhood),
+            grid,
+            rule,
+            boundary: Boundary::Dead,
+            variant: self.variant,
+            scheme,
+            origin: (0, 0),
+            generation: 0,
+            seed,
+        };
+        let session = match &self.pattern {
+            Some(pattern) => session.with_pattern(&Pattern {
+                rule: Some(rule),
+                ..pattern.clone()
+            }),
+            None => session,
+        };
+        session.with_boundary(boundary)
+    }
+
+    /// The rule given, or else the pattern's, or else Conway's.
+    pub fn starting_rule(&self) -> Rule {
+        self.rule
+            .or_else(|| self.pattern.as_ref().and_then(|pattern| pattern.rule))
+            .unwrap_or_default()
+    }
+}
+
+/// The options as command-line flags, over the defaults a TOML config file sets with the same
+/// names, such as `rule = "B36/S23"` or `window-size = "1280x720"`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct OptionArgs {
+    /// TOML file of defaults for these options [default: gol.toml, if there is one]
+    #[arg(long)]
+    pub config: Option<std::path::PathBuf>,
+    /// Rule, e.g. B3/S23 or B2/S34H (defaults to the pattern's rule, then Conway's); see the
+    /// rule formats below
+    #[arg(long)]
+    pub rule: Option<Rule>,
+    /// Width and height of a bounded world, enlarged to fit the pattern if necessary; Life
+    /// worlds of more than 2048 are split into tiles, up to 65536 [default: 64]
+    #[arg(long, value_parser = parse_size)]
+    pub size: Option<u32>,
+    /// [default: torus]
+    #[arg(long, value_enum)]
+    pub boundary: Option<Boundary>,
+    /// Colours the random soup for a multi-colour variant; patterns keep their own colours
+    /// [default: life]
+    #[arg(long, value_enum)]
+    pub variant: Option<Variant>,
+    /// Which cells change each generation: synchronous, probabilistic:P (or
+    /// probabilistic:BIRTH,DEATH), asynchronous:ALPHA, checkerboard or sweep
+    #[arg(long)]
+    pub scheme: Option<UpdateScheme>,
+    /// Seed of the random soup (random if omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Probability that a soup cell starts alive [default: 0.3]
+    #[arg(long, value_parser = parse_density)]
+    pub density: Option<f32>,
+    /// Pattern file to start from (RLE, plaintext, Life 1.05/1.06 or macrocell), centred in the
+    /// world
+    #[arg(long)]
+    pub pattern: Option<std::path::PathBuf>,
+    /// Generations a second (one a frame if omitted)
+    #[arg(long, value_parser = parse_speed)]
+    pub speed: Option<f32>,
+    /// Inner size of the window in pixels, e.g. 1280x720
+    #[arg(long, value_parser = parse_window_size)]
+    pub window_size: Option<(u32, u32)>,
+    /// auto-vsync, auto-no-vsync, fifo, fifo-relaxed, immediate or mailbox
+    #[arg(long, value_parser = parse_present_mode)]
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// Comma-separated backends to choose an adapter from: vulkan, dx12, metal or gl
+    #[arg(long, value_parser = parse_backends)]
+    pub backend: Option<wgpu::Backends>,
+    /// Use the first adapter whose name contains this, ignoring case
+    #[arg(long)]
+    pub adapter: Option<String>,
+}
+
+/// Rule formats, listed after the options in `--help`.
+#[cfg(not(target_arch = "wasm32"))]
+pub const RULE_FORMATS: &str = "\
+Rule formats:
+  B3/S23    birth and survival counts, in any case, with the / optional
+  23/3      the older S/B form
+  B2/S34H   a trailing H for hexagonal cells, or L for triangular ones
+  Counts run from 0 to 8 for square and from 0 to 6 for hexagonal cells. Triangular cells have
+  12 neighbours, but only counts up to 9 can be written.";
+
+#[cfg(not(target_arch = "wasm32"))]
+impl OptionArgs {
+    /// The defaults, then the config file, then the flags given.
+    pub fn options(&self) -> Result<Options> {
+        let mut options = Options::default();
+        let default_config = std::path::Path::new("gol.toml");
+        let config = match &self.config {
+            Some(path) => Some(path.as_path()),
+            None => default_config.exists().then_some(default_config),
+        };
+        if let Some(path) = config {
+            options
+                .load(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+        }
+        if let Some(path) = &self.pattern {
+            options.pattern = Some(read_pattern(path)?);
+        }
+        options.rule = self.rule.or(options.rule);
+        options.size = self.size.unwrap_or(options.size);
+        options.boundary = self.boundary.unwrap_or(options.boundary);
+        options.variant = self.variant.unwrap_or(options.variant);
+        options.scheme = self.scheme.unwrap_or(options.scheme);
+        options.seed = self.seed.or(options.seed);
+        options.density = self.density.unwrap_or(options.density);
+        options.speed = self.speed.or(options.speed);
+        options.window_size = self.window_size.or(options.window_size);
+        options.gpu.present_mode = self.present_mode.or(options.gpu.present_mode);
+        options.gpu.backends = self.backend.or(options.gpu.backends);
+        options.gpu.adapter = self.adapter.clone().or(options.gpu.adapter);
+        Ok(options)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Options {
+    /// Sets the options in a TOML file. A pattern is the path of a pattern file and a script the
+    /// path of a Rhai script, both relative to the config file.
+    fn load(&mut self, path: &std::path::Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        let table: toml::Table = text.parse()?;
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                value => bail!("{key} cannot be {value}"),
+            };
+            if key == "pattern" {
+                let pattern = path.parent().unwrap_or(path).join(value);
+                self.pattern = Some(read_pattern(&pattern)?);
+            } else if key == "script" {
+                self.script = Some(path.parent().unwrap_or(path).join(value));
+            } else {
+                self.set(&key, &value)
+                    .with_context(|| format!("invalid {key}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_pattern(path: &std::path::Path) -> Result<Pattern> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    Pattern::from_text(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn boundary_name(boundary: Boundary) -> &'static str {
+    match boundary {
+        Boundary::Torus => "torus",
+        Boundary::Dead => "dead",
+        Boundary::Unbounded => "unbounded",
+        Boundary::Cube => "cube",
+    }
+}
+
+fn parse_boundary(value: &str) -> Result<Boundary> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "torus" => Boundary::Torus,
+        "dead" => Boundary::Dead,
+        "unbounded" => Boundary::Unbounded,
+        "cube" => Boundary::Cube,
+        _ => bail!("invalid boundary {value:?}, expected torus, dead, unbounded or cube"),
+    })
+}
+
+fn parse_variant(value: &str) -> Result<Variant> {
+    Ok(match value.to_ascii_lowercase().as_str() {
+        "life" => Variant::Life,
+        "immigration" => Variant::Immigration,
+        "quadlife" => Variant::QuadLife,
+        "sand" => Variant::Sand,
+        _ => bail!("invalid variant {value:?}, expected life, immigration, quadlife or sand"),
+    })
+}
+
+fn parse_size(value: &str) -> Result<u32> {
+    let size: u32 = value
+        .parse()
+        .with_context(|| format!("invalid size {value:?}"))?;
+    if !(1..=MAX_TILED_SIZE).contains(&size) {
+        bail!("size {size} is not between 1 and {MAX_TILED_SIZE}");
+    }
+    Ok(size)
+}
+
+fn parse_density(value: &str) -> Result<f32> {
+    let p: f32 = value
+        .parse()
+        .with_context(|| format!("invalid density {value:?}"))?;
+    if !(0.0..=1.0).contains(&p) {
+        bail!("density {p} is not between 0 and 1");
+    }
+    Ok(p)
+}
+
+fn parse_speed(value: &str) -> Result<f32> {
+    let speed: f32 = value
+        .parse()
+        .with_context(|| format!("invalid speed {value:?}"))?;
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        bail!("speed {speed} is not between {MIN_SPEED} and {MAX_SPEED} generations a second");
+    }
+    Ok(speed)
+}
+
+/// Parses `WIDTHxHEIGHT`.
+fn parse_window_size(value: &str) -> Result<(u32, u32)> {
+    let size = value
+        .split_once(['x', 'X'])
+        .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)))
+        .filter(|&(width, height)| width > 0 && height > 0);
+    size.with_context(|| format!("invalid window size {value:?}, expected WIDTHxHEIGHT"))
+}
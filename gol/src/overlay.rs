@@ -0,0 +1,162 @@
+use wgpu::util::DeviceExt;
+
+use crate::grid::{Grid, Rect};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct OverlayUniform {
+    ghost_origin: [i32; 2],
+    ghost_mode: u32,
+    selection_visible: u32,
+    selection_min: [u32; 2],
+    selection_max: [u32; 2],
+}
+
+/// How the ghost is drawn; the values match `Overlay::ghost_mode` in `display.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GhostMode {
+    Hidden = 0,
+    Clipped = 1,
+    Wrapped = 2,
+}
+
+/// Editing aids drawn by the display shader on top of the world: a translucent preview
+/// ("ghost") of a pattern about to be placed, and the selection marquee.
+pub struct Overlay {
+    layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    uniform: OverlayUniform,
+}
+
+impl Overlay {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Overlay Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let uniform = OverlayUniform {
+            ghost_origin: [0, 0],
+            ghost_mode: GhostMode::Hidden as u32,
+            selection_visible: 0,
+            selection_min: [0, 0],
+            selection_max: [0, 0],
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Overlay Uniform Buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group =
+            Self::create_bind_group(device, queue, &layout, &uniform_buffer, &Grid::new(1, 1));
+
+        Self {
+            layout,
+            uniform_buffer,
+            bind_group,
+            uniform,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        cells: &Grid,
+    ) -> wgpu::BindGroup {
+        let data: Vec<f32> = cells.cells.iter().map(|&c| f32::from(c)).collect();
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Ghost Texture"),
+                size: wgpu::Extent3d {
+                    width: cells.width,
+                    height: cells.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&data),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Overlay Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Replaces the previewed cells.
+    pub fn set_ghost_cells(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, cells: &Grid) {
+        self.bind_group =
+            Self::create_bind_group(device, queue, &self.layout, &self.uniform_buffer, cells);
+    }
+
+    /// Moves the preview's top-left corner to `origin` and shows or hides it.
+    pub fn set_ghost(&mut self, queue: &wgpu::Queue, origin: (i32, i32), mode: GhostMode) {
+        self.uniform.ghost_origin = [origin.0, origin.1];
+        self.uniform.ghost_mode = mode as u32;
+        self.write_uniform(queue);
+    }
+
+    pub fn set_selection(&mut self, queue: &wgpu::Queue, selection: Option<Rect>) {
+        self.uniform.selection_visible = u32::from(selection.is_some());
+        if let Some(rect) = selection {
+            self.uniform.selection_min = [rect.x, rect.y];
+            self.uniform.selection_max = [rect.x + rect.width, rect.y + rect.height];
+        }
+        self.write_uniform(queue);
+    }
+
+    fn write_uniform(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniform));
+    }
+}
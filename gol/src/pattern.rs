@@ -0,0 +1,356 @@
+use std::fmt::Write;
+
+use anyhow::{Context, Result, bail};
+
+use crate::grid::Grid;
+use crate::rule::Rule;
+
+/// File extensions of the pattern formats `from_text` reads.
+pub const EXTENSIONS: &[&str] = &["rle", "cells", "mc", "lif", "life"];
+/// The most cells a pattern read from text may span, so that a few cells far apart cannot ask
+/// for an enormous grid.
+const MAX_CELLS: u64 = 1 << 28;
+
+/// A finite pattern together with the metadata pattern files carry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub rule: Option<Rule>,
+    pub grid: Grid,
+}
+
+impl Pattern {
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            name: None,
+            rule: None,
+            grid,
+        }
+    }
+
+    /// Parses a pattern in any format this crate reads, recognised by its content: RLE,
+    /// plaintext (`.cells`), Life 1.05 or 1.06 (`.lif`), or macrocell (`.mc`) if it is small
+    /// enough to expand.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let first = text.trim_start().lines().next().unwrap_or_default().trim();
+        if first.starts_with("#Life 1.06") {
+            Self::from_life_106(text)
+        } else if first.starts_with("#Life 1.05") {
+            Self::from_life_105(text)
+        } else if first.starts_with("[M2]") {
+            let (pattern, _) = crate::macrocell::read(text)?.to_pattern()?;
+            Ok(pattern)
+        } else if text
+            .lines()
+            .map(str::trim)
+            .any(|line| line.starts_with('x') && line.contains('='))
+        {
+            Self::from_rle(text)
+        } else {
+            Self::from_plaintext(text)
+        }
+    }
+
+    /// Parses plaintext, the `.cells` format of the LifeWiki: `!` comment lines, the first of
+    /// them optionally `!Name: ...`, then rows of `.` for dead cells and `O` for live ones.
+    pub fn from_plaintext(text: &str) -> Result<Self> {
+        let mut name = None;
+        let mut rows = Vec::new();
+        for line in text.lines() {
+            let line = line.trim_end();
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(n) = comment.strip_prefix("Name:") {
+                    name = Some(n.trim().to_string());
+                }
+                continue;
+            }
+            let row = line
+                .chars()
+                .map(|c| match c {
+                    '.' => Ok(false),
+                    'O' | 'o' | '*' => Ok(true),
+                    _ => bail!("unexpected character {c:?} in plaintext pattern"),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            rows.push(row);
+        }
+        while rows.last().is_some_and(Vec::is_empty) {
+            rows.pop();
+        }
+        let cells = rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &alive)| alive)
+                .map(move |(x, _)| (x as i64, y as i64))
+        });
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+        let grid = grid_of(cells, Some((width as u64, rows.len() as u64)))?;
+        Ok(Self {
+            name,
+            rule: None,
+            grid,
+        })
+    }
+
+    /// Parses Life 1.06: a `#Life 1.06` header, then the coordinates of each live cell.
+    pub fn from_life_106(text: &str) -> Result<Self> {
+        let mut cells = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut numbers = line.split_whitespace().map(str::parse::<i64>);
+            match (numbers.next(), numbers.next(), numbers.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y)),
+                _ => bail!("malformed Life 1.06 line {line:?}"),
+            }
+        }
+        Ok(Self::new(grid_of(cells, None)?))
+    }
+
+    /// Parses Life 1.05: a `#Life 1.05` header, `#D` descriptions, `#N` for Conway's rule or
+    /// `#R` for another in S/B form, and blocks of `.` and `*` rows each placed by a
+    /// `#P x y` line.
+    pub fn from_life_105(text: &str) -> Result<Self> {
+        let mut rule = None;
+        let mut cells = Vec::new();
+        let (mut x, mut y) = (0i64, 0i64);
+        for line in text.lines().map(str::trim) {
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut words = directive.split_whitespace();
+                match words.next() {
+                    Some("N") => rule = Some(Rule::default()),
+                    Some("R") => rule = Some(words.next().unwrap_or_default().parse()?),
+                    Some("P") => {
+                        let mut position = words.map(str::parse::<i64>);
+                        match (position.next(), position.next()) {
+                            (Some(Ok(px)), Some(Ok(py))) => (x, y) = (px, py),
+                            _ => bail!("malformed Life 1.05 line {line:?}"),
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            for (i, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    '*' | 'O' | 'o' => {
+                        cells.push((x.checked_add(i as i64).with_context(too_far)?, y))
+                    }
+                    _ => bail!("unexpected character {c:?} in Life 1.05 pattern"),
+                }
+            }
+            y = y.checked_add(1).with_context(too_far)?;
+        }
+        Ok(Self {
+            name: None,
+            rule,
+            grid: grid_of(cells, None)?,
+        })
+    }
+
+    /// Parses run length encoded text, as written by Golly and most other Life software.
+    pub fn from_rle(text: &str) -> Result<Self> {
+        let mut name = None;
+        let mut rule = None;
+        let mut size = None;
+        let mut body = String::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(n) = comment.strip_prefix('N') {
+                    name = Some(n.trim().to_string());
+                }
+            } else if size.is_none() && line.starts_with('x') {
+                let mut width = None;
+                let mut height = None;
+                for field in line.split(',') {
+                    let (key, value) = field
+                        .split_once('=')
+                        .with_context(|| format!("malformed RLE header field {field:?}"))?;
+                    match key.trim() {
+                        "x" => width = Some(value.trim().parse::<u32>()?),
+                        "y" => height = Some(value.trim().parse::<u32>()?),
+                        "rule" => rule = Some(value.trim().parse::<Rule>()?),
+                        _ => {}
+                    }
+                }
+                match (width, height) {
+                    (Some(w), Some(h)) => size = Some((w, h)),
+                    _ => bail!("RLE header is missing x or y"),
+                }
+            } else {
+                body.push_str(line);
+                if line.contains('!') {
+                    break;
+                }
+            }
+        }
+
+        let (width, height) = size.context("RLE pattern has no header line")?;
+        if u64::from(width) * u64::from(height) > MAX_CELLS {
+            bail!("the pattern spans {width}x{height} cells, more than {MAX_CELLS}");
+        }
+        let mut grid = Grid::new(width, height);
+        let (mut x, mut y) = (0u32, 0u32);
+        let mut count = 0u32;
+        let too_long = || format!("RLE run is too long for the {width}x{height} header");
+        for c in body.chars() {
+            match c {
+                '0'..='9' => {
+                    count = count
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(c.to_digit(10).unwrap()))
+                        .with_context(too_long)?
+                }
+                '!' => break,
+                c if c.is_whitespace() => {}
+                _ => {
+                    let run = count.max(1);
+                    count = 0;
+                    match c {
+                        '$' => {
+                            y = y.checked_add(run).with_context(too_long)?;
+                            x = 0;
+                        }
+                        'b' | '.' => x = x.checked_add(run).with_context(too_long)?,
+                        // States A to D are the colours of the multi-colour variants; every other
+                        // state letter is treated as alive.
+                        'o' | 'A'..='X' => {
+                            let colour = match c {
+                                'A'..='D' => c as u8 - b'A' + 1,
+                                _ => 1,
+                            };
+                            for _ in 0..run {
+                                if x >= width || y >= height {
+                                    bail!(
+                                        "RLE cell ({x}, {y}) is outside the {width}x{height} header"
+                                    );
+                                }
+                                grid.set_colour(x, y, colour);
+                                x += 1;
+                            }
+                        }
+                        _ => bail!("unexpected character {c:?} in RLE body"),
+                    }
+                }
+            }
+        }
+
+        Ok(Self { name, rule, grid })
+    }
+
+    /// Writes the pattern as run length encoded text with lines of at most 70 characters.
+    pub fn to_rle(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = &self.name {
+            let _ = writeln!(out, "#N {name}");
+        }
+        let _ = write!(out, "x = {}, y = {}", self.grid.width, self.grid.height);
+        if let Some(rule) = &self.rule {
+            let _ = write!(out, ", rule = {rule}");
+        }
+        out.push('\n');
+
+        // Multi-colour grids use the multi-state letters, `.` for dead and `A` onwards for live.
+        let coloured = self.grid.cells.iter().any(|&c| c > 1);
+        let tag = |cell: u8| match (coloured, cell) {
+            (false, 0) => 'b',
+            (false, _) => 'o',
+            (true, 0) => '.',
+            (true, c) => (b'A' + c.min(24) - 1) as char,
+        };
+        let mut tokens = Vec::new();
+        let push = |tokens: &mut Vec<String>, run: u32, tag: char| {
+            if run == 1 {
+                tokens.push(tag.to_string());
+            } else if run > 1 {
+                tokens.push(format!("{run}{tag}"));
+            }
+        };
+        // Rows to advance before the next non-empty row; the first row needs no `$`.
+        let mut pending_rows = 0;
+        for y in 0..self.grid.height {
+            let row: Vec<u8> = (0..self.grid.width)
+                .map(|x| self.grid.colour(x, y))
+                .collect();
+            let Some(last) = row.iter().rposition(|&c| c != 0) else {
+                pending_rows += 1;
+                continue;
+            };
+            push(&mut tokens, pending_rows, '$');
+            pending_rows = 1;
+            let mut x = 0;
+            while x <= last {
+                let cell = row[x];
+                let run = row[x..=last].iter().take_while(|&&c| c == cell).count();
+                push(&mut tokens, run as u32, tag(cell));
+                x += run;
+            }
+        }
+        tokens.push("!".to_string());
+
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len + token.len() > 70 {
+                out.push('\n');
+                line_len = 0;
+            }
+            line_len += token.len();
+            out.push_str(&token);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Whether a file name has the extension of a pattern format.
+pub fn is_pattern_file(name: &str) -> bool {
+    name.rsplit_once('.').is_some_and(|(_, extension)| {
+        EXTENSIONS
+            .iter()
+            .any(|known| extension.eq_ignore_ascii_case(known))
+    })
+}
+
+/// The error for cells too far apart for their distance to be counted.
+fn too_far() -> String {
+    format!("the pattern spans more than {MAX_CELLS} cells")
+}
+
+/// A grid just holding `cells`, or `size` if given, with the top-left cell of the cells'
+/// bounding box at (0, 0) unless a size is given.
+fn grid_of(cells: impl IntoIterator<Item = (i64, i64)>, size: Option<(u64, u64)>) -> Result<Grid> {
+    let cells: Vec<(i64, i64)> = cells.into_iter().collect();
+    let (min_x, min_y) = match size {
+        Some(_) => (0, 0),
+        None => (
+            cells.iter().map(|&(x, _)| x).min().unwrap_or_default(),
+            cells.iter().map(|&(_, y)| y).min().unwrap_or_default(),
+        ),
+    };
+    let (width, height) = match size {
+        Some(size) => size,
+        None => {
+            let span = |max: Option<i64>, min: i64| {
+                max.map_or(Some(0), |max| max.checked_sub(min)?.checked_add(1))
+                    .map(|span| span as u64)
+                    .with_context(too_far)
+            };
+            (
+                span(cells.iter().map(|&(x, _)| x).max(), min_x)?,
+                span(cells.iter().map(|&(_, y)| y).max(), min_y)?,
+            )
+        }
+    };
+    if width.saturating_mul(height) > MAX_CELLS {
+        bail!("the pattern spans {width}x{height} cells, more than {MAX_CELLS}");
+    }
+    let mut grid = Grid::new(width as u32, height as u32);
+    for (x, y) in cells {
+        grid.set((x - min_x) as u32, (y - min_y) as u32, true);
+    }
+    Ok(grid)
+}
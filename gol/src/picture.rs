@@ -0,0 +1,104 @@
+//! Turning pictures into initial states.
+
+use std::fmt;
+
+use anyhow::Result;
+use image::imageops::FilterType;
+
+use crate::grid::Grid;
+
+/// 4x4 Bayer matrix for ordered dithering.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// How grey levels are reduced to live and dead cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Each cell is compared with the threshold on its own.
+    #[default]
+    None,
+    /// The threshold is offset by a repeating Bayer pattern.
+    Ordered,
+    /// The rounding error of each cell is spread over its unvisited neighbours.
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub fn next(self) -> Self {
+        match self {
+            Dither::None => Dither::Ordered,
+            Dither::Ordered => Dither::FloydSteinberg,
+            Dither::FloydSteinberg => Dither::None,
+        }
+    }
+}
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Dither::None => "no dithering",
+            Dither::Ordered => "ordered dithering",
+            Dither::FloydSteinberg => "Floyd-Steinberg dithering",
+        })
+    }
+}
+
+/// A picture resampled to the grid, one brightness between 0 and 1 per cell.
+#[derive(Debug, Clone)]
+pub struct Picture {
+    width: u32,
+    height: u32,
+    brightness: Vec<f32>,
+}
+
+impl Picture {
+    /// Decodes a PNG or JPEG and stretches it to `width` by `height` cells.
+    pub fn decode(bytes: &[u8], width: u32, height: u32) -> Result<Self> {
+        let image = image::load_from_memory(bytes)?;
+        let resized = image.resize_exact(width, height, FilterType::Triangle);
+        Ok(Self {
+            width,
+            height,
+            brightness: resized.to_luma32f().into_raw(),
+        })
+    }
+
+    /// Cells brighter than `threshold` are alive, matching the white live cells on screen.
+    pub fn to_grid(&self, threshold: f32, dither: Dither) -> Grid {
+        let mut grid = Grid::new(self.width, self.height);
+        let mut error = match dither {
+            Dither::FloydSteinberg => self.brightness.clone(),
+            _ => Vec::new(),
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = (y * self.width + x) as usize;
+                let alive = match dither {
+                    Dither::None => self.brightness[i] > threshold,
+                    Dither::Ordered => {
+                        let offset =
+                            (f32::from(BAYER[y as usize % 4][x as usize % 4]) + 0.5) / 16.0 - 0.5;
+                        self.brightness[i] + offset > threshold
+                    }
+                    Dither::FloydSteinberg => {
+                        let value = error[i];
+                        let alive = value > threshold;
+                        let residual = value - if alive { 1.0 } else { 0.0 };
+                        let mut spread = |dx: i32, dy: u32, weight: f32| {
+                            let (nx, ny) = (x as i32 + dx, y + dy);
+                            if nx >= 0 && (nx as u32) < self.width && ny < self.height {
+                                error[(ny * self.width + nx as u32) as usize] += residual * weight;
+                            }
+                        };
+                        spread(1, 0, 7.0 / 16.0);
+                        spread(-1, 1, 3.0 / 16.0);
+                        spread(0, 1, 5.0 / 16.0);
+                        spread(1, 1, 1.0 / 16.0);
+                        alive
+                    }
+                };
+                grid.set(x, y, alive);
+            }
+        }
+        grid
+    }
+}
@@ -0,0 +1,80 @@
+use crate::grid::Grid;
+use crate::library;
+use crate::pattern::Pattern;
+
+/// Picks a pattern, from the library or the clipboard, and orients it before it is stamped into
+/// the world.
+pub struct Placement {
+    library: Vec<Pattern>,
+    selected: usize,
+    name: String,
+    /// The chosen pattern after rotations and flips.
+    oriented: Grid,
+}
+
+impl Placement {
+    pub fn new(selected: usize) -> Self {
+        let mut placement = Self {
+            library: library::patterns(),
+            selected: 0,
+            name: String::new(),
+            oriented: Grid::new(0, 0),
+        };
+        placement.select(selected.min(placement.library.len() - 1));
+        placement
+    }
+
+    /// Places cells from the clipboard rather than the library.
+    pub fn paste(cells: Grid) -> Self {
+        Self {
+            library: library::patterns(),
+            selected: 0,
+            name: "clipboard".to_string(),
+            oriented: cells,
+        }
+    }
+
+    /// Selects the pattern at `index`, ignoring indices past the end of the library.
+    pub fn select(&mut self, index: usize) {
+        if let Some(pattern) = self.library.get(index) {
+            self.selected = index;
+            self.name = pattern
+                .name
+                .clone()
+                .unwrap_or_else(|| "unnamed pattern".to_string());
+            self.oriented = pattern.grid.clone();
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.select((self.selected + 1) % self.library.len());
+    }
+
+    pub fn previous(&mut self) {
+        self.select((self.selected + self.library.len() - 1) % self.library.len());
+    }
+
+    pub fn rotate(&mut self) {
+        self.oriented = self.oriented.rotate_clockwise();
+    }
+
+    pub fn flip(&mut self) {
+        self.oriented = self.oriented.flip_horizontal();
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.oriented
+    }
+
+    /// Where the pattern's top-left corner goes so that it is centred on `cell`.
+    pub fn origin(&self, cell: (i32, i32)) -> (i32, i32) {
+        (
+            cell.0 - self.oriented.width as i32 / 2,
+            cell.1 - self.oriented.height as i32 / 2,
+        )
+    }
+}
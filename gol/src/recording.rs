@@ -0,0 +1,290 @@
+//! Recordings of simulation runs as animated GIFs or numbered PNG frames.
+//!
+//! Frames are drawn through the display pipeline into an offscreen texture and read back, so
+//! recording works the same with a window and in the headless runner.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+use crate::conway::{ConwayCompute, ConwayConfig};
+use crate::display::Display;
+use crate::gpu::HeadlessContext;
+use crate::grid::Boundary;
+use crate::options::{OptionArgs, RULE_FORMATS};
+use crate::overlay::Overlay;
+use crate::viewport::Viewport;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// How a run is recorded.
+#[derive(Debug, Clone, clap::Args)]
+pub struct RecordingOptions {
+    /// Record every K-th generation
+    #[arg(long, default_value_t = 1)]
+    pub every: u32,
+    /// Width and height of each cell in pixels
+    #[arg(long, default_value_t = 4)]
+    pub cell_size: u32,
+    /// Delay between GIF frames in milliseconds
+    #[arg(long, default_value_t = 50)]
+    pub frame_delay: u32,
+    /// A `.gif` file, or a `.png` name that numbered frames are written next to
+    #[arg(long, default_value = "recording.gif")]
+    pub output: PathBuf,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            every: 1,
+            cell_size: 4,
+            frame_delay: 50,
+            output: PathBuf::from("recording.gif"),
+        }
+    }
+}
+
+/// Run a pattern or random soup without a window and record it.
+#[derive(Debug, Clone, clap::Args)]
+#[command(after_help = RULE_FORMATS)]
+pub struct RecordOptions {
+    #[command(flatten)]
+    pub recording: RecordingOptions,
+    /// Number of generations to run
+    #[arg(long, default_value_t = 256)]
+    pub generations: u64,
+    /// The world to run, as for the window; the speed and window options have no effect
+    #[command(flatten)]
+    pub world: OptionArgs,
+}
+
+enum Sink {
+    Gif(Box<GifEncoder<BufWriter<File>>>),
+    Png(PathBuf),
+}
+
+pub struct Recorder {
+    display: Display,
+    overlay: Overlay,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    width: u32,
+    height: u32,
+    every: u32,
+    frame_delay: u32,
+    first_generation: u64,
+    /// The generation captured last, so that a paused world is not captured again.
+    last_generation: Option<u64>,
+    frames: u32,
+    sink: Sink,
+}
+
+impl Recorder {
+    /// Starts recording `conway` from its current generation.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        conway: &ConwayCompute,
+        options: &RecordingOptions,
+    ) -> Result<Self> {
+        let (columns, rows) = conway.size();
+        let cell_size = options.cell_size.max(1);
+        let (width, height) = (columns * cell_size, rows * cell_size);
+        let max = device.limits().max_texture_dimension_2d;
+        if width > max || height > max {
+            bail!("{width}x{height} frames exceed the adapter's {max} pixel texture limit");
+        }
+
+        let sink = match options.output.extension().and_then(|e| e.to_str()) {
+            Some("gif") => {
+                let file = File::create(&options.output)
+                    .with_context(|| format!("unable to create {}", options.output.display()))?;
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+                encoder.set_repeat(Repeat::Infinite)?;
+                Sink::Gif(Box::new(encoder))
+            }
+            Some("png") => Sink::Png(options.output.clone()),
+            _ => bail!("{} should end in .gif or .png", options.output.display()),
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Recording Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Recordings show the whole grid without editing aids
+        let overlay = Overlay::new(device, queue);
+        let display = Display::new(
+            device,
+            conway,
+            &overlay,
+            FORMAT,
+            &Viewport::new(conway.size(), conway.config().rule.neighbourhood),
+        );
+
+        Ok(Self {
+            display,
+            overlay,
+            texture,
+            view,
+            width,
+            height,
+            every: options.every.max(1),
+            frame_delay: options.frame_delay,
+            first_generation: conway.generation(),
+            last_generation: None,
+            frames: 0,
+            sink,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Adds the latest generation to the recording if it is one of every K-th and has not been
+    /// captured already.
+    pub fn capture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        conway: &ConwayCompute,
+    ) -> Result<()> {
+        let generation = conway.generation();
+        if self.last_generation == Some(generation)
+            || !(generation - self.first_generation).is_multiple_of(u64::from(self.every))
+        {
+            return Ok(());
+        }
+        self.last_generation = Some(generation);
+
+        let bytes_per_row = (self.width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Recording Readback Buffer"),
+            size: u64::from(bytes_per_row * self.height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Recording Encoder"),
+        });
+        self.display
+            .draw(&mut encoder, &self.view, conway, &self.overlay);
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        device.poll(wgpu::PollType::Wait)?;
+        pollster::block_on(receiver)??;
+
+        let mut pixels = Vec::with_capacity((self.width * self.height * 4) as usize);
+        {
+            let data = buffer.slice(..).get_mapped_range();
+            for row in data.chunks(bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..self.width as usize * 4]);
+            }
+        }
+        buffer.unmap();
+        let image = RgbaImage::from_raw(self.width, self.height, pixels)
+            .context("frame has the wrong size")?;
+
+        match &mut self.sink {
+            Sink::Gif(encoder) => {
+                let delay = Delay::from_numer_denom_ms(self.frame_delay, 1);
+                encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+            }
+            Sink::Png(path) => {
+                let path = numbered(path, self.frames);
+                image
+                    .save(&path)
+                    .with_context(|| format!("unable to write {}", path.display()))?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// `frames.png` becomes `frames-00042.png` for frame 42.
+fn numbered(path: &Path, frame: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}-{frame:05}.png"))
+}
+
+pub fn run(options: &RecordOptions) -> Result<()> {
+    let world = options.world.options()?;
+    if world.boundary == Boundary::Unbounded {
+        bail!("recording an unbounded world is not supported");
+    }
+    let ctx = pollster::block_on(HeadlessContext::with_options(&world.gpu))?;
+
+    let session = world.session();
+    if world.pattern.is_none() {
+        log::info!("Recording a random soup from seed {}", session.seed);
+    }
+    let config = ConwayConfig {
+        rule: session.rule,
+        boundary: session.boundary,
+        tile_size: None,
+        variant: session.variant,
+        scheme: session.scheme,
+    };
+    let mut conway = ConwayCompute::new(&ctx.device, &ctx.queue, &session.grid, config);
+    let mut recorder = Recorder::new(&ctx.device, &ctx.queue, &conway, &options.recording)?;
+
+    recorder.capture(&ctx.device, &ctx.queue, &conway)?;
+    for _ in 0..options.generations {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Record Step Encoder"),
+            });
+        conway.step(&mut encoder);
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        recorder.capture(&ctx.device, &ctx.queue, &conway)?;
+    }
+
+    log::info!(
+        "Wrote {} frames to {}",
+        recorder.frames(),
+        options.recording.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! The window's connection to a world shared by `gol serve`. Messages from the server arrive as
+//! `UserEvent::Shared`, relayed by a thread natively and by the browser's WebSocket on the web,
+//! and `UserEvent::Disconnected` once the connection is lost.
+
+use anyhow::{Result, bail};
+use winit::event_loop::EventLoopProxy;
+
+use crate::app::UserEvent;
+use crate::shared::{ClientMessage, ServerMessage, SharedWorld};
+
+pub struct Remote {
+    url: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    outgoing: std::sync::mpsc::Sender<ClientMessage>,
+    #[cfg(target_arch = "wasm32")]
+    socket: web_sys::WebSocket,
+    /// The world as the server last described it, once it has.
+    world: Option<SharedWorld>,
+}
+
+impl Remote {
+    /// Starts connecting to the server at `url`, such as `ws://localhost:7878`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connect(url: &str, proxy: EventLoopProxy<UserEvent>) -> Result<Self> {
+        use std::sync::mpsc::{self, TryRecvError};
+
+        use anyhow::Context;
+        use tungstenite::client::IntoClientRequest;
+
+        use crate::serve::Client;
+
+        /// How long the thread waits for the server before sending the window's edits.
+        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+        url.into_client_request()
+            .with_context(|| format!("invalid server URL {url:?}"))?;
+        let (outgoing, edits) = mpsc::channel();
+        let address = url.to_string();
+        std::thread::spawn(move || {
+            let relay = || -> Result<()> {
+                let (mut client, world) = Client::connect(&address)?;
+                if proxy
+                    .send_event(UserEvent::Shared(ServerMessage::Welcome(world)))
+                    .is_err()
+                {
+                    return Ok(());
+                }
+                client.set_timeout(Some(POLL_INTERVAL))?;
+                loop {
+                    loop {
+                        match edits.try_recv() {
+                            Ok(edit) => client.send(&edit)?,
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => return Ok(()),
+                        }
+                    }
+                    if let Some(message) = client.receive()?
+                        && proxy.send_event(UserEvent::Shared(message)).is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+            };
+            if let Err(e) = relay() {
+                let _ = proxy.send_event(UserEvent::Disconnected(format!("{e:#}")));
+            }
+        });
+        Ok(Self {
+            url: url.to_string(),
+            outgoing,
+            world: None,
+        })
+    }
+
+    /// Starts connecting to the server at `url`, such as `ws://localhost:7878`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn connect(url: &str, proxy: EventLoopProxy<UserEvent>) -> Result<Self> {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen::prelude::*;
+
+        use crate::shared::PROTOCOL_VERSION;
+
+        let socket = web_sys::WebSocket::new(url)
+            .map_err(|e| anyhow::anyhow!("unable to connect to {url}: {e:?}"))?;
+        socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+        let opened = socket.clone();
+        let on_open = Closure::<dyn FnMut()>::new(move || {
+            let hello = ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+            };
+            if let Err(e) = opened.send_with_u8_array(&hello.to_bytes()) {
+                log::error!("Unable to greet the server: {e:?}");
+            }
+        });
+        let received = proxy.clone();
+        let on_message = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MessageEvent| {
+            let bytes = js_sys::Uint8Array::new(&event.data()).to_vec();
+            let _ = received.send_event(match ServerMessage::from_bytes(&bytes) {
+                Ok(message) => UserEvent::Shared(message),
+                Err(e) => UserEvent::Disconnected(format!("{e:#}")),
+            });
+        });
+        let on_close = Closure::<dyn FnMut(_)>::new(move |event: web_sys::CloseEvent| {
+            let reason = format!("the connection closed ({})", event.code());
+            let _ = proxy.send_event(UserEvent::Disconnected(reason));
+        });
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        on_open.forget();
+        on_message.forget();
+        on_close.forget();
+        Ok(Self {
+            url: url.to_string(),
+            socket,
+            world: None,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The shared world, once the server has sent it.
+    pub fn world(&self) -> Option<&SharedWorld> {
+        self.world.as_ref()
+    }
+
+    /// Sends an edit to the server, which shares it with every client.
+    pub fn send(&self, message: ClientMessage) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = self.outgoing.send(message);
+        #[cfg(target_arch = "wasm32")]
+        if let Err(e) = self.socket.send_with_u8_array(&message.to_bytes()) {
+            log::error!("Unable to send to the server: {e:?}");
+        }
+    }
+
+    /// Brings the world up to date with a message from the server.
+    pub fn apply(&mut self, message: ServerMessage) -> Result<()> {
+        match (&mut self.world, message) {
+            (world, ServerMessage::Welcome(shared)) => *world = Some(shared),
+            (Some(world), message) => world.apply(message)?,
+            (None, ServerMessage::Refused(reason)) => bail!("the server refused: {reason}"),
+            (None, message) => bail!("expected a welcome from the server, got {message:?}"),
+        }
+        Ok(())
+    }
+}
@@ -1,116 +1,828 @@
-use crate::conway::ConwayCompute;
+use crate::chunk_display::ChunkDisplay;
+use crate::chunks::ChunkedWorld;
+use crate::conway::{ConwayCompute, ConwayConfig, GRID_SIZE};
+use crate::display::Display;
 use crate::gpu::GpuContext;
+use crate::grid::{Boundary, Grid, Rect};
+use crate::guides::{self, GuideDisplay, Guides};
+use crate::hashlife::HashLife;
+use crate::macrocell;
+use crate::options::{MAX_SIZE, Options};
+use crate::overlay::{GhostMode, Overlay};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::recording::{Recorder, RecordingOptions};
+use crate::rule::{Neighbourhood, Rule, Variant};
+use crate::scheme::UpdateScheme;
+use crate::session::Session;
+use crate::surface::{Surface, SurfaceDisplay};
+use crate::tiled::TiledWorld;
+use crate::tiled_display::TiledDisplay;
+use crate::viewport::{MIN_ZOOM, Viewport};
+use anyhow::{Result, bail};
+use winit::dpi::PhysicalPosition;
+use winit::keyboard::PhysicalKey;
 use winit::window::Window;
 
+/// The simulation engine, chosen by the boundary.
+// There is only ever one, so its size does not matter
+#[allow(clippy::large_enum_variant)]
+enum World {
+    /// A single texture that wraps or is surrounded by dead cells.
+    Bounded {
+        conway: ConwayCompute,
+        display: Display,
+        /// Draws the whole world into the minimap.
+        minimap: Display,
+    },
+    /// Chunks allocated as patterns spread.
+    Unbounded {
+        chunks: ChunkedWorld,
+        display: ChunkDisplay,
+    },
+    /// An unbounded world on the CPU, jumping many generations a frame. The part in view is
+    /// uploaded into `window`'s texture to be drawn.
+    HashLife {
+        life: HashLife,
+        window: ConwayCompute,
+        display: Display,
+    },
+    /// A bounded Life world too large for a single texture, split into tiles.
+    Tiled {
+        tiles: TiledWorld,
+        display: TiledDisplay,
+        minimap: TiledDisplay,
+    },
+}
+
+/// The most generations a bounded world is stepped in one frame when asked for several.
+const MAX_STEPS_PER_FRAME: u32 = 64;
+
+/// Cells along each side of the window a HashLife world is drawn through, enough for the
+/// smallest zoom.
+const HASHLIFE_WINDOW: u32 = (GRID_SIZE as f32 / MIN_ZOOM) as u32;
+
+/// The HashLife window for cells of a shape. Hexagonal rows lean, so the cells in view span half
+/// as many columns again.
+fn hashlife_window_size(neighbourhood: Neighbourhood) -> u32 {
+    match neighbourhood {
+        Neighbourhood::Hexagonal => HASHLIFE_WINDOW * 3 / 2,
+        Neighbourhood::Moore | Neighbourhood::Triangular => HASHLIFE_WINDOW,
+    }
+}
+
 pub struct Renderer {
     pub name: String,
-    conway: ConwayCompute,
-    display_pipeline: wgpu::RenderPipeline,
-    display_bind_group: wgpu::BindGroup,
+    world: World,
+    overlay: Overlay,
+    seed: u64,
+    variant: Variant,
+    viewport: Viewport,
+    /// Draws a bounded world on a 3D shape instead of flat.
+    surface: Option<SurfaceDisplay>,
+    /// Where the ghost is in the world, which differs from where it is in a HashLife window.
+    ghost: ((i32, i32), GhostMode),
+    guides: Guides,
+    guide_display: GuideDisplay,
+    /// Shown in a corner over the world; the web build shows it in the page instead.
+    message: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    recorder: Option<Recorder>,
+    /// Why the world or its recording stopped by itself, until the app takes it to report.
+    failure: Option<String>,
 }
 
 impl Renderer {
-    pub fn new(name: &str, ctx: &GpuContext) -> Self {
-        let conway = ConwayCompute::new(&ctx.device, &ctx.queue);
+    /// Starts the world the options describe, split into tiles if it is larger than a single
+    /// texture allows.
+    pub fn new(name: &str, ctx: &GpuContext, options: &Options) -> Self {
+        if options.size > MAX_SIZE && options.boundary != Boundary::Unbounded {
+            match Self::tiled(name, ctx, options) {
+                Ok(renderer) => return renderer,
+                Err(e) => log::warn!("Unable to split the world into tiles: {e:#}"),
+            }
+        }
+        Self::from_session(name, ctx, &options.session())
+    }
 
-        // Create display shader
-        let display_shader = &ctx
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("Display Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("display.wgsl").into()),
-            });
+    /// Starts a world of tiles from a soup, or from the options' pattern in the centre, without
+    /// holding all its cells on the CPU. Only Life is run in tiles, and synchronously.
+    fn tiled(name: &str, ctx: &GpuContext, options: &Options) -> Result<Self> {
+        if options.variant != Variant::Life || options.scheme != UpdateScheme::Synchronous {
+            bail!("only Life worlds updated synchronously are split into tiles");
+        }
+        let rule = options.starting_rule();
+        let mut tiles = TiledWorld::new(
+            &ctx.device,
+            (options.size, options.size),
+            rule,
+            options.boundary,
+            tile_limit(ctx),
+        )?;
+        let seed = options.seed.unwrap_or_else(rand::random);
+        match &options.pattern {
+            Some(pattern) => {
+                let (width, height) = tiles.size();
+                let origin = (
+                    (i64::from(width) - i64::from(pattern.grid.width)) / 2,
+                    (i64::from(height) - i64::from(pattern.grid.height)) / 2,
+                );
+                tiles.stamp(
+                    &ctx.device,
+                    &ctx.queue,
+                    &pattern.grid,
+                    (origin.0 as i32, origin.1 as i32),
+                );
+            }
+            None => tiles.soup(&ctx.device, &ctx.queue, options.density, seed),
+        }
+        let viewport = Viewport::new(tiles.size(), rule.neighbourhood);
+        Ok(Self::from_tiles(name, ctx, tiles, viewport, seed))
+    }
 
-        // Create bind group layout for Conway state buffer
-        let display_bind_group_layout =
-            &ctx.device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("Display Bind Group Layout"),
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        },
-                        count: None,
-                    }],
-                });
-
-        // Create display bind group
-        let display_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Display Bind Group"),
-            layout: &display_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(conway.get_current_texture_view()),
-            }],
-        });
-
-        // Create display pipeline
-        let display_pipeline_layout =
-            ctx.device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Display Pipeline Layout"),
-                    bind_group_layouts: &[&display_bind_group_layout],
-                    push_constant_ranges: &[],
-                });
-
-        let display_pipeline = ctx
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Display Pipeline"),
-                layout: Some(&display_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &display_shader,
-                    entry_point: Some("vs_main"),
-                    buffers: &[], // No vertex buffers - we generate fullscreen triangle in shader
-                    compilation_options: Default::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &display_shader,
-                    entry_point: Some("fs_main"),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: ctx.surface.format(),
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                    compilation_options: Default::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None, // No culling for fullscreen triangle
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
-                cache: None,
-            });
+    fn from_tiles(
+        name: &str,
+        ctx: &GpuContext,
+        tiles: TiledWorld,
+        viewport: Viewport,
+        seed: u64,
+    ) -> Self {
+        log::info!(
+            "Split the {} × {} world into {} × {} tiles",
+            tiles.size().0,
+            tiles.size().1,
+            tiles.tiles().0,
+            tiles.tiles().1
+        );
+        let overlay = Overlay::new(&ctx.device, &ctx.queue);
+        let display = TiledDisplay::new(
+            &ctx.device,
+            &tiles,
+            &overlay,
+            ctx.surface.format(),
+            &viewport,
+        );
+        let minimap = TiledDisplay::new(
+            &ctx.device,
+            &tiles,
+            &overlay,
+            ctx.surface.format(),
+            &Viewport::new(tiles.size(), tiles.rule().neighbourhood),
+        );
+        Self {
+            name: name.to_string(),
+            world: World::Tiled {
+                tiles,
+                display,
+                minimap,
+            },
+            overlay,
+            seed,
+            variant: Variant::Life,
+            viewport,
+            surface: None,
+            ghost: ((0, 0), GhostMode::Hidden),
+            guides: Guides::default(),
+            guide_display: GuideDisplay::new(&ctx.device, ctx.surface.format()),
+            message: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recorder: None,
+            failure: None,
+        }
+    }
+
+    /// Resumes a session, in tiles if its grid is larger than a single texture allows, or else
+    /// cropped to fit one.
+    pub fn from_session(name: &str, ctx: &GpuContext, session: &Session) -> Self {
+        let (width, height) = (session.grid.width, session.grid.height);
+        if session.boundary != Boundary::Unbounded && width.max(height) > MAX_SIZE {
+            match Self::tiled_session(ctx, session) {
+                Ok(tiles) => {
+                    return Self::from_tiles(name, ctx, tiles, session.viewport, session.seed);
+                }
+                Err(e) => log::warn!("Unable to split the world into tiles, so cropping it: {e:#}"),
+            }
+            let cropped = Session {
+                grid: session
+                    .grid
+                    .crop(0, 0, width.min(MAX_SIZE), height.min(MAX_SIZE)),
+                ..session.clone()
+            };
+            return Self::from_session(name, ctx, &cropped);
+        }
+        let overlay = Overlay::new(&ctx.device, &ctx.queue);
+
+        let world = if session.boundary == Boundary::Unbounded {
+            let mut chunks = ChunkedWorld::new(
+                &ctx.device,
+                &ctx.queue,
+                session.rule,
+                &session.grid,
+                session.origin,
+            );
+            chunks.set_generation(session.generation);
+            let display = ChunkDisplay::new(&ctx.device, &overlay, ctx.surface.format());
+            World::Unbounded { chunks, display }
+        } else {
+            let config = ConwayConfig {
+                rule: session.rule,
+                boundary: session.boundary,
+                tile_size: None,
+                variant: session.variant,
+                scheme: session.scheme,
+            };
+            let mut conway = ConwayCompute::new(&ctx.device, &ctx.queue, &session.grid, config);
+            conway.set_generation(&ctx.queue, session.generation);
+            let display = Display::new(
+                &ctx.device,
+                &conway,
+                &overlay,
+                ctx.surface.format(),
+                &session.viewport,
+            );
+            let minimap = Display::new(
+                &ctx.device,
+                &conway,
+                &overlay,
+                ctx.surface.format(),
+                &Viewport::new(conway.size(), session.rule.neighbourhood),
+            );
+            World::Bounded {
+                conway,
+                display,
+                minimap,
+            }
+        };
+
+        Self {
+            name: name.to_string(),
+            world,
+            overlay,
+            seed: session.seed,
+            variant: session.variant,
+            viewport: session.viewport,
+            surface: None,
+            ghost: ((0, 0), GhostMode::Hidden),
+            guides: Guides::default(),
+            guide_display: GuideDisplay::new(&ctx.device, ctx.surface.format()),
+            message: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recorder: None,
+            failure: None,
+        }
+    }
+
+    fn tiled_session(ctx: &GpuContext, session: &Session) -> Result<TiledWorld> {
+        if session.variant != Variant::Life || session.scheme != UpdateScheme::Synchronous {
+            bail!("only Life worlds updated synchronously are split into tiles");
+        }
+        let size = (session.grid.width, session.grid.height);
+        let mut tiles = TiledWorld::new(
+            &ctx.device,
+            size,
+            session.rule,
+            session.boundary,
+            tile_limit(ctx),
+        )?;
+        tiles.write(&ctx.device, &ctx.queue, &session.grid);
+        tiles.set_generation(session.generation);
+        Ok(tiles)
+    }
+
+    /// Runs the session's cells as an unbounded world with HashLife, jumping 2^`step`
+    /// generations a frame. HashLife only knows live and dead, so colours are lost.
+    pub fn hashlife(name: &str, ctx: &GpuContext, session: &Session, step: u32) -> Result<Self> {
+        let origin = (i64::from(session.origin.0), i64::from(session.origin.1));
+        let mut life = HashLife::from_grid(session.rule, &session.grid, origin)?;
+        life.set_generation(session.generation);
+        life.set_step_exponent(step);
+        Ok(Self::from_hashlife(
+            name,
+            ctx,
+            life,
+            session.viewport,
+            session.seed,
+        ))
+    }
+
+    /// Runs a universe built elsewhere, such as one read from a macrocell file, with HashLife.
+    pub fn from_hashlife(
+        name: &str,
+        ctx: &GpuContext,
+        life: HashLife,
+        viewport: Viewport,
+        seed: u64,
+    ) -> Self {
+        let rule = life.rule();
+        let overlay = Overlay::new(&ctx.device, &ctx.queue);
+        let config = ConwayConfig {
+            rule,
+            boundary: Boundary::Dead,
+            tile_size: None,
+            variant: Variant::Life,
+            scheme: UpdateScheme::Synchronous,
+        };
+        let size = hashlife_window_size(rule.neighbourhood);
+        let empty = Grid::new(size, size);
+        let window = ConwayCompute::new(&ctx.device, &ctx.queue, &empty, config);
+        let display = Display::new(
+            &ctx.device,
+            &window,
+            &overlay,
+            ctx.surface.format(),
+            &viewport,
+        );
 
         Self {
             name: name.to_string(),
+            world: World::HashLife {
+                life,
+                window,
+                display,
+            },
+            overlay,
+            seed,
+            variant: Variant::Life,
+            viewport,
+            surface: None,
+            ghost: ((0, 0), GhostMode::Hidden),
+            guides: Guides::default(),
+            guide_display: GuideDisplay::new(&ctx.device, ctx.surface.format()),
+            message: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recorder: None,
+            failure: None,
+        }
+    }
+
+    /// The size of a bounded world, or the number of cells an unbounded world shows at zoom 1.
+    pub fn grid_size(&self) -> (u32, u32) {
+        match &self.world {
+            World::Bounded { conway, .. } => conway.size(),
+            World::Tiled { tiles, .. } => tiles.size(),
+            World::Unbounded { .. } | World::HashLife { .. } => (GRID_SIZE, GRID_SIZE),
+        }
+    }
+
+    /// Seed of the random soup the world started from.
+    #[cfg(target_arch = "wasm32")]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rule(&self) -> Rule {
+        match &self.world {
+            World::Bounded { conway, .. } => conway.config().rule,
+            World::Unbounded { chunks, .. } => chunks.rule(),
+            World::HashLife { life, .. } => life.rule(),
+            World::Tiled { tiles, .. } => tiles.rule(),
+        }
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        match &self.world {
+            World::Bounded { conway, .. } => conway.config().boundary,
+            World::Tiled { tiles, .. } => tiles.boundary(),
+            World::Unbounded { .. } | World::HashLife { .. } => Boundary::Unbounded,
+        }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn generation(&self) -> u64 {
+        match &self.world {
+            World::Bounded { conway, .. } => conway.generation(),
+            World::Unbounded { chunks, .. } => chunks.generation(),
+            World::HashLife { life, .. } => life.generation(),
+            World::Tiled { tiles, .. } => tiles.generation(),
+        }
+    }
+
+    /// The most steps `render` takes in a frame. Unbounded worlds grow their chunks between
+    /// steps, HashLife already jumps many generations a step, and a step of a world in tiles is
+    /// a frame's work.
+    pub fn max_steps_per_frame(&self) -> u32 {
+        match &self.world {
+            World::Bounded { .. } => MAX_STEPS_PER_FRAME,
+            World::Unbounded { .. } | World::HashLife { .. } | World::Tiled { .. } => 1,
+        }
+    }
+
+    /// Which cells change each generation. Only bounded worlds update other than synchronously.
+    pub fn scheme(&self) -> UpdateScheme {
+        match &self.world {
+            World::Bounded { conway, .. } => conway.config().scheme,
+            World::Unbounded { .. } | World::HashLife { .. } | World::Tiled { .. } => {
+                UpdateScheme::Synchronous
+            }
+        }
+    }
+
+    pub fn set_scheme(&mut self, ctx: &GpuContext, scheme: UpdateScheme) -> Result<()> {
+        let World::Bounded { conway, .. } = &mut self.world else {
+            bail!("only bounded worlds can use the {scheme} update scheme");
+        };
+        if self.variant == Variant::Sand {
+            bail!("falling sand has its own way of updating");
+        }
+        let config = ConwayConfig {
+            scheme,
+            ..*conway.config()
+        };
+        conway.set_config(&ctx.queue, config);
+        Ok(())
+    }
+
+    /// The shape the world is drawn on, if it is not drawn flat.
+    pub fn surface(&self) -> Option<Surface> {
+        self.surface.as_ref().map(SurfaceDisplay::surface)
+    }
+
+    /// Draws the world on `surface`, or flat again for `None`. Only bounded worlds whose edges
+    /// meet as the shape's do can be drawn on it.
+    pub fn set_surface(&mut self, ctx: &GpuContext, surface: Option<Surface>) -> Result<()> {
+        let Some(surface) = surface else {
+            self.surface = None;
+            return Ok(());
+        };
+        let World::Bounded { conway, .. } = &self.world else {
+            bail!("only bounded worlds can be drawn on a {surface}");
+        };
+        if conway.config().boundary != surface.boundary() {
+            bail!(
+                "a {surface} needs the {:?} boundary, not {:?}",
+                surface.boundary(),
+                conway.config().boundary
+            );
+        }
+        self.surface = Some(SurfaceDisplay::new(
+            &ctx.device,
             conway,
-            display_pipeline,
-            display_bind_group,
+            ctx.surface.format(),
+            surface,
+        ));
+        Ok(())
+    }
+
+    /// Passes a key to the camera looking at a 3D shape, returning whether it moves the camera.
+    pub fn camera_key(&mut self, key: PhysicalKey, pressed: bool) -> bool {
+        self.surface
+            .as_mut()
+            .is_some_and(|surface| surface.handle_key(key, pressed))
+    }
+
+    /// How many generations, as a power of two, a HashLife world jumps each frame.
+    pub fn hashlife_step(&self) -> Option<u32> {
+        match &self.world {
+            World::HashLife { life, .. } => Some(life.step_exponent()),
+            _ => None,
         }
     }
 
-    pub fn render(&mut self, window: &Window, ctx: &GpuContext) -> Result<(), wgpu::SurfaceError> {
+    pub fn set_hashlife_step(&mut self, step: u32) {
+        if let World::HashLife { life, .. } = &mut self.world {
+            life.set_step_exponent(step);
+        }
+    }
+
+    /// Copies a rectangle of the latest generation of a bounded world back to the CPU.
+    pub fn read_region(
+        &self,
+        ctx: &GpuContext,
+        rect: Rect,
+    ) -> impl Future<Output = Result<Grid>> + use<> {
+        let (mut bounded, mut tiled) = (None, None);
+        match &self.world {
+            World::Bounded { conway, .. } => {
+                bounded = Some(conway.read_region(&ctx.device, &ctx.queue, rect));
+            }
+            World::Tiled { tiles, .. } => {
+                tiled = Some(tiles.read_region(&ctx.device, &ctx.queue, rect));
+            }
+            World::Unbounded { .. } | World::HashLife { .. } => {}
+        }
+        async move {
+            match (bounded, tiled) {
+                (Some(cells), _) => cells.await,
+                (_, Some(cells)) => cells.await,
+                (None, None) => bail!("regions can only be read from bounded worlds"),
+            }
+        }
+    }
+
+    /// Captures everything needed to resume the simulation later.
+    pub fn session(&self, ctx: &GpuContext) -> impl Future<Output = Result<Session>> + use<> {
+        // Only one of these is set, as each world has its own way of reading its cells
+        let (mut bounded, mut unbounded, mut cpu, mut tiled) = (None, None, None, None);
+        let generation = match &self.world {
+            World::Bounded { conway, .. } => {
+                bounded = Some(conway.read(&ctx.device, &ctx.queue));
+                conway.generation()
+            }
+            World::Unbounded { chunks, .. } => {
+                unbounded = Some(chunks.read(&ctx.device, &ctx.queue));
+                chunks.generation()
+            }
+            World::HashLife { life, .. } => {
+                cpu = Some(life.to_grid().and_then(|(grid, (x, y))| {
+                    let origin = (i32::try_from(x)?, i32::try_from(y)?);
+                    Ok((grid, origin))
+                }));
+                life.generation()
+            }
+            World::Tiled { tiles, .. } => {
+                tiled = Some(tiles.read(&ctx.device, &ctx.queue));
+                tiles.generation()
+            }
+        };
+        let rule = self.rule();
+        let boundary = self.boundary();
+        let seed = self.seed;
+        let variant = self.variant;
+        let scheme = self.scheme();
+        let viewport = self.viewport;
+        async move {
+            let (grid, origin) = match (bounded, unbounded, cpu, tiled) {
+                (Some(cells), _, _, _) => (cells.await?, (0, 0)),
+                (_, Some(cells), _, _) => cells.await?,
+                (_, _, Some(cells), _) => cells?,
+                (_, _, _, Some(cells)) => (cells.await?, (0, 0)),
+                (None, None, None, None) => unreachable!(),
+            };
+            Ok(Session {
+                grid,
+                rule,
+                boundary,
+                variant,
+                scheme,
+                origin,
+                generation,
+                seed,
+                viewport,
+            })
+        }
+    }
+
+    /// The whole world as a macrocell pattern. A HashLife world is written as it is, however
+    /// large; any other is read back from the GPU first.
+    pub fn macrocell(&self, ctx: &GpuContext) -> impl Future<Output = Result<String>> + use<> {
+        let written = match &self.world {
+            World::HashLife { life, .. } => Some(macrocell::write(life)),
+            World::Bounded { .. } | World::Unbounded { .. } | World::Tiled { .. } => None,
+        };
+        let session = written.is_none().then(|| self.session(ctx));
+        async move {
+            match (written, session) {
+                (Some(text), _) => Ok(text),
+                (None, Some(session)) => {
+                    let session = session.await?;
+                    let origin = (i64::from(session.origin.0), i64::from(session.origin.1));
+                    let mut life = HashLife::from_grid(session.rule, &session.grid, origin)?;
+                    life.set_generation(session.generation);
+                    Ok(macrocell::write(&life))
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// The grid cell under a cursor position given in window pixels.
+    pub fn cell_at(&self, window: &Window, position: PhysicalPosition<f64>) -> (i32, i32) {
+        let position = self
+            .viewport
+            .grid_position(self.grid_size(), window_fraction(window, position));
+        self.rule().neighbourhood.cell_at(position)
+    }
+
+    /// Drags the view so the cell under `from` ends up under `to`.
+    pub fn pan(
+        &mut self,
+        ctx: &GpuContext,
+        window: &Window,
+        from: PhysicalPosition<f64>,
+        to: PhysicalPosition<f64>,
+    ) {
+        let (from, to) = (window_fraction(window, from), window_fraction(window, to));
+        self.viewport
+            .pan(self.grid_size(), (to.0 - from.0, to.1 - from.1));
+        self.update_view(ctx);
+    }
+
+    /// Zooms the view by `factor` about a cursor position.
+    pub fn zoom(
+        &mut self,
+        ctx: &GpuContext,
+        window: &Window,
+        position: PhysicalPosition<f64>,
+        factor: f32,
+    ) {
+        self.viewport
+            .zoom_about(self.grid_size(), window_fraction(window, position), factor);
+        self.update_view(ctx);
+    }
+
+    fn update_view(&self, ctx: &GpuContext) {
+        // The chunk and HashLife displays upload the view as they draw
+        match &self.world {
+            World::Bounded { display, .. } => display.set_viewport(&ctx.queue, &self.viewport),
+            World::Tiled { display, .. } => display.set_viewport(&ctx.queue, &self.viewport),
+            World::Unbounded { .. } | World::HashLife { .. } => {}
+        }
+    }
+
+    /// Shows `cells` as a preview with its top-left corner at `origin`, or hides the preview.
+    pub fn show_ghost(&mut self, ctx: &GpuContext, cells: Option<&Grid>, origin: (i32, i32)) {
+        let mode = match cells {
+            None => GhostMode::Hidden,
+            Some(_) if self.wraps() => GhostMode::Wrapped,
+            Some(_) => GhostMode::Clipped,
+        };
+        if let Some(cells) = cells {
+            self.overlay.set_ghost_cells(&ctx.device, &ctx.queue, cells);
+        }
+        self.ghost = (origin, mode);
+        self.update_ghost(&ctx.queue);
+    }
+
+    /// Moves the preview without changing its cells.
+    pub fn move_ghost(&mut self, ctx: &GpuContext, origin: (i32, i32)) {
+        let mode = if self.wraps() {
+            GhostMode::Wrapped
+        } else {
+            GhostMode::Clipped
+        };
+        self.ghost = (origin, mode);
+        self.update_ghost(&ctx.queue);
+    }
+
+    fn update_ghost(&mut self, queue: &wgpu::Queue) {
+        let ((x, y), mode) = self.ghost;
+        let origin = match &self.world {
+            World::HashLife { .. } => {
+                let ((left, top), _) = self.hashlife_window();
+                ((i64::from(x) - left) as i32, (i64::from(y) - top) as i32)
+            }
+            _ => (x, y),
+        };
+        self.overlay.set_ghost(queue, origin, mode);
+    }
+
+    /// The top-left corner of the HashLife window around the view, and the view of the window
+    /// matching the view of the world.
+    fn hashlife_window(&self) -> ((i64, i64), Viewport) {
+        let (x, y) = self.viewport.centre;
+        let neighbourhood = self.rule().neighbourhood;
+        let size = hashlife_window_size(neighbourhood);
+        let half = i64::from(size / 2);
+        // Hexagonal rows lean half a cell left for each row down, which the window's own rows
+        // do from its top instead of from row 0
+        let lean = match neighbourhood {
+            Neighbourhood::Hexagonal => 0.5,
+            Neighbourhood::Moore | Neighbourhood::Triangular => 0.0,
+        };
+        let origin = (
+            (x + y * lean).floor() as i64 - half,
+            y.floor() as i64 - half,
+        );
+        let view = Viewport {
+            centre: (
+                x - origin.0 as f32 + origin.1 as f32 * lean,
+                y - origin.1 as f32,
+            ),
+            zoom: self.viewport.zoom * size as f32 / GRID_SIZE as f32,
+        };
+        (origin, view)
+    }
+
+    /// Shows grid lines and the minimap as `guides` say, where the world allows.
+    pub fn set_guides(&mut self, guides: Guides) {
+        self.guides = guides;
+    }
+
+    /// Shows `message` over the world until it is replaced or cleared.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_message(&mut self, message: Option<&str>) {
+        self.message = message.map(str::to_string);
+    }
+
+    pub fn show_selection(&mut self, ctx: &GpuContext, selection: Option<Rect>) {
+        self.overlay.set_selection(&ctx.queue, selection);
+    }
+
+    /// Replaces the whole world with `grid`, and starts counting generations again. In a bounded
+    /// world `grid` must match the grid size; in an unbounded one it is placed at the origin.
+    pub fn restart(&mut self, ctx: &GpuContext, grid: &Grid) {
+        match &mut self.world {
+            World::Bounded { conway, .. } => {
+                conway.write(&ctx.queue, grid);
+                conway.set_generation(&ctx.queue, 0);
+            }
+            World::Unbounded { chunks, .. } => {
+                chunks.clear(&ctx.queue);
+                chunks.stamp(&ctx.device, &ctx.queue, grid, (0, 0));
+                chunks.set_generation(0);
+            }
+            World::HashLife { life, .. } => match HashLife::from_grid(life.rule(), grid, (0, 0)) {
+                Ok(mut restarted) => {
+                    restarted.set_step_exponent(life.step_exponent());
+                    *life = restarted;
+                }
+                Err(e) => log::error!("Unable to restart: {e:#}"),
+            },
+            World::Tiled { tiles, .. } => {
+                tiles.write(&ctx.device, &ctx.queue, grid);
+                tiles.set_generation(0);
+            }
+        }
+    }
+
+    /// Overwrites a bounded world with `grid`, of the same size, now at `generation`.
+    pub fn overwrite(&mut self, ctx: &GpuContext, grid: &Grid, generation: u64) {
+        match &mut self.world {
+            World::Bounded { conway, .. } => {
+                conway.write(&ctx.queue, grid);
+                conway.set_generation(&ctx.queue, generation);
+            }
+            World::Tiled { tiles, .. } => {
+                tiles.write(&ctx.device, &ctx.queue, grid);
+                tiles.set_generation(generation);
+            }
+            World::Unbounded { .. } | World::HashLife { .. } => {}
+        }
+    }
+
+    /// Writes `cells` into the world with their top-left corner at `origin`.
+    pub fn stamp(&mut self, ctx: &GpuContext, cells: &Grid, origin: (i32, i32)) {
+        match &mut self.world {
+            World::Bounded { conway, .. } => {
+                conway.stamp(&ctx.queue, cells, origin.0, origin.1);
+            }
+            World::Unbounded { chunks, .. } => {
+                chunks.stamp(&ctx.device, &ctx.queue, cells, origin);
+            }
+            World::HashLife { life, .. } => {
+                if let Err(e) = life.stamp(cells, (i64::from(origin.0), i64::from(origin.1))) {
+                    self.failure = Some(format!("Unable to draw there: {e:#}"));
+                }
+            }
+            World::Tiled { tiles, .. } => {
+                tiles.stamp(&ctx.device, &ctx.queue, cells, origin);
+            }
+        }
+    }
+
+    /// Records every generation from now on, until `stop_recording` is called.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start_recording(&mut self, ctx: &GpuContext, options: &RecordingOptions) -> Result<()> {
+        let World::Bounded { conway, .. } = &self.world else {
+            bail!("only bounded worlds can be recorded");
+        };
+        let mut recorder = Recorder::new(&ctx.device, &ctx.queue, conway, options)?;
+        recorder.capture(&ctx.device, &ctx.queue, conway)?;
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Finishes the recording, returning how many frames it holds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stop_recording(&mut self) -> Option<u32> {
+        self.recorder.take().map(|recorder| recorder.frames())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Why the world or its recording stopped by itself since this was last asked, if either did.
+    pub fn take_failure(&mut self) -> Option<String> {
+        self.failure.take()
+    }
+
+    fn wraps(&self) -> bool {
+        self.boundary() == Boundary::Torus
+    }
+
+    /// Steps the world up to `steps` times, at most `max_steps_per_frame`, then draws it.
+    /// Returns the steps taken.
+    pub fn render(
+        &mut self,
+        window: &Window,
+        ctx: &GpuContext,
+        steps: u32,
+    ) -> Result<u32, wgpu::SurfaceError> {
         window.request_redraw();
 
         if !ctx.surface.is_configured() {
-            return Ok(());
+            return Ok(0);
         }
+        let mut taken = steps;
 
         let output = ctx.surface.get_current_texture()?;
         let view = output
@@ -123,40 +835,148 @@ impl Renderer {
                 label: Some(&format!("{:?} Renderer Encoder", self.name)),
             });
 
-        // Step Conway's Game of Life
-        self.conway.step(&mut encoder);
-
-        // Render Conway's Game of Life
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Conway Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.1,
-                            b: 0.1,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+        let size = self.grid_size();
+        let target_size = (output.texture.width(), output.texture.height());
+        // Only bounded worlds have a whole to show in the minimap
+        let bounded = matches!(self.world, World::Bounded { .. } | World::Tiled { .. });
+        let minimap_area =
+            (self.guides.minimap && bounded).then(|| guides::minimap_area(target_size, size));
+        if let World::HashLife { life, .. } = &mut self.world {
+            for step in 0..steps {
+                if let Err(e) = life.step() {
+                    self.failure = Some(format!("Stepping stopped: {e:#}"));
+                    taken = step;
+                    break;
+                }
+            }
+        }
+        // The window follows the view, and the ghost with it
+        let (window_origin, window_view) = self.hashlife_window();
+        if let World::HashLife { .. } = self.world {
+            self.update_ghost(&ctx.queue);
+        }
+        match &mut self.world {
+            World::Bounded {
+                conway,
+                display,
+                minimap,
+            } => {
+                // Step Conway's Game of Life, submitting each step on its own while recording so
+                // that every generation can be captured
+                for _ in 0..steps {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(recorder) = &mut self.recorder {
+                        let mut encoder = ctx.device.create_command_encoder(&Default::default());
+                        conway.step(&mut encoder);
+                        ctx.queue.submit(std::iter::once(encoder.finish()));
+                        if let Err(e) = recorder.capture(&ctx.device, &ctx.queue, conway) {
+                            self.failure = Some(format!("Recording stopped: {e:#}"));
+                            self.recorder = None;
+                        }
+                        continue;
+                    }
+                    conway.step(&mut encoder);
+                }
+
+                // Render Conway's Game of Life
+                match &mut self.surface {
+                    Some(surface) => surface.draw(
+                        &ctx.device,
+                        &ctx.queue,
+                        &mut encoder,
+                        &view,
+                        target_size,
+                        conway,
+                    ),
+                    None => {
+                        display.draw(&mut encoder, &view, conway, &self.overlay);
+                        if let Some(area) = minimap_area {
+                            minimap.draw_inset(&mut encoder, &view, conway, &self.overlay, area);
+                        }
+                    }
+                }
+            }
+            World::Unbounded { chunks, display } => {
+                taken = (0..steps).take_while(|_| chunks.step(&mut encoder)).count() as u32;
+                display.draw(
+                    &ctx.device,
+                    &ctx.queue,
+                    &mut encoder,
+                    &view,
+                    chunks,
+                    &self.overlay,
+                    &self.viewport,
+                    size,
+                );
+            }
+            World::HashLife {
+                life,
+                window,
+                display,
+            } => {
+                life.upload_window(&ctx.queue, window, window_origin);
+                display.set_viewport(&ctx.queue, &window_view);
+                display.draw(&mut encoder, &view, window, &self.overlay);
+            }
+            World::Tiled {
+                tiles,
+                display,
+                minimap,
+            } => {
+                for _ in 0..steps {
+                    tiles.step(&mut encoder);
+                }
+                display.draw(&mut encoder, &view, tiles, &self.overlay);
+                if let Some(area) = minimap_area {
+                    minimap.draw_inset(&mut encoder, &view, tiles, &self.overlay, area);
+                }
+            }
+        }
 
-            // Draw fullscreen triangle with Conway's state
-            render_pass.set_pipeline(&self.display_pipeline);
-            render_pass.set_bind_group(0, &self.display_bind_group, &[]);
-            render_pass.draw(0..3, 0..1); // 3 vertices for fullscreen triangle
+        // Grid lines only follow the edges of square cells, and neither they nor the minimap
+        // are drawn over a 3D surface; the message always is
+        let neighbourhood = self.rule().neighbourhood;
+        let flat = self.surface.is_none();
+        let grid_lines = flat && self.guides.grid_lines && neighbourhood == Neighbourhood::Moore;
+        let minimap_area = minimap_area.filter(|_| flat);
+        if grid_lines || minimap_area.is_some() || self.message.is_some() {
+            let minimap = Viewport::new(size, neighbourhood);
+            self.guide_display.draw(
+                &ctx.queue,
+                &mut encoder,
+                &view,
+                target_size,
+                grid_lines,
+                &self.viewport,
+                size,
+                bounded.then_some(size),
+                minimap_area.is_some().then_some(&minimap),
+                self.message.as_deref(),
+            );
         }
 
         ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        if let World::Unbounded { chunks, .. } = &mut self.world {
+            chunks.maintain(&ctx.device, &ctx.queue);
+        }
+
         output.present();
 
-        Ok(())
+        Ok(taken)
     }
 }
+
+/// The most words or rows along each side of a tile, allowing for its halo.
+fn tile_limit(ctx: &GpuContext) -> u32 {
+    ctx.device.limits().max_texture_dimension_2d - 2
+}
+
+/// A position in window pixels as a fraction of the window size.
+fn window_fraction(window: &Window, position: PhysicalPosition<f64>) -> (f64, f64) {
+    let size = window.inner_size();
+    (
+        position.x / f64::from(size.width.max(1)),
+        position.y / f64::from(size.height.max(1)),
+    )
+}
@@ -0,0 +1,359 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+
+use crate::sand::MATERIALS;
+
+const MOORE: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+// Golly's hexagonal layout: the Moore neighbourhood without the top-right and bottom-left cells
+const HEXAGONAL: [(i32, i32); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+// Every triangle sharing an edge or a corner with one pointing up; flipped for one pointing down
+const TRIANGULAR_UP: [(i32, i32); 12] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+];
+const TRIANGULAR_DOWN: [(i32, i32); 12] = [
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (2, -1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// The shape of the cells and which of them are neighbours. Every grid is stored as rows and
+/// columns of square cells, following the conventions of Golly and LifeViewer:
+///
+/// - hexagonal cells are the square grid sheared by half a cell a row, so that row `y` sits
+///   `y / 2` cells left of row 0 and the top-right and bottom-left neighbours drop out;
+/// - triangular cells alternate along each row, pointing up where `x + y` is even.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    #[default]
+    Moore,
+    Hexagonal,
+    Triangular,
+}
+
+impl Neighbourhood {
+    /// The number `Neighbourhood` has in the shaders' parameters.
+    pub fn index(self) -> u32 {
+        match self {
+            Neighbourhood::Moore => 0,
+            Neighbourhood::Hexagonal => 1,
+            Neighbourhood::Triangular => 2,
+        }
+    }
+
+    /// Offsets from the cell at (`x`, `y`) to each of its neighbours.
+    pub fn offsets(self, x: i64, y: i64) -> &'static [(i32, i32)] {
+        match self {
+            Neighbourhood::Moore => &MOORE,
+            Neighbourhood::Hexagonal => &HEXAGONAL,
+            Neighbourhood::Triangular if (x + y) % 2 == 0 => &TRIANGULAR_UP,
+            Neighbourhood::Triangular => &TRIANGULAR_DOWN,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Neighbourhood::Moore => Neighbourhood::Hexagonal,
+            Neighbourhood::Hexagonal => Neighbourhood::Triangular,
+            Neighbourhood::Triangular => Neighbourhood::Moore,
+        }
+    }
+
+    /// The rule worlds switched to these cells start with: Conway's Life, B2/S34H for hexagons
+    /// or B4/S345L for triangles.
+    pub fn rule(self) -> Rule {
+        let (birth, survive): (&[u32], &[u32]) = match self {
+            Neighbourhood::Moore => (&[3], &[2, 3]),
+            Neighbourhood::Hexagonal => (&[2], &[3, 4]),
+            Neighbourhood::Triangular => (&[4], &[3, 4, 5]),
+        };
+        let mask = |counts: &[u32]| counts.iter().fold(0, |mask, n| mask | 1 << n);
+        Rule {
+            birth: mask(birth),
+            survive: mask(survive),
+            neighbourhood: self,
+        }
+    }
+
+    /// The most neighbours a cell has.
+    pub fn size(self) -> u32 {
+        match self {
+            Neighbourhood::Moore => 8,
+            Neighbourhood::Hexagonal => 6,
+            Neighbourhood::Triangular => 12,
+        }
+    }
+
+    /// The furthest a neighbour is from a cell in either direction, and so the furthest a
+    /// change spreads in one generation.
+    pub fn reach(self) -> u32 {
+        match self {
+            Neighbourhood::Moore | Neighbourhood::Hexagonal => 1,
+            Neighbourhood::Triangular => 2,
+        }
+    }
+
+    /// Where the centre of a cell is drawn, in cells.
+    pub fn centre_of(self, (x, y): (i32, i32)) -> (f64, f64) {
+        let (x, y) = (f64::from(x) + 0.5, f64::from(y) + 0.5);
+        match self {
+            Neighbourhood::Hexagonal => (x - (y - 0.5) / 2.0, y),
+            Neighbourhood::Moore | Neighbourhood::Triangular => (x, y),
+        }
+    }
+
+    /// The cell drawn at a position given in cells; the inverse of how `display.wgsl` lays cells
+    /// out.
+    pub fn cell_at(self, (x, y): (f64, f64)) -> (i32, i32) {
+        let row = y.floor();
+        match self {
+            Neighbourhood::Moore => (x.floor() as i32, row as i32),
+            Neighbourhood::Hexagonal => {
+                // The nearest cell centre of this row or the ones either side
+                let mut nearest = (0, 0);
+                let mut best = f64::INFINITY;
+                for row in [row - 1.0, row, row + 1.0] {
+                    let shift = row / 2.0;
+                    let column = (x + shift).floor();
+                    let (dx, dy) = (x - (column + 0.5 - shift), y - (row + 0.5));
+                    let distance = dx * dx + dy * dy;
+                    if distance < best {
+                        best = distance;
+                        nearest = (column as i32, row as i32);
+                    }
+                }
+                nearest
+            }
+            Neighbourhood::Triangular => {
+                // Between the centres of two triangles, split by the edge they share
+                let u = x - 0.5;
+                let column = u.floor();
+                let (across, down) = (u - column, y - row);
+                let (column, row) = (column as i32, row as i32);
+                let up = (column + row).rem_euclid(2) == 0;
+                let left = if up {
+                    across < down
+                } else {
+                    across < 1.0 - down
+                };
+                (if left { column } else { column + 1 }, row)
+            }
+        }
+    }
+}
+
+/// A life-like (outer totalistic) rule.
+///
+/// Bit `n` of `birth` is set when a dead cell with `n` live neighbours is born, and bit `n` of
+/// `survive` is set when a live cell with `n` live neighbours survives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub birth: u16,
+    pub survive: u16,
+    pub neighbourhood: Neighbourhood,
+}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule {
+        birth: 1 << 3,
+        survive: (1 << 2) | (1 << 3),
+        neighbourhood: Neighbourhood::Moore,
+    };
+
+    pub fn next(&self, alive: bool, neighbours: u32) -> bool {
+        let mask = if alive { self.survive } else { self.birth };
+        mask & (1 << neighbours) != 0
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+fn parse_counts(digits: &str, neighbourhood: Neighbourhood) -> Result<u16> {
+    let mut mask = 0;
+    for c in digits.chars() {
+        match c.to_digit(10) {
+            Some(n) if n <= neighbourhood.size() => mask |= 1 << n,
+            _ => bail!("invalid neighbour count {c:?} in rule"),
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// Parses `B3/S23` (any case, `/` optional) or the older `S/B` form `23/3`, followed by `H`
+    /// for hexagonal cells or `L` for triangular ones. Only counts up to nine can be written.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+        let (lower, neighbourhood) = if let Some(rest) = lower.strip_suffix('h') {
+            (rest, Neighbourhood::Hexagonal)
+        } else if let Some(rest) = lower.strip_suffix('l') {
+            (rest, Neighbourhood::Triangular)
+        } else {
+            (lower.as_str(), Neighbourhood::Moore)
+        };
+        let rule = |birth, survive| -> Result<Rule> {
+            Ok(Rule {
+                birth: parse_counts(birth, neighbourhood)?,
+                survive: parse_counts(survive, neighbourhood)?,
+                neighbourhood,
+            })
+        };
+
+        if let Some(rest) = lower.strip_prefix('b') {
+            let (birth, survive) = match rest.find('s') {
+                Some(i) => (&rest[..i], &rest[i + 1..]),
+                None => bail!("rule {s:?} is missing the S part"),
+            };
+            return rule(birth.trim_end_matches('/'), survive);
+        }
+
+        match lower.split_once('/') {
+            Some((survive, birth)) => rule(birth, survive),
+            None => bail!("unrecognised rule {s:?}, expected e.g. B3/S23, B2/S34H or 23/3"),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=9)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| char::from(b'0' + n as u8))
+                .collect()
+        };
+        let suffix = match self.neighbourhood {
+            Neighbourhood::Moore => "",
+            Neighbourhood::Hexagonal => "H",
+            Neighbourhood::Triangular => "L",
+        };
+        write!(
+            f,
+            "B{}/S{}{suffix}",
+            digits(self.birth),
+            digits(self.survive)
+        )
+    }
+}
+
+/// Names of the cell colours, in the order of their states in RLE (A, B, C, D) and the palette
+/// of `display.wgsl`.
+pub const COLOUR_NAMES: [&str; 4] = ["white", "red", "blue", "yellow"];
+
+/// How many colours live cells come in. The rule is unchanged: a cell is born with the colour
+/// most of its parents have or, when three parents all differ, the fourth colour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(clap::ValueEnum))]
+pub enum Variant {
+    /// Every cell is the same colour.
+    #[default]
+    Life,
+    /// Two colours.
+    Immigration,
+    /// Four colours.
+    #[cfg_attr(not(target_arch = "wasm32"), value(name = "quadlife"))]
+    QuadLife,
+    /// Falling sand: cells are materials, which fall and flow instead of following the rule.
+    Sand,
+}
+
+impl Variant {
+    pub fn colours(self) -> u8 {
+        match self {
+            Variant::Life => 1,
+            Variant::Immigration => 2,
+            Variant::QuadLife | Variant::Sand => 4,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Variant::Life => Variant::Immigration,
+            Variant::Immigration => Variant::QuadLife,
+            Variant::QuadLife => Variant::Sand,
+            Variant::Sand => Variant::Life,
+        }
+    }
+
+    /// The name of a live cell's colour, from 1, or of its material in falling sand.
+    pub fn colour_name(self, colour: u8) -> &'static str {
+        match self {
+            Variant::Sand => MATERIALS[usize::from(colour)].name,
+            _ => COLOUR_NAMES[usize::from(colour) - 1],
+        }
+    }
+
+    /// The palette `display.wgsl` draws cells with: 0 for colours, 1 for materials.
+    pub fn palette(self) -> u32 {
+        match self {
+            Variant::Sand => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Variant::Life => "Life",
+            Variant::Immigration => "Immigration",
+            Variant::QuadLife => "QuadLife",
+            Variant::Sand => "Sand",
+        })
+    }
+}
+
+/// The colour of a cell born to parents with these colours, given as counts of each colour's
+/// live neighbours: the most common one, or the one missing from three different ones.
+pub fn birth_colour(counts: [u32; 4]) -> u8 {
+    let mut best = 0;
+    for colour in 1..4 {
+        if counts[colour] > counts[best] {
+            best = colour;
+        }
+    }
+    if counts[best] == 1
+        && counts.iter().sum::<u32>() == 3
+        && let Some(missing) = counts.iter().position(|&count| count == 0)
+    {
+        return missing as u8 + 1;
+    }
+    best as u8 + 1
+}
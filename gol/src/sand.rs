@@ -0,0 +1,208 @@
+//! Falling sand, a block cellular automaton on the Margolus neighbourhood: the grid is split
+//! into 2×2 blocks, offset by one cell on alternate generations, and each block is rearranged on
+//! its own. `sand.wgsl` does the same on the GPU; `step` is the reference it is checked against.
+
+use crate::grid::{Boundary, Grid};
+
+pub const EMPTY: u8 = 0;
+pub const SAND: u8 = 1;
+pub const WATER: u8 = 2;
+pub const STONE: u8 = 3;
+pub const FIRE: u8 = 4;
+
+/// How a material behaves, indexed by the cell's state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Material {
+    pub name: &'static str,
+    /// Heavier materials sink through lighter ones, and lighter ones rise.
+    pub weight: u32,
+    /// Never moves, and holds up whatever rests on it.
+    pub fixed: bool,
+    /// Spreads sideways into empty cells when it cannot fall.
+    pub flows: bool,
+    /// The chance in 256 each generation that the cell burns out and is left empty.
+    pub decay: u32,
+    /// A bit for each material that empties the cell when the two share a block.
+    pub quenched_by: u32,
+}
+
+pub const MATERIALS: [Material; 5] = [
+    Material {
+        name: "empty",
+        weight: 1,
+        fixed: false,
+        flows: false,
+        decay: 0,
+        quenched_by: 0,
+    },
+    Material {
+        name: "sand",
+        weight: 3,
+        fixed: false,
+        flows: false,
+        decay: 0,
+        quenched_by: 0,
+    },
+    Material {
+        name: "water",
+        weight: 2,
+        fixed: false,
+        flows: true,
+        decay: 0,
+        quenched_by: 0,
+    },
+    Material {
+        name: "stone",
+        weight: 4,
+        fixed: true,
+        flows: false,
+        decay: 0,
+        quenched_by: 0,
+    },
+    // Lighter than air, so it rises, flickering sideways until it burns out or meets water
+    Material {
+        name: "fire",
+        weight: 0,
+        fixed: false,
+        flows: true,
+        decay: 40,
+        quenched_by: 1 << WATER,
+    },
+];
+
+/// `MATERIALS` as `sand.wgsl` reads it: weight, flags (1 = fixed, 2 = flows), decay and
+/// quenched_by for each, padded to the array's length in the shader.
+pub(crate) fn material_table() -> [[u32; 4]; 8] {
+    let mut table = [[0; 4]; 8];
+    for (row, material) in table.iter_mut().zip(&MATERIALS) {
+        let flags = u32::from(material.fixed) | u32::from(material.flows) << 1;
+        *row = [material.weight, flags, material.decay, material.quenched_by];
+    }
+    table
+}
+
+/// The PCG hash `step` and `sand.wgsl` draw their random numbers from, as do the update schemes.
+pub fn hash(x: u32) -> u32 {
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+/// Where the blocks along one side of `size` cells start in a generation, and whether they
+/// wrap. Only an even number of cells splits into blocks both ways, so an odd side is walled.
+fn block_starts(size: u32, boundary: Boundary, generation: u64) -> (Vec<i64>, bool) {
+    let offset = (generation & 1) as i64;
+    let wraps = boundary == Boundary::Torus && size.is_multiple_of(2);
+    let starts = if wraps {
+        (0..i64::from(size / 2)).map(|i| 2 * i + offset).collect()
+    } else {
+        (0..=i64::from(size / 2)).map(|i| 2 * i - offset).collect()
+    };
+    (starts, wraps)
+}
+
+/// Rearranges one block of cells in the order top-left, top-right, bottom-left, bottom-right.
+/// Cells marked as outside the grid stay put and hold up the others, like stone.
+fn update_block(cells: &mut [u8; 4], inside: [bool; 4], seed: u32) {
+    let material = |cell: u8| &MATERIALS[usize::from(cell)];
+    let mut present = 0;
+    for (&cell, &inside) in cells.iter().zip(&inside) {
+        if inside {
+            present |= 1 << cell;
+        }
+    }
+
+    let mut random = seed;
+    for (cell, &inside) in cells.iter_mut().zip(&inside) {
+        random = hash(random);
+        let m = material(*cell);
+        if inside && (m.quenched_by & present != 0 || random & 255 < m.decay) {
+            *cell = EMPTY;
+        }
+    }
+
+    random = hash(random);
+    let movable = |cells: &[u8; 4], k: usize| inside[k] && !material(cells[k]).fixed;
+    let sinks = |cells: &[u8; 4], top: usize, bottom: usize| {
+        movable(cells, top)
+            && movable(cells, bottom)
+            && material(cells[top]).weight > material(cells[bottom]).weight
+    };
+
+    // Fall straight down, then topple diagonally, starting from a random side
+    let mut moved = false;
+    for column in 0..2 {
+        if sinks(cells, column, column + 2) {
+            cells.swap(column, column + 2);
+            moved = true;
+        }
+    }
+    if !moved {
+        let first = (random & 1) as usize;
+        for top in [first, 1 - first] {
+            if sinks(cells, top, 3 - top) {
+                cells.swap(top, 3 - top);
+                moved = true;
+                break;
+            }
+        }
+    }
+
+    // Flow sideways into empty cells, each row at random
+    if !moved {
+        for row in 0..2 {
+            let (left, right) = (2 * row, 2 * row + 1);
+            let flows = |k: usize| cells[k] == EMPTY || material(cells[k]).flows;
+            if random >> (row + 1) & 1 != 0
+                && movable(cells, left)
+                && movable(cells, right)
+                && (cells[left] == EMPTY) != (cells[right] == EMPTY)
+                && flows(left)
+                && flows(right)
+            {
+                cells.swap(left, right);
+            }
+        }
+    }
+}
+
+/// One generation of falling sand. Blocks wrap around a torus; any other boundary is a wall.
+pub fn step(grid: &Grid, boundary: Boundary, generation: u64) -> Grid {
+    let (width, height) = (grid.width, grid.height);
+    let (columns, wraps_x) = block_starts(width, boundary, generation);
+    let (rows, wraps_y) = block_starts(height, boundary, generation);
+    let seed = hash(generation as u32);
+
+    let mut next = grid.clone();
+    for (j, &y0) in rows.iter().enumerate() {
+        for (i, &x0) in columns.iter().enumerate() {
+            let mut positions = [None; 4];
+            for (k, position) in positions.iter_mut().enumerate() {
+                let (x, y) = (x0 + (k % 2) as i64, y0 + (k / 2) as i64);
+                let x = if wraps_x { x % i64::from(width) } else { x };
+                let y = if wraps_y { y % i64::from(height) } else { y };
+                if (0..i64::from(width)).contains(&x) && (0..i64::from(height)).contains(&y) {
+                    *position = Some((x as u32, y as u32));
+                }
+            }
+            if positions.iter().all(Option::is_none) {
+                continue;
+            }
+
+            let mut cells = [STONE; 4];
+            for (cell, position) in cells.iter_mut().zip(&positions) {
+                if let Some((x, y)) = *position {
+                    *cell = grid.colour(x, y).min(FIRE);
+                }
+            }
+            let block_seed = hash(i as u32 ^ hash(j as u32 ^ seed));
+            update_block(&mut cells, positions.map(|p| p.is_some()), block_seed);
+            for (&cell, position) in cells.iter().zip(&positions) {
+                if let Some((x, y)) = *position {
+                    next.set_colour(x, y, cell);
+                }
+            }
+        }
+    }
+    next
+}
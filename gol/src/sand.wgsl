@@ -0,0 +1,133 @@
+// One generation of falling sand on 2×2 Margolus blocks, one block per invocation. Matches
+// `step` in sand.rs.
+
+// 0 = empty, then sand, water, stone and fire
+@group(0) @binding(0) var current_state: texture_2d<f32>;
+@group(0) @binding(1) var next_state: texture_storage_2d<r32float, write>;
+
+struct Params {
+    tile_size: vec2<u32>,
+    birth: u32,
+    survive: u32,
+    // 0 = blocks wrap around a torus, anything else walls the grid in
+    boundary: u32,
+    neighbourhood: u32,
+}
+
+@group(0) @binding(2) var<uniform> params: Params;
+// Generations since the start, counted by clock.wgsl
+@group(0) @binding(3) var<storage, read> generation: u32;
+// For each material: weight, flags (1 = fixed, 2 = flows), decay out of 256 and a bit for each
+// material that quenches it
+@group(0) @binding(4) var<uniform> materials: array<vec4<u32>, 8>;
+
+const EMPTY: u32 = 0u;
+const STONE: u32 = 3u;
+const FIRE: u32 = 4u;
+
+// The PCG hash `sand::hash` also uses
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+var<private> cells: array<u32, 4>;
+var<private> inside: array<bool, 4>;
+
+fn movable(k: u32) -> bool {
+    return inside[k] && (materials[cells[k]].y & 1u) == 0u;
+}
+
+fn sinks(top: u32, bottom: u32) -> bool {
+    return movable(top) && movable(bottom) && materials[cells[top]].x > materials[cells[bottom]].x;
+}
+
+fn swap(a: u32, b: u32) {
+    let cell = cells[a];
+    cells[a] = cells[b];
+    cells[b] = cell;
+}
+
+fn flows(k: u32) -> bool {
+    return cells[k] == EMPTY || (materials[cells[k]].y & 2u) != 0u;
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = textureDimensions(current_state);
+    let offset = i32(generation & 1u);
+    // Only an even number of cells splits into blocks both ways, so an odd side is walled
+    let wraps = vec2<bool>(params.boundary == 0u) & (size % 2u == vec2<u32>(0u));
+    let blocks = select(size / 2u + 1u, size / 2u, wraps);
+    if (any(global_id.xy >= blocks)) {
+        return;
+    }
+    let start = 2 * vec2<i32>(global_id.xy) + select(vec2<i32>(-offset), vec2<i32>(offset), wraps);
+
+    var positions: array<vec2<i32>, 4>;
+    var present = 0u;
+    for (var k = 0u; k < 4u; k++) {
+        var cell = start + vec2<i32>(i32(k % 2u), i32(k / 2u));
+        cell = select(cell, cell % vec2<i32>(size), wraps);
+        positions[k] = cell;
+        inside[k] = all(cell >= vec2<i32>(0)) && all(cell < vec2<i32>(size));
+        cells[k] = STONE;
+        if (inside[k]) {
+            cells[k] = min(u32(round(textureLoad(current_state, cell, 0).r)), FIRE);
+            present |= 1u << cells[k];
+        }
+    }
+
+    var random = hash(global_id.x ^ hash(global_id.y ^ hash(generation)));
+
+    // Burn out, or be put out
+    for (var k = 0u; k < 4u; k++) {
+        random = hash(random);
+        let material = materials[cells[k]];
+        if (inside[k] && ((material.w & present) != 0u || (random & 255u) < material.z)) {
+            cells[k] = EMPTY;
+        }
+    }
+
+    random = hash(random);
+
+    // Fall straight down, then topple diagonally, starting from a random side
+    var moved = false;
+    for (var column = 0u; column < 2u; column++) {
+        if (sinks(column, column + 2u)) {
+            swap(column, column + 2u);
+            moved = true;
+        }
+    }
+    if (!moved) {
+        let first = random & 1u;
+        if (sinks(first, 3u - first)) {
+            swap(first, 3u - first);
+            moved = true;
+        } else if (sinks(1u - first, 2u + first)) {
+            swap(1u - first, 2u + first);
+            moved = true;
+        }
+    }
+
+    // Flow sideways into empty cells, each row at random
+    if (!moved) {
+        for (var row = 0u; row < 2u; row++) {
+            let left = 2u * row;
+            let right = left + 1u;
+            if (((random >> (row + 1u)) & 1u) != 0u
+                && movable(left) && movable(right)
+                && (cells[left] == EMPTY) != (cells[right] == EMPTY)
+                && flows(left) && flows(right)) {
+                swap(left, right);
+            }
+        }
+    }
+
+    for (var k = 0u; k < 4u; k++) {
+        if (inside[k]) {
+            textureStore(next_state, positions[k], vec4<f32>(f32(cells[k]), 0.0, 0.0, 1.0));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Update schemes other than every cell at once, for studying how robust a rule's behaviour is.
+//! `conway.wgsl` applies them on the GPU, drawing the same random numbers as `step` so that the
+//! two agree cell for cell.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::grid::{Boundary, Grid};
+use crate::rule::Rule;
+use crate::sand::hash;
+
+/// Which cells change in a generation. Cells left out keep their state.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UpdateScheme {
+    /// Every cell at once.
+    #[default]
+    Synchronous,
+    /// Every cell at once, but a birth only happens with probability `birth` and a death with
+    /// probability `death`.
+    Probabilistic { birth: f32, death: f32 },
+    /// α-asynchronous: each cell is updated with probability `alpha`.
+    Asynchronous { alpha: f32 },
+    /// The cells of one colour of a checkerboard, then the other.
+    Checkerboard,
+    /// One row a generation, from the top down.
+    Sweep,
+}
+
+impl UpdateScheme {
+    /// The next scheme to try, each with even chances.
+    pub fn next(self) -> Self {
+        match self {
+            UpdateScheme::Synchronous => UpdateScheme::Probabilistic {
+                birth: 0.5,
+                death: 0.5,
+            },
+            UpdateScheme::Probabilistic { .. } => UpdateScheme::Asynchronous { alpha: 0.5 },
+            UpdateScheme::Asynchronous { .. } => UpdateScheme::Checkerboard,
+            UpdateScheme::Checkerboard => UpdateScheme::Sweep,
+            UpdateScheme::Sweep => UpdateScheme::Synchronous,
+        }
+    }
+
+    /// Adds `delta` to the scheme's probabilities, keeping them between 0 and 1 and rounded to
+    /// hundredths.
+    pub fn adjust(self, delta: f32) -> Self {
+        let adjust = |p: f32| ((p + delta).clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        match self {
+            UpdateScheme::Probabilistic { birth, death } => UpdateScheme::Probabilistic {
+                birth: adjust(birth),
+                death: adjust(death),
+            },
+            UpdateScheme::Asynchronous { alpha } => UpdateScheme::Asynchronous {
+                alpha: adjust(alpha),
+            },
+            scheme => scheme,
+        }
+    }
+
+    /// The scheme and its two probabilities as `conway.wgsl` takes them.
+    pub(crate) fn params(self) -> (u32, [f32; 2]) {
+        match self {
+            UpdateScheme::Synchronous => (0, [1.0; 2]),
+            UpdateScheme::Probabilistic { birth, death } => (1, [birth, death]),
+            UpdateScheme::Asynchronous { alpha } => (2, [alpha; 2]),
+            UpdateScheme::Checkerboard => (3, [1.0; 2]),
+            UpdateScheme::Sweep => (4, [1.0; 2]),
+        }
+    }
+
+    /// Whether the cell at (`x`, `y`) of a world `height` cells tall changes to what the rule
+    /// says in `generation`, given whether it is alive now.
+    pub fn updates(self, x: u32, y: u32, height: u32, generation: u32, alive: bool) -> bool {
+        match self {
+            UpdateScheme::Synchronous => true,
+            UpdateScheme::Probabilistic { birth, death } => {
+                chance(x, y, generation) < if alive { death } else { birth }
+            }
+            UpdateScheme::Asynchronous { alpha } => chance(x, y, generation) < alpha,
+            UpdateScheme::Checkerboard => (x + y + generation).is_multiple_of(2),
+            UpdateScheme::Sweep => y == generation % height,
+        }
+    }
+}
+
+/// A number in [0, 1) drawn for a cell in a generation, exactly as `conway.wgsl` draws it.
+fn chance(x: u32, y: u32, generation: u32) -> f32 {
+    (hash(x ^ hash(y ^ hash(generation))) >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Advances `grid` by one generation under `scheme`, where `generation` is the one being
+/// left. This is the CPU reference for the compute shader.
+pub fn step(
+    grid: &Grid,
+    rule: &Rule,
+    boundary: Boundary,
+    scheme: UpdateScheme,
+    generation: u32,
+) -> Grid {
+    let mut next = grid.step(rule, boundary);
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let current = grid.colour(x, y);
+            if !scheme.updates(x, y, grid.height, generation, current != 0) {
+                next.set_colour(x, y, current);
+            }
+        }
+    }
+    next
+}
+
+impl FromStr for UpdateScheme {
+    type Err = anyhow::Error;
+
+    /// Parses `synchronous`, `probabilistic:P` (or `probabilistic:BIRTH,DEATH`),
+    /// `asynchronous:ALPHA`, `checkerboard` or `sweep`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, args) = s.split_once(':').unwrap_or((s, ""));
+        let probabilities = args
+            .split(',')
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| {
+                let p: f32 = arg
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid probability {arg:?}"))?;
+                if !(0.0..=1.0).contains(&p) {
+                    bail!("probability {p} is not between 0 and 1");
+                }
+                Ok(p)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let scheme = match (name.to_ascii_lowercase().as_str(), &probabilities[..]) {
+            ("synchronous", []) => UpdateScheme::Synchronous,
+            ("probabilistic", &[p]) => UpdateScheme::Probabilistic { birth: p, death: p },
+            ("probabilistic", &[birth, death]) => UpdateScheme::Probabilistic { birth, death },
+            ("asynchronous", &[alpha]) => UpdateScheme::Asynchronous { alpha },
+            ("checkerboard", []) => UpdateScheme::Checkerboard,
+            ("sweep", []) => UpdateScheme::Sweep,
+            _ => bail!(
+                "invalid update scheme {s:?}, expected synchronous, probabilistic:P, \
+                 probabilistic:BIRTH,DEATH, asynchronous:ALPHA, checkerboard or sweep"
+            ),
+        };
+        Ok(scheme)
+    }
+}
+
+impl fmt::Display for UpdateScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateScheme::Synchronous => f.write_str("synchronous"),
+            UpdateScheme::Probabilistic { birth, death } if birth == death => {
+                write!(f, "probabilistic:{birth}")
+            }
+            UpdateScheme::Probabilistic { birth, death } => {
+                write!(f, "probabilistic:{birth},{death}")
+            }
+            UpdateScheme::Asynchronous { alpha } => write!(f, "asynchronous:{alpha}"),
+            UpdateScheme::Checkerboard => f.write_str("checkerboard"),
+            UpdateScheme::Sweep => f.write_str("sweep"),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Rhai scripts that run experiments on a world: load a pattern, set a rule, step until a
+//! condition holds, save a snapshot, and so on. The window runs its script with F5, and
+//! `gol script` runs one without a window.
+//!
+//! Scripts step a copy of the world on the CPU, with the reference steppers the compute shaders
+//! are checked against, or with HashLife if the world is unbounded. They see these functions:
+//!
+//! - `step()` and `step(n)` advance one or `n` generations
+//! - `generation()` is the number of generations run
+//! - `set_cell(x, y, alive)` and `get_cell(x, y)`
+//! - `population()` counts the live cells
+//! - `bounding_box()` is a map of the `x`, `y`, `width` and `height` of the live cells, or `()`
+//!   if there are none
+//! - `load(path)` starts again from a pattern file, centred
+//! - `save(path)` writes the live cells as macrocell if the path ends in `.mc`, or else RLE
+//! - `set_rule(rule)` takes a rule such as `"B36/S23"`
+//!
+//! Paths are relative to the script, and `print` goes to the log.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use rhai::{Dynamic, Engine, EvalAltResult, Map};
+
+use crate::grid::{Boundary, Grid};
+use crate::hashlife::HashLife;
+use crate::macrocell;
+use crate::options::OptionArgs;
+use crate::pattern::Pattern;
+use crate::rule::{Rule, Variant};
+use crate::sand;
+use crate::scheme;
+use crate::session::Session;
+
+/// Run a Rhai script on a world without a window.
+#[derive(Debug, Clone, clap::Args)]
+#[command(after_help = crate::options::RULE_FORMATS)]
+pub struct ScriptOptions {
+    /// The script to run
+    pub script: PathBuf,
+    #[command(flatten)]
+    pub world: OptionArgs,
+}
+
+pub fn run(options: &ScriptOptions) -> Result<()> {
+    let world = ScriptWorld::new(options.world.options()?.session())?;
+    let world = run_file(&options.script, world)?;
+    log::info!(
+        "Finished at generation {} with {} live cells",
+        world.generation(),
+        world.population()
+    );
+    Ok(())
+}
+
+/// Runs the script at `path` on `world`, returning the world it leaves.
+pub fn run_file(path: &Path, world: ScriptWorld) -> Result<ScriptWorld> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    run_script(&source, world, dir).with_context(|| format!("running {}", path.display()))
+}
+
+/// Runs `source` on `world`, with paths relative to `dir`.
+pub fn run_script(source: &str, world: ScriptWorld, dir: PathBuf) -> Result<ScriptWorld> {
+    let world = Rc::new(RefCell::new(world));
+    let mut engine = Engine::new();
+    engine.on_print(|text| log::info!("{text}"));
+
+    let w = world.clone();
+    engine.register_fn("step", move || w.borrow_mut().step(1).map_err(to_rhai));
+    let w = world.clone();
+    engine.register_fn("step", move |n: i64| -> Result<(), Box<EvalAltResult>> {
+        let n = u64::try_from(n).map_err(|_| format!("cannot step {n} generations"))?;
+        w.borrow_mut().step(n).map_err(to_rhai)
+    });
+    let w = world.clone();
+    engine.register_fn("generation", move || w.borrow().generation() as i64);
+    let w = world.clone();
+    engine.register_fn(
+        "set_cell",
+        move |x: i64, y: i64, alive: bool| -> Result<(), Box<EvalAltResult>> {
+            w.borrow_mut().set_cell(x, y, alive).map_err(to_rhai)
+        },
+    );
+    let w = world.clone();
+    engine.register_fn("get_cell", move |x: i64, y: i64| w.borrow().get_cell(x, y));
+    let w = world.clone();
+    engine.register_fn("population", move || w.borrow().population() as i64);
+    let w = world.clone();
+    engine.register_fn("bounding_box", move || match w.borrow().bounding_box() {
+        Some(((x, y), (width, height))) => {
+            let mut map = Map::new();
+            map.insert("x".into(), x.into());
+            map.insert("y".into(), y.into());
+            map.insert("width".into(), (width as i64).into());
+            map.insert("height".into(), (height as i64).into());
+            Dynamic::from_map(map)
+        }
+        None => Dynamic::UNIT,
+    });
+    let (w, from) = (world.clone(), dir.clone());
+    engine.register_fn(
+        "load",
+        move |path: &str| -> Result<(), Box<EvalAltResult>> {
+            w.borrow_mut().load(&from.join(path)).map_err(to_rhai)
+        },
+    );
+    let (w, to) = (world.clone(), dir);
+    engine.register_fn(
+        "save",
+        move |path: &str| -> Result<(), Box<EvalAltResult>> {
+            w.borrow().save(&to.join(path)).map_err(to_rhai)
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "set_rule",
+        move |rule: &str| -> Result<(), Box<EvalAltResult>> {
+            let rule = rule.parse().map_err(to_rhai)?;
+            w.borrow_mut().set_rule(rule).map_err(to_rhai)
+        },
+    );
+
+    engine.run(source).map_err(|e| anyhow!("{e}"))?;
+    drop(engine);
+    let world = Rc::into_inner(world).expect("the engine held the only other references");
+    Ok(world.into_inner())
+}
+
+fn to_rhai(e: anyhow::Error) -> Box<EvalAltResult> {
+    format!("{e:#}").into()
+}
+
+/// A world run by a script: a bounded grid, or a HashLife universe if it is unbounded.
+pub struct ScriptWorld {
+    /// How the world runs, and its cells if it is bounded.
+    session: Session,
+    life: Option<HashLife>,
+}
+
+impl ScriptWorld {
+    pub fn new(session: Session) -> Result<Self> {
+        let mut world = Self {
+            session,
+            life: None,
+        };
+        world.start_hashlife()?;
+        Ok(world)
+    }
+
+    /// The session the world started from, with its cells now, and the universe holding them if
+    /// it is unbounded.
+    pub fn into_parts(self) -> (Session, Option<HashLife>) {
+        (self.session, self.life)
+    }
+
+    /// Moves the cells of an unbounded world into a universe stepping one generation at a time.
+    fn start_hashlife(&mut self) -> Result<()> {
+        if self.session.boundary != Boundary::Unbounded {
+            return Ok(());
+        }
+        let (x, y) = self.session.origin;
+        let origin = (i64::from(x), i64::from(y));
+        let mut life = HashLife::from_grid(self.session.rule, &self.session.grid, origin)?;
+        life.set_generation(self.session.generation);
+        self.session.grid = Grid::new(0, 0);
+        self.life = Some(life);
+        Ok(())
+    }
+
+    pub fn generation(&self) -> u64 {
+        match &self.life {
+            Some(life) => life.generation(),
+            None => self.session.generation,
+        }
+    }
+
+    pub fn step(&mut self, generations: u64) -> Result<()> {
+        let session = &mut self.session;
+        for _ in 0..generations {
+            match &mut self.life {
+                Some(life) => life.step()?,
+                None => {
+                    session.grid = match session.variant {
+                        Variant::Sand => {
+                            sand::step(&session.grid, session.boundary, session.generation)
+                        }
+                        _ => scheme::step(
+                            &session.grid,
+                            &session.rule,
+                            session.boundary,
+                            session.scheme,
+                            session.generation as u32,
+                        ),
+                    };
+                    session.generation += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<()> {
+        if let Some(life) = &mut self.life {
+            return life.set(x, y, alive);
+        }
+        let grid = &mut self.session.grid;
+        let Some((x, y)) = grid_cell(grid, x, y) else {
+            bail!(
+                "({x}, {y}) is outside the {} × {} world",
+                grid.width,
+                grid.height
+            );
+        };
+        grid.set(x, y, alive);
+        Ok(())
+    }
+
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        match &self.life {
+            Some(life) => life.get(x, y),
+            None => grid_cell(&self.session.grid, x, y)
+                .is_some_and(|(x, y)| self.session.grid.get(x, y)),
+        }
+    }
+
+    pub fn population(&self) -> u64 {
+        match &self.life {
+            Some(life) => life.population(),
+            None => self.session.grid.population() as u64,
+        }
+    }
+
+    /// The top-left corner and size of the smallest rectangle holding every live cell.
+    pub fn bounding_box(&self) -> Option<((i64, i64), (u64, u64))> {
+        match &self.life {
+            Some(life) => life.extent(),
+            None => {
+                let (x, y, width, height) = self.session.grid.bounding_box()?;
+                Some(((x.into(), y.into()), (width.into(), height.into())))
+            }
+        }
+    }
+
+    /// Starts again from the pattern file at `path`, centred. A macrocell universe too large
+    /// for a grid is only accepted by an unbounded world.
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        if self.life.is_some() && text.trim_start().starts_with("[M2]") {
+            let life = macrocell::read(&text)?;
+            self.session.rule = life.rule();
+            self.life = Some(life);
+            return Ok(());
+        }
+        let pattern =
+            Pattern::from_text(&text).with_context(|| format!("parsing {}", path.display()))?;
+        self.session = self.session.clone().with_pattern(&pattern);
+        self.life = None;
+        self.start_hashlife()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = if path.extension().is_some_and(|extension| extension == "mc") {
+            match &self.life {
+                Some(life) => macrocell::write(life),
+                None => macrocell::write(&self.bounded_hashlife()?),
+            }
+        } else {
+            let grid = match &self.life {
+                Some(life) => life.to_grid()?.0,
+                None => self.session.grid.clone(),
+            };
+            Pattern {
+                name: None,
+                rule: Some(self.session.rule),
+                grid,
+            }
+            .to_rle()
+        };
+        std::fs::write(path, text).with_context(|| format!("unable to write {}", path.display()))
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) -> Result<()> {
+        // A universe memoises results under its rule, so it starts over with the new one
+        if let Some(life) = &self.life {
+            let (grid, (x, y)) = life.to_grid()?;
+            let mut next = HashLife::from_grid(rule, &grid, (x, y))?;
+            next.set_generation(life.generation());
+            self.life = Some(next);
+        }
+        self.session = self.session.clone().with_rule(rule);
+        Ok(())
+    }
+
+    /// The bounded grid as a universe, for saving.
+    fn bounded_hashlife(&self) -> Result<HashLife> {
+        let mut life = HashLife::from_grid(self.session.rule, &self.session.grid, (0, 0))?;
+        life.set_generation(self.session.generation);
+        Ok(life)
+    }
+}
+
+/// The grid coordinates of a world position, if it is inside the grid.
+fn grid_cell(grid: &Grid, x: i64, y: i64) -> Option<(u32, u32)> {
+    let x = u32::try_from(x).ok().filter(|&x| x < grid.width)?;
+    let y = u32::try_from(y).ok().filter(|&y| y < grid.height)?;
+    Some((x, y))
+}
@@ -0,0 +1,64 @@
+use crate::grid::Rect;
+
+/// A rectangle dragged out with the mouse, in cell coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    anchor: (i32, i32),
+    corner: (i32, i32),
+}
+
+/// Selection commands that need the selected cells read back from the GPU first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionOp {
+    Copy,
+    Cut,
+    Rotate,
+    Flip,
+    Invert,
+}
+
+impl Selection {
+    pub fn new(cell: (i32, i32)) -> Self {
+        Self {
+            anchor: cell,
+            corner: cell,
+        }
+    }
+
+    pub fn from_rect(rect: Rect) -> Self {
+        Self {
+            anchor: (rect.x as i32, rect.y as i32),
+            corner: (
+                (rect.x + rect.width) as i32 - 1,
+                (rect.y + rect.height) as i32 - 1,
+            ),
+        }
+    }
+
+    pub fn drag_to(&mut self, cell: (i32, i32)) {
+        self.corner = cell;
+    }
+
+    /// True until the mouse has moved to a different cell from where the drag started.
+    pub fn is_click(&self) -> bool {
+        self.anchor == self.corner
+    }
+
+    /// The selected cells, clipped to a grid of the given size.
+    pub fn rect(&self, (width, height): (u32, u32)) -> Option<Rect> {
+        let clamp = |v: i32, max: u32| v.clamp(0, max as i32 - 1) as u32;
+        let left = clamp(self.anchor.0.min(self.corner.0), width);
+        let right = clamp(self.anchor.0.max(self.corner.0), width);
+        let top = clamp(self.anchor.1.min(self.corner.1), height);
+        let bottom = clamp(self.anchor.1.max(self.corner.1), height);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        Some(Rect {
+            x: left,
+            y: top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        })
+    }
+}
@@ -0,0 +1,258 @@
+//! `gol serve`: runs a shared world and lets windows and pages connect to it over WebSocket.
+//!
+//! The world is stepped on the CPU by one thread, which owns it. Each connection has a thread of
+//! its own that forwards its edits to the world and the world's messages to it.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::grid::Boundary;
+use crate::options::{OptionArgs, Options, RULE_FORMATS};
+use crate::rule::Variant;
+use crate::scheme::UpdateScheme;
+use crate::shared::{Changes, ClientMessage, PROTOCOL_VERSION, ServerMessage, SharedWorld};
+
+/// Generations a second when no speed is given.
+const DEFAULT_SPEED: f32 = 10.0;
+/// How long a connection waits for a message before sending those queued for it.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Run a world without a window for windows and pages to join with --connect.
+#[derive(Debug, Clone, clap::Args)]
+#[command(after_help = RULE_FORMATS)]
+pub struct ServeOptions {
+    /// Address to listen on for WebSocket connections
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    pub address: String,
+    #[command(flatten)]
+    pub world: OptionArgs,
+}
+
+pub fn run(options: &ServeOptions) -> Result<()> {
+    let server = Server::bind(&options.address, &options.world.options()?)?;
+    log::info!("Serving a shared world on ws://{}", server.local_addr()?);
+    server.run()
+}
+
+/// A shared world and the socket clients connect to.
+pub struct Server {
+    listener: TcpListener,
+    world: SharedWorld,
+    speed: f32,
+}
+
+impl Server {
+    /// Starts the world `options` describe, stepped at their speed or ten generations a second.
+    /// It must be a bounded Life-like world updated synchronously.
+    pub fn bind(address: impl ToSocketAddrs, options: &Options) -> Result<Self> {
+        let session = options.session();
+        if session.variant == Variant::Sand {
+            bail!("falling sand cannot be shared");
+        }
+        let mut boundary = session.boundary;
+        if boundary == Boundary::Unbounded {
+            log::warn!("A shared world is bounded; walling it in");
+            boundary = Boundary::Dead;
+        }
+        if session.scheme != UpdateScheme::Synchronous {
+            log::warn!("A shared world is updated synchronously; ignoring the update scheme");
+        }
+        let listener = TcpListener::bind(address).context("unable to listen")?;
+        Ok(Self {
+            listener,
+            world: SharedWorld {
+                grid: session.grid,
+                rule: session.rule,
+                boundary,
+                variant: session.variant,
+                generation: 0,
+                paused: false,
+            },
+            speed: options.speed.unwrap_or(DEFAULT_SPEED),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Runs the world and accepts connections until the process ends.
+    pub fn run(self) -> Result<()> {
+        let (events, received) = mpsc::channel();
+        let period = Duration::from_secs_f32(1.0 / self.speed);
+        let world = self.world;
+        thread::spawn(move || simulate(world, period, &received));
+        for (id, stream) in self.listener.incoming().enumerate() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Unable to accept a connection: {e}");
+                    continue;
+                }
+            };
+            let events = events.clone();
+            thread::spawn(move || {
+                if let Err(e) = serve_client(id, stream, &events) {
+                    log::info!("Connection {id} ended: {e:#}");
+                }
+                let _ = events.send(Event::Leave(id));
+            });
+        }
+        Ok(())
+    }
+}
+
+/// What connections tell the world's thread.
+enum Event {
+    /// A client was accepted, and is sent messages on this channel.
+    Join(usize, Sender<Arc<[u8]>>),
+    Edit(ClientMessage),
+    Leave(usize),
+}
+
+/// Steps the world every `period` unless it is paused, sending each client the cells changed
+/// by the step and by the edits received since the last one.
+fn simulate(mut world: SharedWorld, period: Duration, events: &Receiver<Event>) {
+    let mut clients: HashMap<usize, Sender<Arc<[u8]>>> = HashMap::new();
+    // The grid as the clients last saw it
+    let mut sent = world.grid.clone();
+    let mut next = Instant::now() + period;
+    loop {
+        let message = match events.recv_timeout(next.saturating_duration_since(Instant::now())) {
+            Ok(Event::Join(id, client)) => {
+                log::info!("Connection {id} joined");
+                let welcome = ServerMessage::Welcome(world.clone()).to_bytes();
+                let _ = client.send(welcome.into());
+                clients.insert(id, client);
+                continue;
+            }
+            Ok(Event::Leave(id)) => {
+                clients.remove(&id);
+                continue;
+            }
+            Ok(Event::Edit(edit)) => match world.edit(edit) {
+                Some(message) => message,
+                None => continue,
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                next += period;
+                if !world.paused {
+                    world.step();
+                }
+                let changes = Changes::between(&sent, &world.grid);
+                if world.paused && changes.is_empty() {
+                    continue;
+                }
+                sent.clone_from(&world.grid);
+                ServerMessage::Update {
+                    generation: world.generation,
+                    changes,
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let bytes: Arc<[u8]> = message.to_bytes().into();
+        clients.retain(|_, client| client.send(bytes.clone()).is_ok());
+    }
+}
+
+/// Accepts a connection speaking our protocol, then relays messages both ways until it closes.
+fn serve_client(id: usize, stream: TcpStream, events: &Sender<Event>) -> Result<()> {
+    let mut socket = tungstenite::accept(stream).context("WebSocket handshake failed")?;
+    let hello = match socket.read()? {
+        Message::Binary(bytes) => ClientMessage::from_bytes(&bytes)?,
+        message => bail!("expected a greeting, got {message:?}"),
+    };
+    if hello
+        != (ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })
+    {
+        let reason = format!("the server speaks protocol version {PROTOCOL_VERSION}");
+        socket.send(Message::binary(ServerMessage::Refused(reason).to_bytes()))?;
+        socket.close(None)?;
+        bail!("refused {hello:?}");
+    }
+
+    let (client, outgoing) = mpsc::channel();
+    events.send(Event::Join(id, client))?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    loop {
+        for bytes in outgoing.try_iter() {
+            socket.send(Message::binary(bytes.to_vec()))?;
+        }
+        match socket.read() {
+            Ok(Message::Binary(bytes)) => {
+                events.send(Event::Edit(ClientMessage::from_bytes(&bytes)?))?
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// A blocking connection to a shared world, for native clients.
+pub struct Client {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl Client {
+    /// Connects to a server at a `ws://` URL, returning the world it sent.
+    pub fn connect(url: &str) -> Result<(Self, SharedWorld)> {
+        let (socket, _) =
+            tungstenite::connect(url).with_context(|| format!("unable to connect to {url}"))?;
+        let mut client = Self { socket };
+        client.send(&ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match client.receive()? {
+            Some(ServerMessage::Welcome(world)) => Ok((client, world)),
+            Some(ServerMessage::Refused(reason)) => {
+                bail!("{url} refused to connect: {reason}")
+            }
+            message => bail!("expected a welcome from {url}, got {message:?}"),
+        }
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        self.socket.send(Message::binary(message.to_bytes()))?;
+        Ok(())
+    }
+
+    /// Waits up to `timeout`, or forever for `None`, for messages from the server from now on.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        if let MaybeTlsStream::Plain(stream) = self.socket.get_ref() {
+            stream.set_read_timeout(timeout)?;
+        }
+        Ok(())
+    }
+
+    /// The next message from the server, or `None` if none came within the timeout.
+    pub fn receive(&mut self) -> Result<Option<ServerMessage>> {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Binary(bytes)) => return ServerMessage::from_bytes(&bytes).map(Some),
+                Ok(Message::Close(_)) => bail!("the server closed the connection"),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Complete simulations saved to a file, or to `localStorage` on the web.
+//!
+//! A session is an RLE pattern of the whole grid, preceded by `#S` comment lines holding the rest
+//! of the state, so other Life software opens it as an ordinary pattern. The text is the same on
+//! every platform and floats are written in their shortest round-trip form, so a session loads
+//! bit-identically wherever it was saved.
+
+use std::fmt::Write;
+
+use anyhow::{Context, Result, bail};
+use rand::Rng;
+
+use crate::conway::GRID_SIZE;
+use crate::grid::{Boundary, CUBE_FACES, Grid};
+use crate::options::MAX_SIZE;
+use crate::pattern::Pattern;
+use crate::rule::{Rule, Variant};
+use crate::scheme::UpdateScheme;
+use crate::viewport::Viewport;
+
+const VERSION: u32 = 1;
+/// The widest face of a cube whose six faces side by side still fit one texture.
+const MAX_FACE: u32 = MAX_SIZE / 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub grid: Grid,
+    pub rule: Rule,
+    pub boundary: Boundary,
+    pub variant: Variant,
+    /// Which cells change each generation; only bounded worlds use anything but synchronous.
+    pub scheme: UpdateScheme,
+    /// World position of the grid's top-left corner; always (0, 0) unless unbounded.
+    pub origin: (i32, i32),
+    pub generation: u64,
+    /// Seed of the random soup the simulation started from.
+    pub seed: u64,
+    pub viewport: Viewport,
+}
+
+impl Session {
+    /// The same world with a different boundary. Leaving an unbounded world keeps its cells where
+    /// they are if they fit a default-sized grid; otherwise the grid grows to hold them, centred,
+    /// and the view follows.
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        if self.boundary == Boundary::Unbounded && boundary != Boundary::Unbounded {
+            let (x, y) = self.origin;
+            let (width, height) = (self.grid.width, self.grid.height);
+            let fits =
+                x >= 0 && y >= 0 && x as u32 + width <= GRID_SIZE && y as u32 + height <= GRID_SIZE;
+            let (mut grid, position) = if fits {
+                (Grid::new(GRID_SIZE, GRID_SIZE), (x as u32, y as u32))
+            } else {
+                let size = (width.max(GRID_SIZE), height.max(GRID_SIZE));
+                let position = ((size.0 - width) / 2, (size.1 - height) / 2);
+                (Grid::new(size.0, size.1), position)
+            };
+            grid.paste(&self.grid, position.0, position.1);
+            let neighbourhood = self.rule.neighbourhood;
+            let (to_x, to_y) = neighbourhood.centre_of((position.0 as i32, position.1 as i32));
+            let (from_x, from_y) = neighbourhood.centre_of((x, y));
+            self.viewport.centre.0 += (to_x - from_x) as f32;
+            self.viewport.centre.1 += (to_y - from_y) as f32;
+            self.grid = grid;
+            self.origin = (0, 0);
+        }
+        // A cube needs a grid of six faces side by side; the world goes on the front one,
+        // cropped about its centre if it is wider than a face can be
+        if boundary == Boundary::Cube
+            && (self.grid.width != 6 * self.grid.height || self.grid.height > MAX_FACE)
+        {
+            let size = self.grid.width.max(self.grid.height).min(MAX_FACE);
+            let (width, height) = (self.grid.width.min(size), self.grid.height.min(size));
+            let world = self.grid.crop(
+                (self.grid.width - width) / 2,
+                (self.grid.height - height) / 2,
+                width,
+                height,
+            );
+            let mut grid = Grid::new(6 * size, size);
+            let front = CUBE_FACES
+                .iter()
+                .position(|face| face[0] == [0, 0, 1])
+                .unwrap_or_default() as u32;
+            grid.paste(
+                &world,
+                front * size + (size - width) / 2,
+                (size - height) / 2,
+            );
+            self.viewport = Viewport::new((grid.width, grid.height), self.rule.neighbourhood);
+            self.grid = grid;
+        }
+        self.boundary = boundary;
+        self
+    }
+
+    /// The same world played as a different variant. Every cell turns white for plain Life, and
+    /// takes a random colour of the variant otherwise. Falling sand needs a bounded flat world,
+    /// so an unbounded world or a cube is walled in, and updates its own way.
+    pub fn with_variant(mut self, variant: Variant, rng: &mut impl Rng) -> Self {
+        if variant == Variant::Sand {
+            if matches!(self.boundary, Boundary::Unbounded | Boundary::Cube) {
+                self = self.with_boundary(Boundary::Dead);
+            }
+            self.scheme = UpdateScheme::Synchronous;
+        }
+        let colour = (variant == Variant::Life).then_some(1);
+        self.grid.recolour(colour, variant.colours(), rng);
+        self.variant = variant;
+        self
+    }
+
+    /// The same world under a different rule. The view stays on the same cells, wherever cells
+    /// of the new rule's shape put them.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        let (x, y) = self.viewport.centre;
+        let cell = self
+            .rule
+            .neighbourhood
+            .cell_at((f64::from(x), f64::from(y)));
+        let (x, y) = rule.neighbourhood.centre_of(cell);
+        self.viewport.centre = (x as f32, y as f32);
+        self.rule = rule;
+        self
+    }
+
+    /// The same kind of world started again from `pattern`, centred in a grid at least as big
+    /// as the old one, under the pattern's rule if it has one.
+    pub fn with_pattern(mut self, pattern: &Pattern) -> Self {
+        let boundary = self.boundary;
+        let (width, height) = match boundary {
+            Boundary::Unbounded => (GRID_SIZE, GRID_SIZE),
+            // Only the front face of a cube is filled
+            Boundary::Cube => (self.grid.height, self.grid.height),
+            Boundary::Torus | Boundary::Dead => (self.grid.width, self.grid.height),
+        };
+        let (width, height) = (
+            width.max(pattern.grid.width),
+            height.max(pattern.grid.height),
+        );
+        let mut grid = Grid::new(width, height);
+        grid.paste(
+            &pattern.grid,
+            (width - pattern.grid.width) / 2,
+            (height - pattern.grid.height) / 2,
+        );
+        self.rule = pattern.rule.unwrap_or(self.rule);
+        self.viewport = Viewport::new((width, height), self.rule.neighbourhood);
+        self.grid = grid;
+        self.origin = (0, 0);
+        self.generation = 0;
+        // Laid out flat, then folded onto the faces of a cube if need be
+        self.boundary = Boundary::Dead;
+        self.with_boundary(boundary)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let boundary = match self.boundary {
+            Boundary::Torus => "torus",
+            Boundary::Dead => "dead",
+            Boundary::Unbounded => "unbounded",
+            Boundary::Cube => "cube",
+        };
+        let Viewport { centre, zoom } = self.viewport;
+        let _ = writeln!(out, "#S version {VERSION}");
+        let _ = writeln!(out, "#S boundary {boundary}");
+        match self.variant {
+            Variant::Life => {}
+            Variant::Immigration => {
+                let _ = writeln!(out, "#S variant immigration");
+            }
+            Variant::QuadLife => {
+                let _ = writeln!(out, "#S variant quadlife");
+            }
+            Variant::Sand => {
+                let _ = writeln!(out, "#S variant sand");
+            }
+        }
+        if self.scheme != UpdateScheme::Synchronous {
+            let _ = writeln!(out, "#S scheme {}", self.scheme);
+        }
+        if self.origin != (0, 0) {
+            let _ = writeln!(out, "#S origin {} {}", self.origin.0, self.origin.1);
+        }
+        let _ = writeln!(out, "#S generation {}", self.generation);
+        let _ = writeln!(out, "#S seed {}", self.seed);
+        let _ = writeln!(out, "#S viewport {} {} {zoom}", centre.0, centre.1);
+
+        let pattern = Pattern {
+            name: None,
+            rule: Some(self.rule),
+            grid: self.grid.clone(),
+        };
+        out.push_str(&pattern.to_rle());
+        out
+    }
+
+    pub fn from_text(text: &str) -> Result<Self> {
+        let pattern = Pattern::from_rle(text)?;
+        let mut session = Self {
+            viewport: Viewport::new(
+                (pattern.grid.width, pattern.grid.height),
+                pattern.rule.unwrap_or_default().neighbourhood,
+            ),
+            grid: pattern.grid,
+            rule: pattern.rule.unwrap_or_default(),
+            boundary: Boundary::default(),
+            variant: Variant::default(),
+            scheme: UpdateScheme::default(),
+            origin: (0, 0),
+            generation: 0,
+            seed: 0,
+        };
+
+        let mut version = None;
+        let mut boundary = Boundary::default();
+        for line in text.lines() {
+            let Some(fields) = line.trim().strip_prefix("#S") else {
+                continue;
+            };
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            let context = || format!("malformed session line {line:?}");
+            match fields.as_slice() {
+                ["version", v] => version = Some(v.parse::<u32>().with_context(context)?),
+                ["boundary", "torus"] => boundary = Boundary::Torus,
+                ["boundary", "dead"] => boundary = Boundary::Dead,
+                ["boundary", "unbounded"] => boundary = Boundary::Unbounded,
+                ["boundary", "cube"] => boundary = Boundary::Cube,
+                ["variant", "life"] => session.variant = Variant::Life,
+                ["variant", "immigration"] => session.variant = Variant::Immigration,
+                ["variant", "quadlife"] => session.variant = Variant::QuadLife,
+                ["variant", "sand"] => session.variant = Variant::Sand,
+                ["scheme", scheme] => session.scheme = scheme.parse().with_context(context)?,
+                ["origin", x, y] => {
+                    session.origin = (
+                        x.parse().with_context(context)?,
+                        y.parse().with_context(context)?,
+                    )
+                }
+                ["generation", g] => session.generation = g.parse().with_context(context)?,
+                ["seed", s] => session.seed = s.parse().with_context(context)?,
+                ["viewport", x, y, zoom] => {
+                    session.viewport = Viewport {
+                        centre: (
+                            x.parse().with_context(context)?,
+                            y.parse().with_context(context)?,
+                        ),
+                        zoom: zoom.parse().with_context(context)?,
+                    }
+                }
+                _ => bail!("unknown session line {line:?}"),
+            }
+        }
+        match version {
+            // Through `with_boundary`, so that a cube is always six faces side by side
+            Some(VERSION) => Ok(session.with_boundary(boundary)),
+            Some(v) => bail!("unsupported session version {v}"),
+            None => bail!("not a session: there is no #S version line"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SESSION_FILE: &str = "gol-session.rle";
+
+/// Writes the session to `gol-session.rle` in the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(session: &Session) -> Result<()> {
+    std::fs::write(SESSION_FILE, session.to_text())
+        .with_context(|| format!("unable to write {SESSION_FILE}"))?;
+    log::info!("Saved session to {SESSION_FILE}");
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load() -> Result<Session> {
+    let text = std::fs::read_to_string(SESSION_FILE)
+        .with_context(|| format!("unable to read {SESSION_FILE}"))?;
+    Session::from_text(&text).with_context(|| format!("unable to load {SESSION_FILE}"))
+}
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "gol-session";
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .context("no browser window")?
+        .local_storage()
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?
+        .context("localStorage is unavailable")
+}
+
+/// Writes the session to the page's `localStorage`.
+#[cfg(target_arch = "wasm32")]
+pub fn save(session: &Session) -> Result<()> {
+    local_storage()?
+        .set_item(STORAGE_KEY, &session.to_text())
+        .map_err(|e| anyhow::anyhow!("unable to store the session: {e:?}"))?;
+    log::info!("Saved session to localStorage[{STORAGE_KEY:?}]");
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load() -> Result<Session> {
+    let text = local_storage()?
+        .get_item(STORAGE_KEY)
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?
+        .context("no session has been saved")?;
+    Session::from_text(&text)
+}
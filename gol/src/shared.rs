@@ -0,0 +1,460 @@
+//! A world shared by several windows and pages: `gol serve` runs it and clients connect over
+//! WebSocket to watch it and edit it.
+//!
+//! Every message is binary: a tag byte, then its fields as LEB128 numbers, signed ones zigzag
+//! encoded, and length-prefixed bytes. Cells travel as the runs that changed since the last
+//! update, so a quiet generation costs a few bytes. Clients and server must speak the same
+//! `PROTOCOL_VERSION`, and each update follows the last, so clients stay in lockstep with the
+//! server's generations.
+
+use anyhow::{Context, Result, bail, ensure};
+
+use crate::grid::{Boundary, Grid};
+use crate::rule::{Rule, Variant};
+
+/// Bumped whenever a message changes; connections from other versions are refused.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Runs of cells closer than this are sent as one, as the gap costs less than another run.
+const MERGE_GAP: usize = 4;
+/// The most cells a grid sent to us may have.
+const MAX_CELLS: u64 = 1 << 28;
+
+/// What clients send to the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    /// The first message of every connection, answered with `Welcome` if the versions match.
+    Hello {
+        version: u32,
+    },
+    /// Brings cells to life or kills them.
+    Paint {
+        cells: Vec<(i32, i32)>,
+        alive: bool,
+    },
+    /// Overwrites the cells under `cells` with its top-left corner at `origin`, wrapping
+    /// around a torus and clipped otherwise.
+    Stamp {
+        origin: (i32, i32),
+        cells: Grid,
+    },
+    Pause,
+    Resume,
+    SetRule(Rule),
+}
+
+/// What the server sends to its clients.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// The whole world, sent once a connection is accepted.
+    Welcome(SharedWorld),
+    /// The cells changed since the last update, by edits and by stepping to `generation`.
+    Update {
+        generation: u64,
+        changes: Changes,
+    },
+    Rule(Rule),
+    Paused(bool),
+    /// The connection was not accepted, and why.
+    Refused(String),
+}
+
+/// The state of a shared world, which the server runs and each client mirrors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedWorld {
+    pub grid: Grid,
+    pub rule: Rule,
+    pub boundary: Boundary,
+    pub variant: Variant,
+    pub generation: u64,
+    pub paused: bool,
+}
+
+impl SharedWorld {
+    /// Advances the world a generation on the CPU.
+    pub fn step(&mut self) {
+        self.grid = self.grid.step(&self.rule, self.boundary);
+        self.generation += 1;
+    }
+
+    /// Carries out a client's edit, returning the message telling every client about it if it
+    /// is not sent with the next update.
+    pub fn edit(&mut self, message: ClientMessage) -> Option<ServerMessage> {
+        match message {
+            ClientMessage::Hello { .. } => None,
+            ClientMessage::Paint { cells, alive } => {
+                let mut cell = Grid::new(1, 1);
+                cell.set(0, 0, alive);
+                for origin in cells {
+                    self.stamp(&cell, origin);
+                }
+                None
+            }
+            ClientMessage::Stamp { origin, cells } => {
+                self.stamp(&cells, origin);
+                None
+            }
+            ClientMessage::Pause | ClientMessage::Resume => {
+                self.paused = message == ClientMessage::Pause;
+                Some(ServerMessage::Paused(self.paused))
+            }
+            ClientMessage::SetRule(rule) => {
+                self.rule = rule;
+                Some(ServerMessage::Rule(rule))
+            }
+        }
+    }
+
+    /// Brings the mirror up to date with a message from the server.
+    pub fn apply(&mut self, message: ServerMessage) -> Result<()> {
+        match message {
+            ServerMessage::Welcome(world) => *self = world,
+            ServerMessage::Update {
+                generation,
+                changes,
+            } => {
+                ensure!(
+                    generation == self.generation || generation == self.generation + 1,
+                    "out of step with the server: generation {generation} followed {}",
+                    self.generation
+                );
+                changes.apply(&mut self.grid)?;
+                self.generation = generation;
+            }
+            ServerMessage::Rule(rule) => self.rule = rule,
+            ServerMessage::Paused(paused) => self.paused = paused,
+            ServerMessage::Refused(reason) => bail!("the server refused to connect: {reason}"),
+        }
+        Ok(())
+    }
+
+    /// Writes `cells` like `ConwayCompute::stamp`: wrapped on a torus, clipped otherwise.
+    fn stamp(&mut self, cells: &Grid, (x, y): (i32, i32)) {
+        let (width, height) = (self.grid.width as i32, self.grid.height as i32);
+        let wraps = self.boundary == Boundary::Torus;
+        for (cx, cy) in (0..cells.height).flat_map(|cy| (0..cells.width).map(move |cx| (cx, cy))) {
+            let (mut gx, mut gy) = (x + cx as i32, y + cy as i32);
+            if wraps {
+                (gx, gy) = (gx.rem_euclid(width), gy.rem_euclid(height));
+            } else if gx < 0 || gy < 0 || gx >= width || gy >= height {
+                continue;
+            }
+            self.grid
+                .set_colour(gx as u32, gy as u32, cells.colour(cx, cy));
+        }
+    }
+}
+
+/// Runs of cells that changed between two grids of the same size, each as the index of its
+/// first cell and the new colours.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    runs: Vec<(usize, Vec<u8>)>,
+}
+
+impl Changes {
+    pub fn between(old: &Grid, new: &Grid) -> Self {
+        let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+        let changed = old.cells.iter().zip(&new.cells).enumerate();
+        for (i, _) in changed.filter(|(_, (old, new))| old != new) {
+            match runs.last_mut() {
+                Some((start, cells)) if i - (*start + cells.len()) < MERGE_GAP => {
+                    cells.extend_from_slice(&new.cells[*start + cells.len()..=i]);
+                }
+                _ => runs.push((i, vec![new.cells[i]])),
+            }
+        }
+        Self { runs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    fn apply(&self, grid: &mut Grid) -> Result<()> {
+        for (start, cells) in &self.runs {
+            let run = grid
+                .cells
+                .get_mut(*start..start + cells.len())
+                .context("the changes do not fit the grid")?;
+            run.copy_from_slice(cells);
+        }
+        Ok(())
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        write_number(out, self.runs.len() as u64);
+        let mut end = 0;
+        for (start, cells) in &self.runs {
+            write_number(out, (start - end) as u64);
+            write_bytes(out, cells);
+            end = start + cells.len();
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self> {
+        let count = reader.number()?;
+        let mut runs = Vec::new();
+        let mut end = 0usize;
+        for _ in 0..count {
+            let start = end
+                .checked_add(reader.number()? as usize)
+                .context("malformed changes")?;
+            let cells = reader.bytes()?.to_vec();
+            end = start
+                .checked_add(cells.len())
+                .context("malformed changes")?;
+            runs.push((start, cells));
+        }
+        Ok(Self { runs })
+    }
+}
+
+impl ClientMessage {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ClientMessage::Hello { version } => {
+                out.push(0);
+                write_number(&mut out, u64::from(*version));
+            }
+            ClientMessage::Paint { cells, alive } => {
+                out.push(1);
+                out.push(u8::from(*alive));
+                write_number(&mut out, cells.len() as u64);
+                for &(x, y) in cells {
+                    write_signed(&mut out, x);
+                    write_signed(&mut out, y);
+                }
+            }
+            ClientMessage::Stamp { origin, cells } => {
+                out.push(2);
+                write_signed(&mut out, origin.0);
+                write_signed(&mut out, origin.1);
+                write_grid(&mut out, cells);
+            }
+            ClientMessage::Pause => out.push(3),
+            ClientMessage::Resume => out.push(4),
+            ClientMessage::SetRule(rule) => {
+                out.push(5);
+                write_bytes(&mut out, rule.to_string().as_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes };
+        let message = match reader.byte()? {
+            0 => ClientMessage::Hello {
+                version: u32::try_from(reader.number()?).context("invalid version")?,
+            },
+            1 => {
+                let alive = reader.byte()? != 0;
+                let count = reader.number()?;
+                let cells = (0..count)
+                    .map(|_| Ok((reader.signed()?, reader.signed()?)))
+                    .collect::<Result<_>>()?;
+                ClientMessage::Paint { cells, alive }
+            }
+            2 => ClientMessage::Stamp {
+                origin: (reader.signed()?, reader.signed()?),
+                cells: reader.grid()?,
+            },
+            3 => ClientMessage::Pause,
+            4 => ClientMessage::Resume,
+            5 => ClientMessage::SetRule(reader.rule()?),
+            tag => bail!("unknown client message {tag}"),
+        };
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+impl ServerMessage {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ServerMessage::Welcome(world) => {
+                out.push(0);
+                write_bytes(&mut out, world.rule.to_string().as_bytes());
+                out.push(boundary_code(world.boundary));
+                out.push(variant_code(world.variant));
+                write_number(&mut out, world.generation);
+                out.push(u8::from(world.paused));
+                write_grid(&mut out, &world.grid);
+            }
+            ServerMessage::Update {
+                generation,
+                changes,
+            } => {
+                out.push(1);
+                write_number(&mut out, *generation);
+                changes.write(&mut out);
+            }
+            ServerMessage::Rule(rule) => {
+                out.push(2);
+                write_bytes(&mut out, rule.to_string().as_bytes());
+            }
+            ServerMessage::Paused(paused) => {
+                out.push(3);
+                out.push(u8::from(*paused));
+            }
+            ServerMessage::Refused(reason) => {
+                out.push(4);
+                write_bytes(&mut out, reason.as_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes };
+        let message = match reader.byte()? {
+            0 => ServerMessage::Welcome(SharedWorld {
+                rule: reader.rule()?,
+                boundary: boundary_from_code(reader.byte()?)?,
+                variant: variant_from_code(reader.byte()?)?,
+                generation: reader.number()?,
+                paused: reader.byte()? != 0,
+                grid: reader.grid()?,
+            }),
+            1 => ServerMessage::Update {
+                generation: reader.number()?,
+                changes: Changes::read(&mut reader)?,
+            },
+            2 => ServerMessage::Rule(reader.rule()?),
+            3 => ServerMessage::Paused(reader.byte()? != 0),
+            4 => ServerMessage::Refused(reader.string()?),
+            tag => bail!("unknown server message {tag}"),
+        };
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+fn write_number(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_signed(out: &mut Vec<u8>, n: i32) {
+    write_number(out, u64::from(((n << 1) ^ (n >> 31)) as u32));
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_number(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// A grid as its size and its live cells, the changes from an empty grid.
+fn write_grid(out: &mut Vec<u8>, grid: &Grid) {
+    write_number(out, u64::from(grid.width));
+    write_number(out, u64::from(grid.height));
+    Changes::between(&Grid::new(grid.width, grid.height), grid).write(out);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8> {
+        let (&byte, rest) = self
+            .bytes
+            .split_first()
+            .context("the message is truncated")?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn number(&mut self) -> Result<u64> {
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        bail!("a number in the message is too long")
+    }
+
+    fn signed(&mut self) -> Result<i32> {
+        let n = u32::try_from(self.number()?).context("a coordinate is out of range")?;
+        Ok((n >> 1) as i32 ^ -((n & 1) as i32))
+    }
+
+    fn bytes(&mut self) -> Result<&[u8]> {
+        let len = self.number()?;
+        ensure!(len <= self.bytes.len() as u64, "the message is truncated");
+        let (bytes, rest) = self.bytes.split_at(len as usize);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        Ok(std::str::from_utf8(self.bytes()?)?.to_string())
+    }
+
+    fn rule(&mut self) -> Result<Rule> {
+        self.string()?.parse()
+    }
+
+    fn grid(&mut self) -> Result<Grid> {
+        let width = u32::try_from(self.number()?)?;
+        let height = u32::try_from(self.number()?)?;
+        ensure!(
+            u64::from(width) * u64::from(height) <= MAX_CELLS,
+            "a {width} × {height} grid is too large"
+        );
+        let mut grid = Grid::new(width, height);
+        Changes::read(self)?.apply(&mut grid)?;
+        Ok(grid)
+    }
+
+    fn finish(&self) -> Result<()> {
+        ensure!(self.bytes.is_empty(), "the message has trailing bytes");
+        Ok(())
+    }
+}
+
+fn boundary_code(boundary: Boundary) -> u8 {
+    match boundary {
+        Boundary::Torus => 0,
+        Boundary::Dead => 1,
+        Boundary::Unbounded => 2,
+        Boundary::Cube => 3,
+    }
+}
+
+fn boundary_from_code(code: u8) -> Result<Boundary> {
+    Ok(match code {
+        0 => Boundary::Torus,
+        1 => Boundary::Dead,
+        2 => Boundary::Unbounded,
+        3 => Boundary::Cube,
+        _ => bail!("unknown boundary {code}"),
+    })
+}
+
+fn variant_code(variant: Variant) -> u8 {
+    match variant {
+        Variant::Life => 0,
+        Variant::Immigration => 1,
+        Variant::QuadLife => 2,
+        Variant::Sand => 3,
+    }
+}
+
+fn variant_from_code(code: u8) -> Result<Variant> {
+    Ok(match code {
+        0 => Variant::Life,
+        1 => Variant::Immigration,
+        2 => Variant::QuadLife,
+        3 => Variant::Sand,
+        _ => bail!("unknown variant {code}"),
+    })
+}
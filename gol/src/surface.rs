@@ -0,0 +1,408 @@
+//! Worlds drawn on 3D shapes. The state texture is wrapped over a mesh and looked at through the
+//! same camera and controller as hello-wgpu's scene.
+
+use std::f32::consts::TAU;
+use std::fmt;
+
+use scene::camera::{Camera, CameraUniform};
+use scene::camera_controller::CameraController;
+use scene::model::{Mesh, ModelVertex, Vertex};
+use wgpu::util::DeviceExt;
+use winit::keyboard::PhysicalKey;
+
+use crate::conway::ConwayCompute;
+use crate::grid::{Boundary, CUBE_FACES};
+
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// A camera looking at the origin from above and in front, far enough back to see a shape two
+/// or three units across.
+pub fn orbit_camera() -> Camera {
+    Camera {
+        eye: (0.0, 1.5, 3.5).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: cgmath::Vector3::unit_y(),
+        aspect: 1.0,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 100.0,
+    }
+}
+
+/// A view of the depth buffer for a render target `size` pixels, keeping `texture` while its
+/// size matches and replacing it otherwise.
+pub fn depth_view(
+    device: &wgpu::Device,
+    texture: &mut Option<wgpu::Texture>,
+    (width, height): (u32, u32),
+) -> wgpu::TextureView {
+    let depth_texture = match texture.take() {
+        Some(texture) if (texture.width(), texture.height()) == (width, height) => texture,
+        _ => device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }),
+    };
+    texture
+        .insert(depth_texture)
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// A shape to draw a bounded world on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+    /// Joins opposite edges just as the toroidal boundary does.
+    Torus,
+    /// A cube world folded up into its cube.
+    Cube,
+    /// A cube world puffed out into a ball.
+    Sphere,
+}
+
+impl Surface {
+    /// The boundary whose edges meet as the shape's do.
+    pub fn boundary(self) -> Boundary {
+        match self {
+            Self::Torus => Boundary::Torus,
+            Self::Cube | Self::Sphere => Boundary::Cube,
+        }
+    }
+
+    /// The shape after this one, or `None` after the last to go back to a flat view.
+    pub fn next(surface: Option<Self>) -> Option<Self> {
+        match surface {
+            None => Some(Self::Torus),
+            Some(Self::Torus) => Some(Self::Cube),
+            Some(Self::Cube) => Some(Self::Sphere),
+            Some(Self::Sphere) => None,
+        }
+    }
+}
+
+impl fmt::Display for Surface {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Torus => "torus",
+            Self::Cube => "cube",
+            Self::Sphere => "sphere",
+        })
+    }
+}
+
+/// A vertex of a shape, where `tex_coords` runs 0 to 1 across the state texture. Shapes are not
+/// normal mapped, so have no tangents.
+fn vertex(position: [f32; 3], tex_coords: [f32; 2], normal: [f32; 3]) -> ModelVertex {
+    ModelVertex {
+        position,
+        tex_coords,
+        normal,
+        tangent: [0.0; 3],
+        bitangent: [0.0; 3],
+    }
+}
+
+/// Adds a patch of `segments` by `segments` quads, placing the vertex at each fraction of the
+/// way across and down the patch with `place`.
+fn add_patch(
+    vertices: &mut Vec<ModelVertex>,
+    indices: &mut Vec<u32>,
+    segments: u32,
+    place: impl Fn(f32, f32) -> ModelVertex,
+) {
+    let first = vertices.len() as u32;
+    for j in 0..=segments {
+        for i in 0..=segments {
+            vertices.push(place(
+                i as f32 / segments as f32,
+                j as f32 / segments as f32,
+            ));
+        }
+    }
+    let row = segments + 1;
+    for j in 0..segments {
+        for i in 0..segments {
+            let corner = first + j * row + i;
+            indices.extend([corner, corner + row, corner + 1]);
+            indices.extend([corner + 1, corner + row, corner + row + 1]);
+        }
+    }
+}
+
+/// The mesh of `surface`. Its material is the state texture, which the display binds itself.
+fn mesh(device: &wgpu::Device, surface: Surface) -> Mesh {
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    match surface {
+        Surface::Torus => {
+            // The texture runs around the ring across, and around the tube down
+            let (major, minor) = (1.0, 0.4);
+            add_patch(&mut vertices, &mut indices, 96, |s, t| {
+                let (u, v) = (s * TAU, t * TAU);
+                let normal = [v.cos() * u.cos(), v.sin(), v.cos() * u.sin()];
+                let ring = major + minor * v.cos();
+                let position = [ring * u.cos(), minor * v.sin(), ring * u.sin()];
+                vertex(position, [s, t], normal)
+            });
+        }
+        Surface::Cube | Surface::Sphere => {
+            for (face, [normal, right, down]) in CUBE_FACES.iter().enumerate() {
+                add_patch(&mut vertices, &mut indices, 24, |s, t| {
+                    let point: [f32; 3] = std::array::from_fn(|i| {
+                        normal[i] as f32
+                            + (2.0 * s - 1.0) * right[i] as f32
+                            + (2.0 * t - 1.0) * down[i] as f32
+                    });
+                    let (position, normal) = if surface == Surface::Sphere {
+                        let length = point.iter().map(|c| c * c).sum::<f32>().sqrt();
+                        let unit = point.map(|c| c / length);
+                        (unit, unit)
+                    } else {
+                        (point, normal.map(|c| c as f32))
+                    };
+                    vertex(position, [(face as f32 + s) / 6.0, t], normal)
+                });
+            }
+        }
+    }
+
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{surface} Vertex Buffer")),
+        contents: bytemuck::cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{surface} Index Buffer")),
+        contents: bytemuck::cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+    Mesh {
+        vertex_buffer,
+        index_buffer,
+        num_elements: indices.len() as u32,
+        material: 0,
+    }
+}
+
+/// Draws a bounded world with `surface.wgsl` on a shape, seen through a camera that the arrow
+/// keys or WASD move around it.
+pub struct SurfaceDisplay {
+    surface: Surface,
+    mesh: Mesh,
+    pipeline: wgpu::RenderPipeline,
+    bind_groups: [wgpu::BindGroup; 2],
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_uniform: CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    /// Made to match the render target on the first draw, and again whenever its size changes.
+    depth_texture: Option<wgpu::Texture>,
+}
+
+impl SurfaceDisplay {
+    pub fn new(
+        device: &wgpu::Device,
+        conway: &ConwayCompute,
+        format: wgpu::TextureFormat,
+        surface: Surface,
+    ) -> Self {
+        let camera = orbit_camera();
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::bytes_of(&camera_uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Surface Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("surface.wgsl").into()),
+        });
+
+        // The state texture is the material, read a cell at a time rather than filtered
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Surface Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // One for each ping-pong texture
+        let bind_groups = conway.texture_views().each_ref().map(|view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Surface Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: camera_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Surface Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Surface Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ModelVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // The faces do not all wind the same way, and the depth test hides the far side
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            surface,
+            mesh: mesh(device, surface),
+            pipeline,
+            bind_groups,
+            camera,
+            camera_controller: CameraController::new(0.05),
+            camera_uniform,
+            camera_buffer,
+            depth_texture: None,
+        }
+    }
+
+    pub fn surface(&self) -> Surface {
+        self.surface
+    }
+
+    /// Passes a key to the camera controller, returning whether it moves the camera.
+    pub fn handle_key(&mut self, key: PhysicalKey, pressed: bool) -> bool {
+        match key {
+            PhysicalKey::Code(code) => self.camera_controller.handle_key(code, pressed),
+            PhysicalKey::Unidentified(_) => false,
+        }
+    }
+
+    /// Moves the camera for the keys held down, then records a pass drawing the latest
+    /// generation on the shape over the whole of `target`, which is `size` pixels.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+        conway: &ConwayCompute,
+    ) {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        self.camera_controller.update_camera(&mut self.camera);
+        self.camera.aspect = width as f32 / height as f32;
+        self.camera_uniform.update_view_proj(&self.camera);
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&self.camera_uniform),
+        );
+
+        let depth_view = depth_view(device, &mut self.depth_texture, (width, height));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Surface Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.1,
+                        b: 0.1,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_groups[conway.current_index()], &[]);
+        render_pass.set_vertex_buffer(0, self.mesh.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.mesh.num_elements, 0, 0..1);
+    }
+}
@@ -0,0 +1,54 @@
+// Draws the world on a 3D shape, lit from above, with the state texture as its material
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var conway_state: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> camera: Camera;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    // Position in the state texture, 0 to 1 across it
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    out.tex_coords = model.tex_coords;
+    out.normal = model.normal;
+    return out;
+}
+
+// Cell colours of the multi-colour variants, as in display.wgsl, with dead cells dark blue so the
+// shape shows
+fn palette(cell: f32) -> vec3<f32> {
+    switch (u32(round(cell))) {
+        case 0u: { return vec3<f32>(0.05, 0.06, 0.1); }
+        case 2u: { return vec3<f32>(1.0, 0.35, 0.3); }
+        case 3u: { return vec3<f32>(0.35, 0.55, 1.0); }
+        case 4u: { return vec3<f32>(1.0, 0.85, 0.25); }
+        default: { return vec3<f32>(1.0, 1.0, 1.0); }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(conway_state));
+    let coord = clamp(vec2<i32>(in.tex_coords * vec2<f32>(size)), vec2<i32>(0), size - 1);
+    let cell = textureLoad(conway_state, coord, 0).r;
+
+    let light = normalize(vec3<f32>(0.5, 1.0, 0.8));
+    let diffuse = 0.35 + 0.65 * max(dot(normalize(in.normal), light), 0.0);
+    return vec4<f32>(palette(cell) * diffuse, 1.0);
+}
@@ -0,0 +1,705 @@
+//! A bounded Life world too large for one texture, such as 65536 × 65536 cells.
+//!
+//! Cells are packed 32 to a word, and the words split into tiles, one per layer of an array
+//! texture. Each tile has a halo one word wide around it holding the edges of the tiles beside
+//! it, which every pass stitches as it writes, so that a step only reads its own layer.
+
+use anyhow::{Result, bail};
+use wgpu::util::DeviceExt;
+
+use crate::grid::{Boundary, Grid, Rect};
+use crate::rule::Rule;
+
+/// Cells packed into each texel.
+pub const CELLS_PER_WORD: u32 = 32;
+const WORKGROUP_SIZE: u32 = 16;
+/// The most cells read back at once, a byte each once unpacked.
+const MAX_READ_CELLS: u64 = 1 << 26;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    birth: u32,
+    survive: u32,
+    neighbourhood: u32,
+    wraps: u32,
+    tiles: [u32; 2],
+    tile_size: [u32; 2],
+    stamp_origin: [u32; 2],
+    stamp_size: [u32; 2],
+    seed: u32,
+    threshold: u32,
+    _padding: [u32; 2],
+}
+
+pub struct TiledWorld {
+    step_pipeline: wgpu::ComputePipeline,
+    stamp_pipeline: wgpu::ComputePipeline,
+    soup_pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    /// Pairs of words for stamping: the cells to overwrite, then their new values.
+    stamp_buffer: wgpu::Buffer,
+    textures: [wgpu::Texture; 2],
+    views: [wgpu::TextureView; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+    current_texture: usize,
+    generation: u64,
+    rule: Rule,
+    boundary: Boundary,
+    /// Tiles across and down.
+    tiles: (u32, u32),
+    /// Words across and rows down each tile, without its halo.
+    tile_size: (u32, u32),
+}
+
+impl TiledWorld {
+    /// Creates an empty world of `size` cells, split into the fewest tiles of at most `max_tile`
+    /// words across and rows down that divide it evenly. A world with dead edges is enlarged to
+    /// a whole number of words across, while a torus must already be one.
+    pub fn new(
+        device: &wgpu::Device,
+        size: (u32, u32),
+        rule: Rule,
+        boundary: Boundary,
+        max_tile: u32,
+    ) -> Result<Self> {
+        if !matches!(boundary, Boundary::Torus | Boundary::Dead) {
+            bail!("only a torus or a world with dead edges can be split into tiles");
+        }
+        if boundary == Boundary::Torus && !size.0.is_multiple_of(CELLS_PER_WORD) {
+            bail!(
+                "a {} × {} torus is not a whole number of {CELLS_PER_WORD}-cell words across",
+                size.0,
+                size.1
+            );
+        }
+        let split = |length: u32| {
+            let fewest = length.div_ceil(max_tile.max(1)).max(1);
+            let tiles = (fewest..=length.max(1))
+                .find(|tiles| length.is_multiple_of(*tiles))
+                .unwrap_or(1);
+            (tiles, length / tiles)
+        };
+        let (tiles_x, words) = split(size.0.div_ceil(CELLS_PER_WORD));
+        let (tiles_y, rows) = split(size.1);
+        let layers = tiles_x * tiles_y;
+        let max_layers = device.limits().max_texture_array_layers;
+        if layers > max_layers {
+            bail!(
+                "a {} × {} world needs {layers} tiles, more than the {max_layers} the GPU allows",
+                size.0,
+                size.1
+            );
+        }
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tiled Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("tiled.wgsl").into()),
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Tiled Bind Group Layout"),
+            entries: &[
+                // Input tiles (read)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Uint,
+                    },
+                    count: None,
+                },
+                // Output tiles (write)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::R32Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                // Rule, tiling and the parameters of stamps and soups
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Words to stamp
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Tiled Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Tiled Compute Pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let texture_descriptor = wgpu::TextureDescriptor {
+            label: Some("Tiled State Texture"),
+            size: wgpu::Extent3d {
+                width: words + 2,
+                height: rows + 2,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Uint,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        };
+        let textures = [
+            device.create_texture(&texture_descriptor),
+            device.create_texture(&texture_descriptor),
+        ];
+        // A single layer would otherwise be viewed as a plain 2D texture
+        let views = textures.each_ref().map(|texture| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2Array),
+                ..Default::default()
+            })
+        });
+
+        let params = Params {
+            birth: rule.birth.into(),
+            survive: rule.survive.into(),
+            neighbourhood: rule.neighbourhood.index(),
+            wraps: (boundary == Boundary::Torus).into(),
+            tiles: [tiles_x, tiles_y],
+            tile_size: [words, rows],
+            stamp_origin: [0; 2],
+            stamp_size: [0; 2],
+            seed: 0,
+            threshold: 0,
+            _padding: [0; 2],
+        };
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tiled Params Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let stamp_buffer = create_stamp_buffer(device, 2);
+        let bind_groups =
+            create_bind_groups(device, &layout, &views, &params_buffer, &stamp_buffer);
+
+        Ok(Self {
+            step_pipeline: pipeline("step"),
+            stamp_pipeline: pipeline("stamp"),
+            soup_pipeline: pipeline("soup"),
+            layout,
+            params_buffer,
+            stamp_buffer,
+            textures,
+            views,
+            bind_groups,
+            current_texture: 0,
+            generation: 0,
+            rule,
+            boundary,
+            tiles: (tiles_x, tiles_y),
+            tile_size: (words, rows),
+        })
+    }
+
+    /// Width and height in cells.
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.tiles.0 * self.tile_size.0 * CELLS_PER_WORD,
+            self.tiles.1 * self.tile_size.1,
+        )
+    }
+
+    /// Tiles across and down.
+    pub fn tiles(&self) -> (u32, u32) {
+        self.tiles
+    }
+
+    /// Cells across and down each tile.
+    pub fn tile_size(&self) -> (u32, u32) {
+        (self.tile_size.0 * CELLS_PER_WORD, self.tile_size.1)
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Number of steps taken since the world was created.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Index of the texture holding the latest generation, for callers that keep one bind group
+    /// per ping-pong texture.
+    pub fn current_index(&self) -> usize {
+        self.current_texture
+    }
+
+    pub fn texture_views(&self) -> &[wgpu::TextureView; 2] {
+        &self.views
+    }
+
+    pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.dispatch(encoder, &self.step_pipeline);
+        self.current_texture = 1 - self.current_texture;
+        self.generation += 1;
+    }
+
+    /// Records a pass running `pipeline` over every word of every tile.
+    fn dispatch(&self, encoder: &mut wgpu::CommandEncoder, pipeline: &wgpu::ComputePipeline) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Tiled Compute Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &self.bind_groups[self.current_texture], &[]);
+        compute_pass.dispatch_workgroups(
+            self.tile_size.0.div_ceil(WORKGROUP_SIZE),
+            self.tile_size.1.div_ceil(WORKGROUP_SIZE),
+            self.tiles.0 * self.tiles.1,
+        );
+    }
+
+    /// Runs a stamp or soup pass with `params` straight away. Steps only read the rule and the
+    /// tiling, which these leave as they were.
+    fn run(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, soup: bool, params: Params) {
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Tiled Encoder"),
+        });
+        let pipeline = if soup {
+            &self.soup_pipeline
+        } else {
+            &self.stamp_pipeline
+        };
+        self.dispatch(&mut encoder, pipeline);
+        queue.submit(std::iter::once(encoder.finish()));
+        self.current_texture = 1 - self.current_texture;
+    }
+
+    fn params(&self) -> Params {
+        Params {
+            birth: self.rule.birth.into(),
+            survive: self.rule.survive.into(),
+            neighbourhood: self.rule.neighbourhood.index(),
+            wraps: (self.boundary == Boundary::Torus).into(),
+            tiles: [self.tiles.0, self.tiles.1],
+            tile_size: [self.tile_size.0, self.tile_size.1],
+            stamp_origin: [0; 2],
+            stamp_size: [0; 2],
+            seed: 0,
+            threshold: 0,
+            _padding: [0; 2],
+        }
+    }
+
+    /// Replaces every cell with a random one, alive with probability `density`.
+    pub fn soup(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, density: f32, seed: u64) {
+        let params = Params {
+            seed: (seed ^ (seed >> 32)) as u32,
+            threshold: (f64::from(density.clamp(0.0, 1.0)) * f64::from(u32::MAX)) as u32,
+            ..self.params()
+        };
+        self.run(device, queue, true, params);
+    }
+
+    /// Replaces the whole world with `grid`, its top-left corner at the world's. Cells beyond
+    /// the grid die.
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, grid: &Grid) {
+        let (words, rows) = self.tile_size;
+        for ty in 0..self.tiles.1 {
+            for tx in 0..self.tiles.0 {
+                let origin = (tx * words * CELLS_PER_WORD, ty * rows);
+                let data = pack(grid, origin, (words, rows));
+                queue.write_texture(
+                    wgpu::TexelCopyTextureInfo {
+                        texture: &self.textures[self.current_texture],
+                        mip_level: 0,
+                        origin: wgpu::Origin3d {
+                            x: 1,
+                            y: 1,
+                            z: ty * self.tiles.0 + tx,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    bytemuck::cast_slice(&data),
+                    wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(words * 4),
+                        rows_per_image: Some(rows),
+                    },
+                    wgpu::Extent3d {
+                        width: words,
+                        height: rows,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+        // Stamping nothing copies the tiles, stitching their halos
+        self.run(device, queue, false, self.params());
+    }
+
+    /// Overwrites the cells under `grid` with its top-left corner at `origin`. On a torus the
+    /// pattern wraps around the edges, otherwise it is clipped.
+    pub fn stamp(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        grid: &Grid,
+        origin: (i32, i32),
+    ) {
+        let (width, height) = self.size();
+        let world_words = width / CELLS_PER_WORD;
+        let wraps = self.boundary == Boundary::Torus;
+        // The columns and rows of the world under the grid, from the first
+        let span = |origin: i32, length: u32, world: u32| -> Option<(u32, u32, u32)> {
+            if wraps {
+                Some((origin.rem_euclid(world as i32) as u32, 0, length.min(world)))
+            } else {
+                let skip = origin.min(0).unsigned_abs();
+                let first = origin.max(0) as u32;
+                let end = (i64::from(origin) + i64::from(length)).min(i64::from(world));
+                (end > i64::from(first)).then(|| (first, skip, end as u32 - first))
+            }
+        };
+        let (Some((x, skip_x, columns)), Some((y, skip_y, rows))) = (
+            span(origin.0, grid.width, width),
+            span(origin.1, grid.height, height),
+        ) else {
+            return;
+        };
+        let first_word = x / CELLS_PER_WORD;
+        let stamp_words = (x % CELLS_PER_WORD + columns)
+            .div_ceil(CELLS_PER_WORD)
+            .min(world_words);
+
+        let mut words = vec![0; (stamp_words * rows * 2) as usize];
+        for j in 0..rows {
+            for i in 0..columns {
+                let column = (x + i) % width;
+                let word = (column / CELLS_PER_WORD + world_words - first_word) % world_words;
+                let bit = 1 << (column % CELLS_PER_WORD);
+                let index = ((j * stamp_words + word) * 2) as usize;
+                words[index] |= bit;
+                if grid.get(skip_x + i, skip_y + j) {
+                    words[index + 1] |= bit;
+                }
+            }
+        }
+
+        let size = (words.len() * 4) as u64;
+        if size > self.stamp_buffer.size() {
+            self.stamp_buffer = create_stamp_buffer(device, size.next_power_of_two());
+            self.bind_groups = create_bind_groups(
+                device,
+                &self.layout,
+                &self.views,
+                &self.params_buffer,
+                &self.stamp_buffer,
+            );
+        }
+        queue.write_buffer(&self.stamp_buffer, 0, bytemuck::cast_slice(&words));
+        let params = Params {
+            stamp_origin: [first_word, y],
+            stamp_size: [stamp_words, rows],
+            ..self.params()
+        };
+        self.run(device, queue, false, params);
+    }
+
+    /// Copies the latest generation back to the CPU.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<Grid>> + use<> {
+        let (width, height) = self.size();
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        self.read_region(device, queue, rect)
+    }
+
+    /// Copies a rectangle of the latest generation back to the CPU, each overlapping part of a
+    /// tile into its own part of one buffer.
+    ///
+    /// The copy is submitted immediately, so the returned future does not borrow `self`. On
+    /// native it blocks on the device when polled; on the web it resolves from the browser's
+    /// event loop.
+    pub fn read_region(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: Rect,
+    ) -> impl Future<Output = Result<Grid>> + use<> {
+        let copied = self.copy_region(device, queue, rect);
+        let device = device.clone();
+        async move {
+            let (buffer, parts, receiver) = copied?;
+            #[cfg(not(target_arch = "wasm32"))]
+            device.poll(wgpu::PollType::Wait)?;
+            #[cfg(target_arch = "wasm32")]
+            let _ = device;
+            receiver.await??;
+
+            let mut grid = Grid::new(rect.width, rect.height);
+            {
+                let data = buffer.slice(..).get_mapped_range();
+                for part in &parts {
+                    part.unpack(&data, rect, &mut grid);
+                }
+            }
+            buffer.unmap();
+            Ok(grid)
+        }
+    }
+
+    /// Submits copies of the words under `rect` to a buffer being mapped for reading.
+    #[allow(clippy::type_complexity)]
+    fn copy_region(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rect: Rect,
+    ) -> Result<(
+        wgpu::Buffer,
+        Vec<ReadPart>,
+        futures_channel::oneshot::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    )> {
+        if u64::from(rect.width) * u64::from(rect.height) > MAX_READ_CELLS {
+            bail!(
+                "{} × {} cells are too many to read back at once",
+                rect.width,
+                rect.height
+            );
+        }
+        let (tile_width, tile_height) = self.tile_size();
+        let mut parts = Vec::new();
+        let mut size = 0_u64;
+        for ty in 0..self.tiles.1 {
+            for tx in 0..self.tiles.0 {
+                let (left, top) = (tx * tile_width, ty * tile_height);
+                // The cells of the tile in the rectangle
+                let x0 = rect.x.max(left);
+                let x1 = (rect.x + rect.width).min(left + tile_width);
+                let y0 = rect.y.max(top);
+                let y1 = (rect.y + rect.height).min(top + tile_height);
+                if x0 >= x1 || y0 >= y1 {
+                    continue;
+                }
+                let first_word = (x0 - left) / CELLS_PER_WORD;
+                let words = (x1 - left).div_ceil(CELLS_PER_WORD) - first_word;
+                let bytes_per_row =
+                    (words * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+                let offset = size.next_multiple_of(256);
+                parts.push(ReadPart {
+                    layer: ty * self.tiles.0 + tx,
+                    first_word,
+                    first_row: y0 - top,
+                    words,
+                    rows: y1 - y0,
+                    origin: (left + first_word * CELLS_PER_WORD, y0),
+                    offset,
+                    bytes_per_row,
+                });
+                size = offset + u64::from(bytes_per_row * (y1 - y0));
+            }
+        }
+        if size > device.limits().max_buffer_size {
+            bail!(
+                "{} × {} cells are too many to read back at once",
+                rect.width,
+                rect.height
+            );
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tiled Readback Buffer"),
+            size: size.max(4),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Tiled Readback Encoder"),
+        });
+        for part in &parts {
+            encoder.copy_texture_to_buffer(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.textures[self.current_texture],
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: part.first_word + 1,
+                        y: part.first_row + 1,
+                        z: part.layer,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::TexelCopyBufferInfo {
+                    buffer: &buffer,
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset: part.offset,
+                        bytes_per_row: Some(part.bytes_per_row),
+                        rows_per_image: Some(part.rows),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: part.words,
+                    height: part.rows,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        Ok((buffer, parts, receiver))
+    }
+}
+
+/// Words of one tile copied back to the CPU.
+struct ReadPart {
+    layer: u32,
+    first_word: u32,
+    first_row: u32,
+    words: u32,
+    rows: u32,
+    /// The world position of the first cell copied.
+    origin: (u32, u32),
+    offset: u64,
+    bytes_per_row: u32,
+}
+
+impl ReadPart {
+    /// Sets the cells of `grid`, which holds `rect`, that this part copied.
+    fn unpack(&self, data: &[u8], rect: Rect, grid: &mut Grid) {
+        let start = self.offset as usize;
+        let rows = data[start..].chunks(self.bytes_per_row as usize);
+        for (j, row) in rows.take(self.rows as usize).enumerate() {
+            let words: &[u32] = bytemuck::cast_slice(&row[..self.words as usize * 4]);
+            let y = self.origin.1 + j as u32 - rect.y;
+            for (i, &word) in words.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let bit = bits.trailing_zeros();
+                    bits &= bits - 1;
+                    let x = self.origin.0 + i as u32 * CELLS_PER_WORD + bit;
+                    if (rect.x..rect.x + rect.width).contains(&x) {
+                        grid.set(x - rect.x, y, true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The cells of `grid` from `origin`, packed into words, `size` of them across and down.
+fn pack(grid: &Grid, origin: (u32, u32), (words, rows): (u32, u32)) -> Vec<u32> {
+    let mut data = vec![0; (words * rows) as usize];
+    let columns = grid
+        .width
+        .saturating_sub(origin.0)
+        .min(words * CELLS_PER_WORD);
+    for j in 0..rows.min(grid.height.saturating_sub(origin.1)) {
+        for i in 0..columns {
+            if grid.get(origin.0 + i, origin.1 + j) {
+                data[(j * words + i / CELLS_PER_WORD) as usize] |= 1 << (i % CELLS_PER_WORD);
+            }
+        }
+    }
+    data
+}
+
+fn create_stamp_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Tiled Stamp Buffer"),
+        size: size.max(8),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    views: &[wgpu::TextureView; 2],
+    params_buffer: &wgpu::Buffer,
+    stamp_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    let bind_group = |label, input: &wgpu::TextureView, output: &wgpu::TextureView| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(output),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: stamp_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    };
+    [
+        bind_group("Tiled Bind Group A", &views[0], &views[1]),
+        bind_group("Tiled Bind Group B", &views[1], &views[0]),
+    ]
+}
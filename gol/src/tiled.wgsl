@@ -0,0 +1,177 @@
+// A bounded two-state world too large for one texture, with cells packed 32 to a word (bit n =
+// the cell n to the right of the word's first) and the words split into tiles, one per layer.
+// Each tile is surrounded by a halo one word wide holding its neighbours' edge words, so a step
+// only reads its own layer. Every pass stitches the halos as it goes: a word on the edge of a
+// tile is also written into the halos of the tiles it borders.
+
+const WORD: i32 = 32;
+
+struct Params {
+    // Bit n set = born/survives with n neighbours
+    birth: u32,
+    survive: u32,
+    // 0 = Moore, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+    // 1 = the edges wrap around, 0 = cells beyond them are dead
+    wraps: u32,
+    // Tiles across and down
+    tiles: vec2<u32>,
+    // Words across and rows down each tile, halo excluded
+    tile_size: vec2<u32>,
+    // The words `stamp` overwrites, from this word and row of the world, wrapping around
+    stamp_origin: vec2<u32>,
+    stamp_size: vec2<u32>,
+    // Random cells written by `soup`: alive where the hash of the cell is below `threshold`
+    seed: u32,
+    threshold: u32,
+}
+
+@group(0) @binding(0) var current_state: texture_2d_array<u32>;
+@group(0) @binding(1) var next_state: texture_storage_2d_array<r32uint, write>;
+@group(0) @binding(2) var<uniform> params: Params;
+// Pairs of words for `stamp`, row by row: the cells to overwrite, then their new values
+@group(0) @binding(3) var<storage, read> stamp_words: array<u32>;
+
+fn tile_of(layer: u32) -> vec2<u32> {
+    return vec2<u32>(layer % params.tiles.x, layer / params.tiles.x);
+}
+
+// The word and row of the world a word of a tile is at
+fn world_word(layer: u32, local: vec2<u32>) -> vec2<u32> {
+    return tile_of(layer) * params.tile_size + local;
+}
+
+// Writes a word of a tile, and into the halo of each tile beside it whose edge the word is on
+fn store(layer: u32, local: vec2<u32>, value: u32) {
+    let position = vec2<i32>(local);
+    textureStore(next_state, position + 1, layer, vec4<u32>(value, 0u, 0u, 0u));
+
+    let size = vec2<i32>(params.tile_size);
+    let tiles = vec2<i32>(params.tiles);
+    let tile = vec2<i32>(tile_of(layer));
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let d = vec2<i32>(dx, dy);
+            // On the edge towards d along each axis that d moves along
+            let edge = select(size - 1, vec2<i32>(0), d < vec2<i32>(0));
+            if (all(d == vec2<i32>(0)) || any((d != vec2<i32>(0)) & (position != edge))) {
+                continue;
+            }
+            var neighbour = tile + d;
+            if (any(neighbour < vec2<i32>(0)) || any(neighbour >= tiles)) {
+                if (params.wraps == 0u) {
+                    continue;
+                }
+                neighbour = (neighbour + tiles) % tiles;
+            }
+            let halo = position - d * size + 1;
+            let index = u32(neighbour.y * tiles.x + neighbour.x);
+            textureStore(next_state, halo, index, vec4<u32>(value, 0u, 0u, 0u));
+        }
+    }
+}
+
+// Cell `x` of three words side by side, counting from the first cell of the middle one
+fn cell(words: array<u32, 3>, x: i32) -> u32 {
+    let bit = x + WORD;
+    return (words[bit / WORD] >> u32(bit % WORD)) & 1u;
+}
+
+// Whether the cell `d` away is a neighbour, as in chunks.wgsl. `up` is whether a triangular cell
+// points up.
+fn is_neighbour(d: vec2<i32>, up: bool) -> bool {
+    if (all(d == vec2<i32>(0))) {
+        return false;
+    }
+    switch (params.neighbourhood) {
+        // Hexagonal: no top-right or bottom-left neighbour
+        case 1u: {
+            return abs(d.x) <= 1 && d.x * d.y != -1;
+        }
+        // Triangular: three cells touch the point of the triangle and five its base
+        case 2u: {
+            return d.y != select(1, -1, up) || abs(d.x) <= 1;
+        }
+        default: {
+            return abs(d.x) <= 1;
+        }
+    }
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= params.tile_size)) {
+        return;
+    }
+    let layer = id.z;
+    // Texel of the word, past the halo
+    let texel = vec2<i32>(id.xy) + 1;
+
+    // The word and those around it, which the halo provides at the edges
+    var rows: array<array<u32, 3>, 3>;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let word = textureLoad(current_state, texel + vec2<i32>(dx, dy), layer, 0).r;
+            rows[dy + 1][dx + 1] = word;
+        }
+    }
+
+    // Words start on even cells, so the row tells which way the first triangle points
+    let row = world_word(layer, id.xy).y;
+    var next = 0u;
+    for (var x = 0; x < WORD; x++) {
+        let up = (u32(x) + row) % 2u == 0u;
+        var neighbors = 0u;
+        for (var dy = -1; dy <= 1; dy++) {
+            for (var dx = -2; dx <= 2; dx++) {
+                if (is_neighbour(vec2<i32>(dx, dy), up)) {
+                    neighbors += cell(rows[dy + 1], x + dx);
+                }
+            }
+        }
+        // Life-like rules: look up the neighbour count in the birth or survival mask
+        let mask = select(params.birth, params.survive, cell(rows[1], x) == 1u);
+        next |= ((mask >> neighbors) & 1u) << u32(x);
+    }
+    store(layer, id.xy, next);
+}
+
+// Copies the world, overwriting the cells under the stamp
+@compute @workgroup_size(16, 16, 1)
+fn stamp(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= params.tile_size)) {
+        return;
+    }
+    let layer = id.z;
+    var word = textureLoad(current_state, vec2<i32>(id.xy) + 1, layer, 0).r;
+    let size = params.tiles * params.tile_size;
+    let offset = (world_word(layer, id.xy) + size - params.stamp_origin) % size;
+    if (all(offset < params.stamp_size)) {
+        let index = (offset.y * params.stamp_size.x + offset.x) * 2u;
+        word = (word & ~stamp_words[index]) | stamp_words[index + 1u];
+    }
+    store(layer, id.xy, word);
+}
+
+// A 32-bit integer hash (PCG)
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Replaces the world with random cells
+@compute @workgroup_size(16, 16, 1)
+fn soup(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= params.tile_size)) {
+        return;
+    }
+    let position = world_word(id.z, id.xy);
+    let row = hash(params.seed ^ hash(position.y));
+    var word = 0u;
+    for (var x = 0u; x < u32(WORD); x++) {
+        let h = hash(row ^ (position.x * u32(WORD) + x));
+        word |= u32(h < params.threshold) << x;
+    }
+    store(id.z, id.xy, word);
+}
@@ -0,0 +1,208 @@
+use wgpu::util::DeviceExt;
+
+use crate::overlay::Overlay;
+use crate::tiled::TiledWorld;
+use crate::viewport::Viewport;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TiledView {
+    centre: [f32; 2],
+    zoom: f32,
+    neighbourhood: u32,
+    size: [u32; 2],
+    tile_size: [u32; 2],
+    tiles_across: u32,
+    _padding: [u32; 3],
+}
+
+/// Draws a `TiledWorld` with `tiled_display.wgsl`, finding each cell's tile as it goes.
+pub struct TiledDisplay {
+    pipeline: wgpu::RenderPipeline,
+    bind_groups: [wgpu::BindGroup; 2],
+    view_buffer: wgpu::Buffer,
+    view: TiledView,
+}
+
+impl TiledDisplay {
+    pub fn new(
+        device: &wgpu::Device,
+        world: &TiledWorld,
+        overlay: &Overlay,
+        format: wgpu::TextureFormat,
+        viewport: &Viewport,
+    ) -> Self {
+        let (width, height) = world.size();
+        let (tile_width, tile_height) = world.tile_size();
+        let view = TiledView {
+            centre: [viewport.centre.0, viewport.centre.1],
+            zoom: viewport.zoom,
+            neighbourhood: world.rule().neighbourhood.index(),
+            size: [width, height],
+            tile_size: [tile_width, tile_height],
+            tiles_across: world.tiles().0,
+            _padding: [0; 3],
+        };
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tiled View Buffer"),
+            contents: bytemuck::bytes_of(&view),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tiled Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("tiled_display.wgsl").into()),
+        });
+
+        // Tiles and the view
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Tiled Display Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Uint,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // One bind group for each ping-pong texture
+        let bind_groups = world.texture_views().each_ref().map(|tiles| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Tiled Display Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(tiles),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: view_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Tiled Display Pipeline Layout"),
+            bind_group_layouts: &[&layout, overlay.layout()],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tiled Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_groups,
+            view_buffer,
+            view,
+        }
+    }
+
+    pub fn set_viewport(&self, queue: &wgpu::Queue, viewport: &Viewport) {
+        let view = TiledView {
+            centre: [viewport.centre.0, viewport.centre.1],
+            zoom: viewport.zoom,
+            ..self.view
+        };
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&view));
+    }
+
+    /// Records a pass drawing the latest generation over the whole of `target`.
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        world: &TiledWorld,
+        overlay: &Overlay,
+    ) {
+        self.record(encoder, target, world, overlay, None);
+    }
+
+    /// Records a pass drawing the latest generation into `area` of `target`, given in pixels
+    /// as `(x, y, width, height)`, over what is already there.
+    pub fn draw_inset(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        world: &TiledWorld,
+        overlay: &Overlay,
+        area: [f32; 4],
+    ) {
+        self.record(encoder, target, world, overlay, Some(area));
+    }
+
+    fn record(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        world: &TiledWorld,
+        overlay: &Overlay,
+        area: Option<[f32; 4]>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Tiled Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: match area {
+                        Some(_) => wgpu::LoadOp::Load,
+                        None => wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if let Some([x, y, width, height]) = area {
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_groups[world.current_index()], &[]);
+        render_pass.set_bind_group(1, overlay.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
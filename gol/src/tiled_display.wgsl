@@ -0,0 +1,162 @@
+// Draws a world split into tiles by tiled.rs, with cells packed 32 to a word, over a fullscreen
+// triangle. Each fragment finds the tile its cell is in and loads the word past the tile's halo.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // Create fullscreen triangle (covers whole screen)
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    // Row 0 is the top of the screen, matching window coordinates
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+// Tiles of words, one per layer, as in tiled.wgsl
+@group(0) @binding(0) var tiles: texture_2d_array<u32>;
+
+// Part of the world shown in the window
+struct View {
+    // World position, in cells, at the centre of the window
+    centre: vec2<f32>,
+    // 1 = the world fills the window
+    zoom: f32,
+    // Shape of the cells: 0 = square, 1 = hexagonal, 2 = triangular
+    neighbourhood: u32,
+    // Cells across and down the world
+    size: vec2<u32>,
+    // Cells across and down each tile
+    tile_size: vec2<u32>,
+    // Tiles across the world
+    tiles_across: u32,
+}
+
+@group(0) @binding(1) var<uniform> view: View;
+
+// Editing aids drawn over the world, as in display.wgsl
+struct Overlay {
+    ghost_origin: vec2<i32>,
+    ghost_mode: u32,
+    selection_visible: u32,
+    selection_min: vec2<u32>,
+    selection_max: vec2<u32>,
+}
+
+@group(1) @binding(0) var ghost_cells: texture_2d<f32>;
+@group(1) @binding(1) var<uniform> overlay: Overlay;
+
+// The cell drawn at a position given in cells, as in display.wgsl
+fn cell_at(pos: vec2<f32>) -> vec2<i32> {
+    let row = floor(pos.y);
+    switch (view.neighbourhood) {
+        // Hexagonal: the nearest cell centre, each row shifted half a cell left of the one above
+        case 1u: {
+            var nearest = vec2<i32>(0);
+            var best = 4.0;
+            for (var r = row - 1.0; r <= row + 1.0; r += 1.0) {
+                let shift = r / 2.0;
+                let column = floor(pos.x + shift);
+                let d = pos - vec2<f32>(column + 0.5 - shift, r + 0.5);
+                if (dot(d, d) < best) {
+                    best = dot(d, d);
+                    nearest = vec2<i32>(i32(column), i32(r));
+                }
+            }
+            return nearest;
+        }
+        // Triangular: between two triangle centres, split by the edge they share
+        case 2u: {
+            let u = pos.x - 0.5;
+            let column = floor(u);
+            let across = u - column;
+            let down = pos.y - row;
+            let cell = vec2<i32>(i32(column), i32(row));
+            let up = ((cell.x + cell.y) % 2 + 2) % 2 == 0;
+            let left = select(across < 1.0 - down, across < down, up);
+            return cell + vec2<i32>(select(1, 0, left), 0);
+        }
+        default: {
+            return vec2<i32>(floor(pos));
+        }
+    }
+}
+
+// Whether a cell of the world is alive
+fn alive(cell: vec2<u32>) -> bool {
+    let tile = cell / view.tile_size;
+    let local = cell % view.tile_size;
+    let layer = tile.y * view.tiles_across + tile.x;
+    let texel = vec2<i32>(vec2<u32>(local.x / 32u, local.y) + 1u);
+    let word = textureLoad(tiles, texel, layer, 0).r;
+    return ((word >> (local.x % 32u)) & 1u) == 1u;
+}
+
+// The share of live cells a pixel `footprint` cells across covers, from up to 4 × 4 samples as
+// in display.wgsl
+fn coverage(pos: vec2<f32>, footprint: vec2<f32>) -> f32 {
+    let samples = vec2<i32>(clamp(ceil(footprint), vec2<f32>(1.0), vec2<f32>(4.0)));
+    var live = 0;
+    for (var y = 0; y < samples.y; y++) {
+        for (var x = 0; x < samples.x; x++) {
+            let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / vec2<f32>(samples) - 0.5;
+            let cell = cell_at(pos + offset * footprint);
+            if (all(cell >= vec2<i32>(0)) && all(cell < vec2<i32>(view.size))
+                && alive(vec2<u32>(cell))) {
+                live++;
+            }
+        }
+    }
+    return f32(live) / f32(samples.x * samples.y);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pos = view.centre + (in.uv - 0.5) * vec2<f32>(view.size) / view.zoom;
+    // Taken before any branch, as derivatives must be
+    let footprint = fwidth(pos);
+    let coord = cell_at(pos);
+
+    // Bounds check
+    if (any(coord < vec2<i32>(0)) || any(coord >= vec2<i32>(view.size))) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);  // Black for out of bounds
+    }
+    let grid_pos = vec2<u32>(coord);
+
+    // Live cells = white, dead cells = black
+    var color = vec3<f32>(coverage(pos, footprint));
+
+    if (overlay.selection_visible != 0u
+        && all(grid_pos >= overlay.selection_min) && all(grid_pos < overlay.selection_max)) {
+        let on_edge = any(grid_pos == overlay.selection_min)
+            || any(grid_pos + vec2<u32>(1u) == overlay.selection_max);
+        if (on_edge) {
+            color = mix(color, vec3<f32>(1.0, 0.8, 0.2), 0.5);  // Yellow = marquee
+        } else {
+            color = mix(color, vec3<f32>(1.0, 0.8, 0.2), 0.15);
+        }
+    }
+
+    if (overlay.ghost_mode != 0u) {
+        let ghost_size = vec2<i32>(textureDimensions(ghost_cells));
+        var local = coord - overlay.ghost_origin;
+        if (overlay.ghost_mode == 2u) {
+            let size = vec2<i32>(view.size);
+            local = (local % size + size) % size;
+        }
+        if (all(local >= vec2<i32>(0)) && all(local < ghost_size)) {
+            if (textureLoad(ghost_cells, local, 0).r > 0.5) {
+                color = mix(color, vec3<f32>(0.2, 0.9, 0.4), 0.7);  // Green = ghost cell
+            } else {
+                color = mix(color, vec3<f32>(0.2, 0.3, 0.8), 0.3);  // Blue = ghost footprint
+            }
+        }
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,705 @@
+//! Langton's ant and other turmites: ants walking a grid of coloured cells, each reading the
+//! colour under it, repainting the cell, turning and stepping forward by its rule table.
+//!
+//! `turmite.wgsl` steps every ant at once. When ants share a cell, only the one with the lowest
+//! index acts on it; the others wait a step, so the outcome does not depend on which ant the GPU
+//! gets to first. `TurmiteWorld::step` is the CPU reference.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use rand::Rng;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 64;
+/// The most states and colours a rule can have, so its table fits the shader's uniform.
+const MAX_STATES: usize = 16;
+const MAX_COLOURS: usize = 16;
+/// Visits are kept above a cell's colour in the same u32, and stop counting here.
+const MAX_VISITS: u32 = (1 << 24) - 1;
+
+/// The rules the app cycles through: Langton's ant, three more ants and a two-state turmite
+/// that grows a square spiral.
+pub const TURMITES: [&str; 5] = [
+    "RL",
+    "LLRR",
+    "RLR",
+    "LRRRRRLLR",
+    "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}",
+];
+
+/// How far an ant turns, clockwise, before stepping forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Turn {
+    None,
+    Right,
+    UTurn,
+    Left,
+}
+
+impl Turn {
+    /// Quarter turns clockwise.
+    pub fn quarters(self) -> u8 {
+        match self {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::UTurn => 2,
+            Turn::Left => 3,
+        }
+    }
+
+    /// Golly's turmite notation: 1 = no turn, 2 = right, 4 = U-turn, 8 = left.
+    fn golly(self) -> u32 {
+        1 << self.quarters()
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Turn::None => 'N',
+            Turn::Right => 'R',
+            Turn::UTurn => 'U',
+            Turn::Left => 'L',
+        }
+    }
+}
+
+/// What an ant in some state does on a cell of some colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transition {
+    /// The colour left on the cell.
+    pub write: u8,
+    pub turn: Turn,
+    pub next_state: u8,
+}
+
+/// A turmite's rule table, with a transition for each state and colour.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Turmite {
+    pub states: u8,
+    pub colours: u8,
+    /// Indexed by `state * colours + colour`.
+    pub table: Vec<Transition>,
+}
+
+impl Turmite {
+    pub fn transition(&self, state: u8, colour: u8) -> Transition {
+        self.table[usize::from(state) * usize::from(self.colours) + usize::from(colour)]
+    }
+
+    /// The letters of an ant, one turn per colour, or `None` for a turmite with more states or
+    /// other colours than an ant's.
+    fn ant_letters(&self) -> Option<String> {
+        let ant = self.states == 1
+            && self.table.iter().enumerate().all(|(colour, t)| {
+                usize::from(t.write) == (colour + 1) % usize::from(self.colours)
+                    && t.next_state == 0
+            });
+        ant.then(|| self.table.iter().map(|t| t.turn.letter()).collect())
+    }
+}
+
+/// Parses Golly's turmite notation, `{{{write, turn, next state}, ...}, ...}` with a list of
+/// transitions for each colour inside a list for each state.
+fn parse_table(s: &str) -> Result<Turmite> {
+    let mut states: Vec<Vec<Transition>> = Vec::new();
+    let mut colours = Vec::new();
+    let mut numbers = Vec::new();
+    let mut number = String::new();
+    let mut depth = 0;
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if !number.is_empty() {
+            numbers.push(number.parse::<u32>()?);
+            number.clear();
+        }
+        match (c, depth) {
+            ('{', 0..=2) => depth += 1,
+            ('}', 3) => {
+                let &[write, turn, next_state] = numbers.as_slice() else {
+                    bail!("turmite transitions need three numbers, not {numbers:?}");
+                };
+                let turn = match turn {
+                    1 => Turn::None,
+                    2 => Turn::Right,
+                    4 => Turn::UTurn,
+                    8 => Turn::Left,
+                    _ => bail!("invalid turn {turn} in turmite, expected 1, 2, 4 or 8"),
+                };
+                colours.push((write, turn, next_state));
+                numbers.clear();
+                depth -= 1;
+            }
+            ('}', 2) => {
+                states.push(
+                    colours
+                        .drain(..)
+                        .map(|(write, turn, next_state)| Transition {
+                            write: write.min(255) as u8,
+                            turn,
+                            next_state: next_state.min(255) as u8,
+                        })
+                        .collect(),
+                );
+                depth -= 1;
+            }
+            ('}', 1) => depth -= 1,
+            (',', 1..=3) => {}
+            _ => bail!("unexpected {c:?} in turmite {s:?}"),
+        }
+    }
+    if depth != 0 || states.is_empty() {
+        bail!("unbalanced braces in turmite {s:?}");
+    }
+
+    let colour_count = states[0].len();
+    if !(2..=MAX_COLOURS).contains(&colour_count) || states.len() > MAX_STATES {
+        bail!("turmites can have 1 to {MAX_STATES} states and 2 to {MAX_COLOURS} colours");
+    }
+    if states.iter().any(|state| state.len() != colour_count) {
+        bail!("every state of a turmite needs a transition for each colour");
+    }
+    let table: Vec<Transition> = states.iter().flatten().copied().collect();
+    if table
+        .iter()
+        .any(|t| usize::from(t.write) >= colour_count || usize::from(t.next_state) >= states.len())
+    {
+        bail!("turmite {s:?} writes a colour or enters a state it does not have");
+    }
+    Ok(Turmite {
+        states: states.len() as u8,
+        colours: colour_count as u8,
+        table,
+    })
+}
+
+impl FromStr for Turmite {
+    type Err = anyhow::Error;
+
+    /// Parses an ant's turns, one letter per colour (`L`, `R`, `N` for none or `U` for a U-turn)
+    /// such as `RL` for Langton's ant, or a full table in Golly's turmite notation such as
+    /// `{{{1,2,0},{0,8,0}}}`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with('{') {
+            return parse_table(s);
+        }
+        let turns = s
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'N' => Ok(Turn::None),
+                'R' => Ok(Turn::Right),
+                'U' => Ok(Turn::UTurn),
+                'L' => Ok(Turn::Left),
+                _ => bail!("invalid turn {c:?} in ant {s:?}, expected L, R, N or U"),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !(2..=MAX_COLOURS).contains(&turns.len()) {
+            bail!("ants need 2 to {MAX_COLOURS} turns, one per colour");
+        }
+        let colours = turns.len();
+        Ok(Turmite {
+            states: 1,
+            colours: colours as u8,
+            table: turns
+                .into_iter()
+                .enumerate()
+                .map(|(colour, turn)| Transition {
+                    write: ((colour + 1) % colours) as u8,
+                    turn,
+                    next_state: 0,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for Turmite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(letters) = self.ant_letters() {
+            return f.write_str(&letters);
+        }
+        let states: Vec<String> = self
+            .table
+            .chunks(usize::from(self.colours))
+            .map(|state| {
+                let transitions: Vec<String> = state
+                    .iter()
+                    .map(|t| format!("{{{},{},{}}}", t.write, t.turn.golly(), t.next_state))
+                    .collect();
+                format!("{{{}}}", transitions.join(","))
+            })
+            .collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+/// An ant's position, the way it faces (0 = up, then clockwise) and its state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ant {
+    pub x: u32,
+    pub y: u32,
+    pub direction: u8,
+    pub state: u8,
+}
+
+impl Ant {
+    /// Turns, then steps forward around the edges of a torus.
+    fn advance(&mut self, turn: Turn, (width, height): (u32, u32)) {
+        self.direction = (self.direction + turn.quarters()) % 4;
+        match self.direction {
+            0 => self.y = (self.y + height - 1) % height,
+            1 => self.x = (self.x + 1) % width,
+            2 => self.y = (self.y + 1) % height,
+            _ => self.x = (self.x + width - 1) % width,
+        }
+    }
+}
+
+/// Where the ants start.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Start {
+    /// A square of neighbouring ants in the middle, all facing up.
+    #[default]
+    Cluster,
+    /// Evenly spaced across the middle row, facing up.
+    Line,
+    /// Anywhere, facing any way.
+    Random,
+}
+
+impl Start {
+    pub fn next(self) -> Self {
+        match self {
+            Start::Cluster => Start::Line,
+            Start::Line => Start::Random,
+            Start::Random => Start::Cluster,
+        }
+    }
+
+    /// `count` ants on a grid of `width` by `height` cells.
+    pub fn place(self, count: u32, (width, height): (u32, u32), rng: &mut impl Rng) -> Vec<Ant> {
+        let ant = |x, y, direction| Ant {
+            x,
+            y,
+            direction,
+            state: 0,
+        };
+        match self {
+            Start::Cluster => {
+                let side = (count as f64).sqrt().ceil() as u32;
+                let (left, top) = (
+                    (width - side.min(width)) / 2,
+                    (height - side.min(height)) / 2,
+                );
+                (0..count)
+                    .map(|i| ant((left + i % side) % width, (top + i / side) % height, 0))
+                    .collect()
+            }
+            Start::Line => (0..count)
+                .map(|i| ant((2 * i + 1) * width / (2 * count), height / 2, 0))
+                .collect(),
+            Start::Random => (0..count)
+                .map(|_| {
+                    ant(
+                        rng.random_range(0..width),
+                        rng.random_range(0..height),
+                        rng.random_range(0..4),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Start {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Start::Cluster => "in a cluster",
+            Start::Line => "in a line",
+            Start::Random => "scattered",
+        })
+    }
+}
+
+/// Ants on a torus of coloured cells held on the CPU, with how often each cell was stepped on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TurmiteWorld {
+    pub width: u32,
+    pub height: u32,
+    /// Row by row from the top-left.
+    pub colours: Vec<u8>,
+    pub visits: Vec<u32>,
+    pub ants: Vec<Ant>,
+}
+
+impl TurmiteWorld {
+    /// Blank cells, none of them visited yet.
+    pub fn new(width: u32, height: u32, ants: Vec<Ant>) -> Self {
+        let cells = (width * height) as usize;
+        Self {
+            width,
+            height,
+            colours: vec![0; cells],
+            visits: vec![0; cells],
+            ants,
+        }
+    }
+
+    fn index(&self, ant: &Ant) -> usize {
+        (ant.y * self.width + ant.x) as usize
+    }
+
+    /// Moves every ant once. This is the CPU reference for `turmite.wgsl`.
+    pub fn step(&mut self, rule: &Turmite) {
+        let mut owners = vec![u32::MAX; self.colours.len()];
+        for (i, ant) in self.ants.iter().enumerate() {
+            let owner = &mut owners[self.index(ant)];
+            *owner = (*owner).min(i as u32);
+        }
+        for i in 0..self.ants.len() {
+            let ant = self.ants[i];
+            let cell = self.index(&ant);
+            if owners[cell] != i as u32 {
+                continue;
+            }
+            let transition = rule.transition(ant.state, self.colours[cell]);
+            self.colours[cell] = transition.write;
+            self.visits[cell] = (self.visits[cell] + 1).min(MAX_VISITS);
+            let ant = &mut self.ants[i];
+            ant.state = transition.next_state;
+            ant.advance(transition.turn, (self.width, self.height));
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    width: u32,
+    height: u32,
+    colours: u32,
+    ants: u32,
+}
+
+/// An ant as `turmite.wgsl` keeps it. `claimed` is the cell it claimed this step, to be
+/// released again.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct AntRaw {
+    x: u32,
+    y: u32,
+    // Direction, with the state above it
+    heading: u32,
+    claimed: u32,
+}
+
+/// Ants stepped by `turmite.wgsl`. Each cell is a u32 holding its colour in the low byte and its
+/// visits above it; a second buffer has the lowest ant claiming each cell, followed by the most
+/// visits to any cell.
+pub struct TurmiteCompute {
+    claim_pipeline: wgpu::ComputePipeline,
+    act_pipeline: wgpu::ComputePipeline,
+    release_pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    cell_buffer: wgpu::Buffer,
+    owner_buffer: wgpu::Buffer,
+    ant_buffer: wgpu::Buffer,
+    rule: Turmite,
+    width: u32,
+    height: u32,
+    ants: u32,
+    steps: u64,
+}
+
+impl TurmiteCompute {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: &TurmiteWorld,
+        rule: &Turmite,
+    ) -> Self {
+        let (width, height) = (world.width, world.height);
+        let cells = u64::from(width * height);
+        let ants = world.ants.len() as u32;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Turmite Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("turmite.wgsl").into()),
+        });
+
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = wgpu::BufferBindingType::Storage { read_only: false };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Turmite Bind Group Layout"),
+            entries: &[
+                // Cells
+                entry(0, storage),
+                // Claims, then the most visits
+                entry(1, storage),
+                // Ants
+                entry(2, storage),
+                // Size and counts
+                entry(3, wgpu::BufferBindingType::Uniform),
+                // Rule table
+                entry(4, wgpu::BufferBindingType::Uniform),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Turmite Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("Turmite {entry_point} Pipeline")),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let usage = wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC;
+        let cell_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Turmite Cell Buffer"),
+            size: cells * 4,
+            usage,
+            mapped_at_creation: false,
+        });
+        let owner_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Turmite Owner Buffer"),
+            size: (cells + 1) * 4,
+            usage,
+            mapped_at_creation: false,
+        });
+        let ant_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Turmite Ant Buffer"),
+            size: u64::from(ants.max(1)) * std::mem::size_of::<AntRaw>() as u64,
+            usage,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Turmite Params Buffer"),
+            contents: bytemuck::bytes_of(&Params {
+                width,
+                height,
+                colours: u32::from(rule.colours),
+                ants,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        // Write, turn and next state for every state and colour
+        let mut table = [[0u32; 4]; MAX_STATES * MAX_COLOURS];
+        for (row, t) in table.iter_mut().zip(&rule.table) {
+            *row = [
+                u32::from(t.write),
+                u32::from(t.turn.quarters()),
+                u32::from(t.next_state),
+                0,
+            ];
+        }
+        let table_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Turmite Table Buffer"),
+            contents: bytemuck::cast_slice(&table),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Turmite Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: cell_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: owner_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: ant_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: table_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let turmites = Self {
+            claim_pipeline: pipeline("claim"),
+            act_pipeline: pipeline("act"),
+            release_pipeline: pipeline("release"),
+            bind_group,
+            cell_buffer,
+            owner_buffer,
+            ant_buffer,
+            rule: rule.clone(),
+            width,
+            height,
+            ants,
+            steps: 0,
+        };
+        turmites.write(queue, world);
+        turmites
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn rule(&self) -> &Turmite {
+        &self.rule
+    }
+
+    pub fn ant_count(&self) -> u32 {
+        self.ants
+    }
+
+    /// Moves every ant `steps` times in one pass.
+    pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Turmite Compute Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        let workgroups = self.ants.div_ceil(WORKGROUP_SIZE);
+        for _ in 0..steps {
+            for pipeline in [
+                &self.claim_pipeline,
+                &self.act_pipeline,
+                &self.release_pipeline,
+            ] {
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+        }
+        drop(compute_pass);
+        self.steps += u64::from(steps);
+    }
+
+    /// Number of times each ant has been moved.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// The cells, then the claims followed by the most visits to a cell, for a display.
+    pub fn cell_buffers(&self) -> (&wgpu::Buffer, &wgpu::Buffer) {
+        (&self.cell_buffer, &self.owner_buffer)
+    }
+
+    pub fn ant_buffer(&self) -> &wgpu::Buffer {
+        &self.ant_buffer
+    }
+
+    /// Replaces the cells and ants with `world`'s, which must match in size and ant count.
+    pub fn write(&self, queue: &wgpu::Queue, world: &TurmiteWorld) {
+        let cells: Vec<u32> = world
+            .colours
+            .iter()
+            .zip(&world.visits)
+            .map(|(&colour, &visits)| u32::from(colour) | visits.min(MAX_VISITS) << 8)
+            .collect();
+        queue.write_buffer(&self.cell_buffer, 0, bytemuck::cast_slice(&cells));
+
+        let mut owners = vec![u32::MAX; cells.len()];
+        owners.push(world.visits.iter().copied().max().unwrap_or(0));
+        queue.write_buffer(&self.owner_buffer, 0, bytemuck::cast_slice(&owners));
+
+        let ants: Vec<AntRaw> = world
+            .ants
+            .iter()
+            .map(|ant| AntRaw {
+                x: ant.x,
+                y: ant.y,
+                heading: u32::from(ant.direction) | u32::from(ant.state) << 8,
+                claimed: 0,
+            })
+            .collect();
+        queue.write_buffer(&self.ant_buffer, 0, bytemuck::cast_slice(&ants));
+    }
+
+    /// Copies the cells and ants back to the CPU, as `ConwayCompute::read` does.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = Result<TurmiteWorld>> + use<> {
+        let (cell_size, ant_size) = (self.cell_buffer.size(), self.ant_buffer.size());
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Turmite Readback Buffer"),
+            size: cell_size + ant_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Turmite Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.cell_buffer, 0, &buffer, 0, cell_size);
+        encoder.copy_buffer_to_buffer(&self.ant_buffer, 0, &buffer, cell_size, ant_size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        let device = device.clone();
+        let (width, height, ants) = (self.width, self.height, self.ants as usize);
+
+        async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            device.poll(wgpu::PollType::Wait)?;
+            #[cfg(target_arch = "wasm32")]
+            let _ = device;
+            receiver.await??;
+
+            let mut world = TurmiteWorld::new(width, height, Vec::new());
+            {
+                let data = buffer.slice(..).get_mapped_range();
+                let (cells, rest) = data.split_at(cell_size as usize);
+                let cells: &[u32] = bytemuck::cast_slice(cells);
+                for (i, &cell) in cells.iter().enumerate() {
+                    world.colours[i] = (cell & 0xff) as u8;
+                    world.visits[i] = cell >> 8;
+                }
+                let raw: &[AntRaw] = bytemuck::cast_slice(rest);
+                world.ants = raw[..ants]
+                    .iter()
+                    .map(|ant| Ant {
+                        x: ant.x,
+                        y: ant.y,
+                        direction: (ant.heading & 3) as u8,
+                        state: (ant.heading >> 8) as u8,
+                    })
+                    .collect();
+            }
+            buffer.unmap();
+
+            Ok(world)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// One step of every ant, in three passes: each ant claims the cell it stands on, the lowest
+// claiming ant acts on it, then every claim is released. Matches `TurmiteWorld::step` in
+// turmite.rs.
+
+struct Params {
+    width: u32,
+    height: u32,
+    // Colours in the rule
+    colours: u32,
+    ants: u32,
+}
+
+struct Ant {
+    x: u32,
+    y: u32,
+    // Direction (0 = up, then clockwise), with the state above it
+    heading: u32,
+    // The cell claimed this step
+    claimed: u32,
+}
+
+// Colour in the low byte, visits above it
+@group(0) @binding(0) var<storage, read_write> cells: array<u32>;
+// The lowest ant claiming each cell, or 0xffffffff; then the most visits to any cell
+@group(0) @binding(1) var<storage, read_write> owners: array<atomic<u32>>;
+@group(0) @binding(2) var<storage, read_write> ants: array<Ant>;
+@group(0) @binding(3) var<uniform> params: Params;
+// Write, turn (quarters clockwise) and next state, indexed by state * colours + colour
+@group(0) @binding(4) var<uniform> table: array<vec4<u32>, 256>;
+
+const MAX_VISITS: u32 = 0xffffffu;
+
+@compute @workgroup_size(64)
+fn claim(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if (i >= params.ants) {
+        return;
+    }
+    let cell = ants[i].y * params.width + ants[i].x;
+    ants[i].claimed = cell;
+    atomicMin(&owners[cell], i);
+}
+
+@compute @workgroup_size(64)
+fn act(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if (i >= params.ants) {
+        return;
+    }
+    var ant = ants[i];
+    let cell = ant.claimed;
+    // Another ant on the same cell goes first; this one waits
+    if (atomicLoad(&owners[cell]) != i) {
+        return;
+    }
+
+    let colour = cells[cell] & 0xffu;
+    let state = ant.heading >> 8u;
+    let transition = table[state * params.colours + colour];
+    let visits = min((cells[cell] >> 8u) + 1u, MAX_VISITS);
+    cells[cell] = transition.x | (visits << 8u);
+    atomicMax(&owners[params.width * params.height], visits);
+
+    let direction = ((ant.heading & 3u) + transition.y) % 4u;
+    switch (direction) {
+        case 0u: { ant.y = (ant.y + params.height - 1u) % params.height; }
+        case 1u: { ant.x = (ant.x + 1u) % params.width; }
+        case 2u: { ant.y = (ant.y + 1u) % params.height; }
+        default: { ant.x = (ant.x + params.width - 1u) % params.width; }
+    }
+    ant.heading = direction | (transition.z << 8u);
+    ants[i] = ant;
+}
+
+@compute @workgroup_size(64)
+fn release(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if (i >= params.ants) {
+        return;
+    }
+    atomicStore(&owners[ants[i].claimed], 0xffffffffu);
+}
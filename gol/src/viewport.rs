@@ -0,0 +1,87 @@
+use crate::options::MAX_SIZE;
+use crate::rule::Neighbourhood;
+
+pub const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 64.0;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ViewUniform {
+    centre: [f32; 2],
+    zoom: f32,
+    neighbourhood: u32,
+    palette: u32,
+    _padding: u32,
+}
+
+/// The part of the grid shown in the window.
+///
+/// Window positions are given as fractions of the window size, (0, 0) being the top-left
+/// corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// The grid position, in cells, at the centre of the window.
+    pub centre: (f32, f32),
+    /// How many times larger than the window-filling size the grid is drawn.
+    pub zoom: f32,
+}
+
+impl Viewport {
+    /// Shows the whole grid, which leans to the left with hexagonal cells.
+    pub fn new(grid_size: (u32, u32), neighbourhood: Neighbourhood) -> Self {
+        let (width, height) = (grid_size.0 as f32, grid_size.1 as f32);
+        match neighbourhood {
+            Neighbourhood::Hexagonal => Self {
+                centre: ((width - height / 2.0) / 2.0, height / 2.0),
+                zoom: (width / (width + height / 2.0)).max(MIN_ZOOM),
+            },
+            Neighbourhood::Moore | Neighbourhood::Triangular => Self {
+                centre: (width / 2.0, height / 2.0),
+                zoom: 1.0,
+            },
+        }
+    }
+
+    /// The grid position, in cells, at a window position.
+    pub fn grid_position(&self, grid_size: (u32, u32), window: (f64, f64)) -> (f64, f64) {
+        let zoom = f64::from(self.zoom);
+        (
+            f64::from(self.centre.0) + (window.0 - 0.5) * f64::from(grid_size.0) / zoom,
+            f64::from(self.centre.1) + (window.1 - 0.5) * f64::from(grid_size.1) / zoom,
+        )
+    }
+
+    /// Scales the view by `factor`, keeping the cell under `window` in place.
+    pub fn zoom_about(&mut self, grid_size: (u32, u32), window: (f64, f64), factor: f32) {
+        let (x, y) = self.grid_position(grid_size, window);
+        // Worlds larger than a texture can be zoomed in as far as the largest texture
+        let largest = grid_size.0.max(grid_size.1) as f32 / MAX_SIZE as f32;
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM * largest.max(1.0));
+        let zoom = f64::from(self.zoom);
+        self.centre = (
+            (x - (window.0 - 0.5) * f64::from(grid_size.0) / zoom) as f32,
+            (y - (window.1 - 0.5) * f64::from(grid_size.1) / zoom) as f32,
+        );
+    }
+
+    /// Drags the grid by a distance given as a fraction of the window size.
+    pub fn pan(&mut self, grid_size: (u32, u32), delta: (f64, f64)) {
+        let zoom = f64::from(self.zoom);
+        self.centre = (
+            (f64::from(self.centre.0) - delta.0 * f64::from(grid_size.0) / zoom) as f32,
+            (f64::from(self.centre.1) - delta.1 * f64::from(grid_size.1) / zoom) as f32,
+        );
+    }
+
+    /// The view for a shader drawing cells of the neighbourhood's shape in a palette given by
+    /// `Variant::palette`.
+    pub fn uniform(&self, neighbourhood: Neighbourhood, palette: u32) -> ViewUniform {
+        ViewUniform {
+            centre: [self.centre.0, self.centre.1],
+            zoom: self.zoom,
+            neighbourhood: neighbourhood.index(),
+            palette,
+            _padding: 0,
+        }
+    }
+}
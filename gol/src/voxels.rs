@@ -0,0 +1,423 @@
+use scene::camera::{Camera, CameraUniform};
+use scene::camera_controller::CameraController;
+use scene::instance::InstanceRaw;
+use wgpu::util::DeviceExt;
+use winit::keyboard::PhysicalKey;
+use winit::window::Window;
+
+use crate::gpu::GpuContext;
+use crate::life3d::{Grid3d, Life3dCompute, Rule3d};
+use crate::surface::{DEPTH_FORMAT, depth_view, orbit_camera};
+
+/// Cells along each edge of a 3D world.
+pub const VOXEL_GRID_SIZE: u32 = 48;
+const WORKGROUP_SIZE: u32 = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VoxelVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl VoxelVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+            wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<VoxelVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// A unit cube about the origin, with four vertices a face so each face has its own normal.
+fn cube() -> (Vec<VoxelVertex>, Vec<u16>) {
+    let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    for axis in 0..3 {
+        for sign in [1.0, -1.0] {
+            let normal: [f32; 3] = axes[axis].map(|c| c * sign);
+            let (u, v) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+            let first = vertices.len() as u16;
+            for (a, b) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+                let position = std::array::from_fn(|i| normal[i] * 0.5 + u[i] * a + v[i] * b);
+                vertices.push(VoxelVertex { position, normal });
+            }
+            indices.extend([0, 1, 2, 0, 2, 3].map(|i| first + i));
+        }
+    }
+    (vertices, indices)
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct CompactParams {
+    size: u32,
+    scale: f32,
+    index_count: u32,
+    _padding: u32,
+}
+
+/// A 3D world stepped on the GPU and drawn as a cube for each live cell. Each frame
+/// `voxels_compact.wgsl` gathers the live cells into an instance buffer and counts them into
+/// the arguments of an indirect draw.
+pub struct Voxels {
+    life: Life3dCompute,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    indirect_buffer: wgpu::Buffer,
+    reset_pipeline: wgpu::ComputePipeline,
+    compact_pipeline: wgpu::ComputePipeline,
+    compact_bind_groups: [wgpu::BindGroup; 2],
+    render_pipeline: wgpu::RenderPipeline,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_uniform: CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    depth_texture: Option<wgpu::Texture>,
+}
+
+impl Voxels {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        initial_state: &Grid3d,
+        rule: Rule3d,
+    ) -> Self {
+        let life = Life3dCompute::new(device, queue, initial_state, rule);
+        let size = life.size();
+
+        let (vertices, indices) = cube();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Voxel Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Voxel Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // Written by `voxels_compact.wgsl` rather than built on the CPU, with room for every cell
+        // to be alive at once
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Voxel Instance Buffer"),
+            size: u64::from(size * size * size) * std::mem::size_of::<InstanceRaw>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+        let indirect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Voxel Indirect Buffer"),
+            size: std::mem::size_of::<wgpu::util::DrawIndexedIndirectArgs>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Voxel Compact Params Buffer"),
+            contents: bytemuck::bytes_of(&CompactParams {
+                size,
+                scale: 2.0 / size as f32,
+                index_count: indices.len() as u32,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let compact_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Voxel Compact Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("voxels_compact.wgsl").into()),
+        });
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let compact_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Voxel Compact Bind Group Layout"),
+            entries: &[
+                // Cells of the latest generation
+                storage(0, true),
+                // Instances
+                storage(1, false),
+                // Indirect draw arguments
+                storage(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        // One for each ping-pong buffer
+        let compact_bind_groups = life.state_buffers().each_ref().map(|cells| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Voxel Compact Bind Group"),
+                layout: &compact_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: cells.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: instance_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: indirect_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+        let compact_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Voxel Compact Pipeline Layout"),
+                bind_group_layouts: &[&compact_layout],
+                push_constant_ranges: &[],
+            });
+        let compute_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("Voxel {entry_point} Pipeline")),
+                layout: Some(&compact_pipeline_layout),
+                module: &compact_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let reset_pipeline = compute_pipeline("reset");
+        let compact_pipeline = compute_pipeline("compact");
+
+        let camera = orbit_camera();
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::bytes_of(&camera_uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Voxel Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Voxel Camera Bind Group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Voxel Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("voxels.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Voxel Pipeline Layout"),
+                bind_group_layouts: &[&camera_layout],
+                push_constant_ranges: &[],
+            });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Voxel Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[VoxelVertex::desc(), InstanceRaw::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            life,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            indirect_buffer,
+            reset_pipeline,
+            compact_pipeline,
+            compact_bind_groups,
+            render_pipeline,
+            camera,
+            camera_controller: CameraController::new(0.05),
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            depth_texture: None,
+        }
+    }
+
+    pub fn rule(&self) -> Rule3d {
+        self.life.rule()
+    }
+
+    /// Passes a key to the camera controller, returning whether it moves the camera.
+    pub fn handle_key(&mut self, key: PhysicalKey, pressed: bool) -> bool {
+        match key {
+            PhysicalKey::Code(code) => self.camera_controller.handle_key(code, pressed),
+            PhysicalKey::Unidentified(_) => false,
+        }
+    }
+
+    /// Steps the world, then records the passes that gather its live cells and draw them over
+    /// the whole of `target`, which is `size` pixels.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        self.camera_controller.update_camera(&mut self.camera);
+        self.camera.aspect = width as f32 / height as f32;
+        self.camera_uniform.update_view_proj(&self.camera);
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&self.camera_uniform),
+        );
+
+        self.life.step(encoder);
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Voxel Compact Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_bind_group(
+                0,
+                &self.compact_bind_groups[self.life.current_index()],
+                &[],
+            );
+            compute_pass.set_pipeline(&self.reset_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+            compute_pass.set_pipeline(&self.compact_pipeline);
+            let workgroups = self.life.size().div_ceil(WORKGROUP_SIZE);
+            compute_pass.dispatch_workgroups(workgroups, workgroups, workgroups);
+        }
+
+        let depth_view = depth_view(device, &mut self.depth_texture, (width, height));
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Voxel Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.1,
+                        b: 0.1,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed_indirect(&self.indirect_buffer, 0);
+    }
+
+    pub fn render(&mut self, window: &Window, ctx: &GpuContext) -> Result<(), wgpu::SurfaceError> {
+        window.request_redraw();
+
+        if !ctx.surface.is_configured() {
+            return Ok(());
+        }
+
+        let output = ctx.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Voxel Encoder"),
+            });
+        let size = (output.texture.width(), output.texture.height());
+        self.draw(&ctx.device, &ctx.queue, &mut encoder, &view, size);
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+// Gathers the live cells of a 3D world into instances for `draw_indexed_indirect`, so the CPU
+// never sees them. `reset` runs first, then `compact` once per cell.
+
+struct Params {
+    // Cells along each edge of the world
+    size: u32,
+    // Edge of a voxel in world units; the world spans -1 to 1 on each axis
+    scale: f32,
+    // Indices of the cube drawn for each voxel
+    index_count: u32,
+    _padding: u32,
+}
+
+// The arguments of an indexed indirect draw, as `wgpu::util::DrawIndexedIndirectArgs`
+struct DrawArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<storage, read> cells: array<u32>;
+// `InstanceRaw` from instance.rs: a model matrix then a normal matrix, column by column, with
+// no padding between the columns of the mat3
+@group(0) @binding(1) var<storage, read_write> instances: array<f32>;
+@group(0) @binding(2) var<storage, read_write> draw_args: DrawArgs;
+@group(0) @binding(3) var<uniform> params: Params;
+
+const FLOATS_PER_INSTANCE: u32 = 25u;
+
+@compute @workgroup_size(1)
+fn reset() {
+    draw_args.index_count = params.index_count;
+    atomicStore(&draw_args.instance_count, 0u);
+    draw_args.first_index = 0u;
+    draw_args.base_vertex = 0;
+    draw_args.first_instance = 0u;
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn compact(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = params.size;
+    if (any(global_id >= vec3<u32>(size))) {
+        return;
+    }
+    if (cells[(global_id.z * size + global_id.y) * size + global_id.x] == 0u) {
+        return;
+    }
+
+    let base = atomicAdd(&draw_args.instance_count, 1u) * FLOATS_PER_INSTANCE;
+    let centre = (vec3<f32>(global_id) + 0.5) * params.scale - 1.0;
+    // Voxels are drawn a little smaller than their cells, so neighbours stay distinct
+    let edge = params.scale * 0.85;
+    let model = array<f32, 16>(
+        edge, 0.0, 0.0, 0.0,
+        0.0, edge, 0.0, 0.0,
+        0.0, 0.0, edge, 0.0,
+        centre.x, centre.y, centre.z, 1.0,
+    );
+    for (var i = 0u; i < 16u; i++) {
+        instances[base + i] = model[i];
+    }
+    // Scaling evenly leaves the normals as they are
+    let normal = array<f32, 9>(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0);
+    for (var i = 0u; i < 9u; i++) {
+        instances[base + 16u + i] = normal[i];
+    }
+}
@@ -0,0 +1,222 @@
+//! The JavaScript API of the web build, and its options in the page URL. Pages create a
+//! `GameOfLife` once `init` has started the simulator, and each call is sent to the running app
+//! through its `EventLoopProxy`.
+
+use std::cell::RefCell;
+
+use futures_channel::oneshot;
+use wasm_bindgen::prelude::*;
+use winit::event_loop::EventLoopProxy;
+
+use crate::app::UserEvent;
+use crate::options::Options;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+thread_local! {
+    /// The proxy of the running event loop, set when it starts.
+    static PROXY: RefCell<Option<EventLoopProxy<UserEvent>>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn set_proxy(proxy: EventLoopProxy<UserEvent>) {
+    PROXY.with_borrow_mut(|slot| *slot = Some(proxy));
+}
+
+/// The options in the query of the page URL, such as `?rule=B36/S23&size=512`, then those in its
+/// hash, which is kept up to date as settings change. Invalid ones are logged and skipped.
+pub(crate) fn location_options() -> Options {
+    let mut options = Options::default();
+    let Some(location) = web_sys::window().map(|window| window.location()) else {
+        return options;
+    };
+    let parts = [location.search(), location.hash()];
+    for part in parts.iter().flatten() {
+        let part = part.trim_start_matches(['?', '#']);
+        for pair in part.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decoded = decode(key).zip(decode(value));
+            let Some((key, value)) = decoded else {
+                log::warn!("Ignoring {pair:?} in the page URL: it is not properly encoded");
+                continue;
+            };
+            if let Err(e) = options.set(&key, &value) {
+                log::warn!("Ignoring {key:?} in the page URL: {e:#}");
+            }
+        }
+    }
+    options
+}
+
+/// Replaces the hash of the page URL with `options`, without adding to the history.
+pub(crate) fn set_location_hash(options: &Options) {
+    let hash = options
+        .pairs()
+        .iter()
+        .map(|(key, value)| format!("{key}={}", js_sys::encode_uri_component(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    if location
+        .hash()
+        .is_ok_and(|current| current == format!("#{hash}"))
+    {
+        return;
+    }
+    let url = format!(
+        "{}{}#{hash}",
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default()
+    );
+    if let Err(e) = window
+        .history()
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)))
+    {
+        log::warn!("Unable to update the page URL: {e:?}");
+    }
+}
+
+/// Shows `message` in the page's `#message` element, or hides it for `None`.
+pub(crate) fn show_message(message: Option<&str>) {
+    const MESSAGE_ID: &str = "message";
+
+    let Some(element) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(MESSAGE_ID))
+    else {
+        return;
+    };
+    element.set_text_content(message);
+    if let Err(e) = element.toggle_attribute_with_force("hidden", message.is_none()) {
+        log::warn!("Unable to show the message: {e:?}");
+    }
+}
+
+/// Decodes a query component, where `+` stands for a space.
+fn decode(component: &str) -> Option<String> {
+    js_sys::decode_uri_component(&component.replace('+', " "))
+        .ok()
+        .map(String::from)
+}
+
+/// A request from the page, carried out by the app when it reaches the event loop.
+pub enum Command {
+    SetRule(Rule),
+    LoadPattern(Pattern),
+    /// Pauses, then advances this many generations.
+    Step(u32),
+    Pause,
+    Resume,
+    SetCell {
+        x: i32,
+        y: i32,
+        alive: bool,
+    },
+    Population(oneshot::Sender<Result<u64, String>>),
+    ExportRle(oneshot::Sender<Result<String, String>>),
+    /// Calls the function with the generation whenever it changes, or stops calling it.
+    OnGeneration(Option<js_sys::Function>),
+}
+
+/// A handle on the running simulator for pages building their own controls.
+#[wasm_bindgen]
+pub struct GameOfLife {
+    proxy: EventLoopProxy<UserEvent>,
+}
+
+#[wasm_bindgen]
+impl GameOfLife {
+    /// Fails unless `init` has started the simulator.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<GameOfLife, JsError> {
+        let proxy = PROXY.with_borrow(Clone::clone);
+        let proxy = proxy.ok_or_else(|| JsError::new("the simulator has not been started"))?;
+        Ok(Self { proxy })
+    }
+
+    /// Rebuilds the world under a rule such as `B36/S23`.
+    #[wasm_bindgen(js_name = setRule)]
+    pub fn set_rule(&self, rule: &str) -> Result<(), JsError> {
+        let rule = rule.parse().map_err(to_js_error)?;
+        self.send(Command::SetRule(rule))
+    }
+
+    /// Starts the world again from an RLE pattern, centred, under its rule if it has one.
+    #[wasm_bindgen(js_name = loadRle)]
+    pub fn load_rle(&self, rle: &str) -> Result<(), JsError> {
+        let pattern = Pattern::from_rle(rle).map_err(to_js_error)?;
+        self.send(Command::LoadPattern(pattern))
+    }
+
+    /// Pauses, then advances `generations` generations over the next frames.
+    pub fn step(&self, generations: u32) -> Result<(), JsError> {
+        self.send(Command::Step(generations))
+    }
+
+    pub fn pause(&self) -> Result<(), JsError> {
+        self.send(Command::Pause)
+    }
+
+    pub fn resume(&self) -> Result<(), JsError> {
+        self.send(Command::Resume)
+    }
+
+    /// Brings a cell to life in the current paint, or kills it.
+    #[wasm_bindgen(js_name = setCell)]
+    pub fn set_cell(&self, x: i32, y: i32, alive: bool) -> Result<(), JsError> {
+        self.send(Command::SetCell { x, y, alive })
+    }
+
+    /// Resolves to the number of live cells.
+    #[wasm_bindgen(js_name = getPopulation)]
+    pub fn get_population(&self) -> Result<js_sys::Promise, JsError> {
+        let (reply, population) = oneshot::channel();
+        self.send(Command::Population(reply))?;
+        Ok(promise(async move {
+            let population = population
+                .await
+                .map_err(|_| "the simulator has stopped")??;
+            Ok(JsValue::from_f64(population as f64))
+        }))
+    }
+
+    /// Resolves to the whole world as an RLE pattern.
+    #[wasm_bindgen(js_name = exportRle)]
+    pub fn export_rle(&self) -> Result<js_sys::Promise, JsError> {
+        let (reply, rle) = oneshot::channel();
+        self.send(Command::ExportRle(reply))?;
+        Ok(promise(async move {
+            let rle = rle.await.map_err(|_| "the simulator has stopped")??;
+            Ok(JsValue::from_str(&rle))
+        }))
+    }
+
+    /// Calls `callback` with each new generation number, or stops for `null`. There is a single
+    /// callback, so setting one replaces the last.
+    #[wasm_bindgen(js_name = onGeneration)]
+    pub fn on_generation(&self, callback: Option<js_sys::Function>) -> Result<(), JsError> {
+        self.send(Command::OnGeneration(callback))
+    }
+}
+
+impl GameOfLife {
+    fn send(&self, command: Command) -> Result<(), JsError> {
+        self.proxy
+            .send_event(UserEvent::Command(command))
+            .map_err(|_| JsError::new("the simulator has stopped"))
+    }
+}
+
+fn to_js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&format!("{e:#}"))
+}
+
+/// A promise settled by `task`, rejected with an `Error` if it fails.
+fn promise(task: impl Future<Output = Result<JsValue, String>> + 'static) -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        task.await
+            .map_err(|e| JsValue::from(js_sys::Error::new(&e)))
+    })
+}
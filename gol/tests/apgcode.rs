@@ -0,0 +1,66 @@
+//! Objects named by their canonical codes, whichever way round and wherever they are.
+
+use gol::apgcode::{self, Cells, UNIDENTIFIED};
+use gol::rule::Rule;
+
+/// The cells drawn as rows of `o` for alive and `.` for dead.
+fn cells(rows: &[&str]) -> Cells {
+    let mut cells = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == 'o' {
+                cells.push((x as i32, y as i32));
+            }
+        }
+    }
+    cells
+}
+
+/// The eight rotations and reflections of `cells`, moved well away from the origin.
+fn images(cells: &[(i32, i32)]) -> Vec<Cells> {
+    (0..8)
+        .map(|t| {
+            cells
+                .iter()
+                .map(|&(x, y)| {
+                    let (x, y) = if t & 4 != 0 { (y, x) } else { (x, y) };
+                    let x = if t & 1 != 0 { -x } else { x };
+                    let y = if t & 2 != 0 { -y } else { y };
+                    (x + 100, y - 50)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn still_lifes_and_oscillators_have_their_known_codes() {
+    for (rows, code) in [
+        (&["oo", "oo"][..], "xs4_33"),
+        (&[".oo.", "o..o", ".oo."], "xs6_696"),
+        (&["oo.", "o.o", ".o."], "xs5_253"),
+        (&["ooo"], "xp2_7"),
+    ] {
+        for image in images(&cells(rows)) {
+            assert_eq!(apgcode::classify(&image, &Rule::CONWAY), code, "{rows:?}");
+        }
+    }
+}
+
+#[test]
+fn a_glider_is_the_same_in_every_phase_and_direction() {
+    let mut glider = cells(&[".o.", "..o", "ooo"]);
+    for _ in 0..4 {
+        for image in images(&glider) {
+            assert_eq!(apgcode::classify(&image, &Rule::CONWAY), "xq4_153");
+        }
+        glider = apgcode::step(&glider, &Rule::CONWAY);
+    }
+}
+
+#[test]
+fn dying_objects_are_unidentified() {
+    let pair = cells(&["oo"]);
+    assert_eq!(apgcode::classify(&pair, &Rule::CONWAY), UNIDENTIFIED);
+    assert_eq!(apgcode::classify(&[], &Rule::CONWAY), UNIDENTIFIED);
+}
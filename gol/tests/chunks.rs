@@ -0,0 +1,45 @@
+//! Unbounded worlds checked against the CPU reference on a grid too large for anything to reach
+//! its edges.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::chunks::ChunkedWorld;
+use gol::grid::{Boundary, Grid};
+use gol::rule::Rule;
+
+/// Where the seed goes: far enough from a chunk edge that growth two cells a generation
+/// arrives between checks every 8 generations, unflagged.
+const ORIGIN: (i32, i32) = (37, 37);
+
+#[test]
+fn growth_at_full_speed_crosses_chunks() {
+    let ctx = context();
+    // Every rule here spreads as far as its neighbourhood reaches each generation
+    for rule in ["B1/S012345678", "B1/S0123456H", "B1/S012345L"] {
+        let rule: Rule = rule.parse().unwrap();
+        let mut seed = Grid::new(4, 4);
+        seed.set(1, 1, true);
+        seed.set(2, 2, true);
+        let mut world = ChunkedWorld::new(&ctx.device, &ctx.queue, rule, &seed, ORIGIN);
+
+        let mut expected = Grid::new(320, 320);
+        expected.paste(&seed, 158, 158);
+        for _ in 0..60 {
+            let mut encoder = ctx.device.create_command_encoder(&Default::default());
+            assert!(world.step(&mut encoder));
+            ctx.queue.submit(Some(encoder.finish()));
+            world.maintain(&ctx.device, &ctx.queue);
+            expected = expected.step(&rule, Boundary::Dead);
+        }
+
+        let (grid, (x, y)) = pollster::block_on(world.read(&ctx.device, &ctx.queue)).unwrap();
+        let (left, top) = (x + 158 - ORIGIN.0, y + 158 - ORIGIN.1);
+        assert!(left >= 0 && top >= 0, "{rule} spread to ({x}, {y})");
+        let mut actual = Grid::new(320, 320);
+        actual.paste(&grid, left as u32, top as u32);
+        assert_eq!(actual.population(), grid.population(), "{rule}");
+        assert_eq!(actual, expected, "{rule}");
+    }
+}
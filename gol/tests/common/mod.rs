@@ -0,0 +1,10 @@
+//! Helpers shared by the integration tests.
+
+use gol::gpu::HeadlessContext;
+
+/// A device on whichever adapter is at hand, software ones such as Mesa's llvmpipe included.
+/// Tests that need one fail without it, rather than pass having checked nothing.
+pub fn context() -> HeadlessContext {
+    pollster::block_on(HeadlessContext::new())
+        .unwrap_or_else(|error| panic!("the GPU tests need an adapter: {error:#}"))
+}
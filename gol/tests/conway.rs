@@ -0,0 +1,132 @@
+//! The compute shaders of bounded worlds, checked against the CPU reference.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::conway::{ConwayCompute, ConwayConfig};
+use gol::gpu::HeadlessContext;
+use gol::grid::{Boundary, Grid};
+use gol::rule::{Neighbourhood, Variant};
+use gol::sand;
+use gol::scheme::{self, UpdateScheme};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+const GENERATIONS: u32 = 8;
+
+/// A 64 × 48 soup coloured at random for `variant`.
+fn soup(variant: Variant, seed: u64) -> Grid {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = Grid::random(64, 48, 0.4, &mut rng);
+    grid.recolour(None, variant.colours(), &mut rng);
+    grid
+}
+
+/// Steps `grid` on the GPU, returning every generation after the first.
+fn run(ctx: &HeadlessContext, grid: &Grid, config: ConwayConfig) -> Vec<Grid> {
+    let mut compute = ConwayCompute::new(&ctx.device, &ctx.queue, grid, config);
+    (0..GENERATIONS)
+        .map(|_| {
+            let mut encoder = ctx.device.create_command_encoder(&Default::default());
+            compute.step(&mut encoder);
+            ctx.queue.submit(Some(encoder.finish()));
+            pollster::block_on(compute.read(&ctx.device, &ctx.queue)).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn colours_are_inherited_as_on_the_cpu() {
+    let ctx = context();
+    for variant in [Variant::Immigration, Variant::QuadLife] {
+        for neighbourhood in [
+            Neighbourhood::Moore,
+            Neighbourhood::Hexagonal,
+            Neighbourhood::Triangular,
+        ] {
+            let rule = neighbourhood.rule();
+            for boundary in [Boundary::Torus, Boundary::Dead] {
+                let config = ConwayConfig {
+                    rule,
+                    boundary,
+                    variant,
+                    ..Default::default()
+                };
+                let mut expected = soup(variant, 5);
+                for (generation, actual) in run(&ctx, &expected, config).into_iter().enumerate() {
+                    expected = expected.step(&rule, boundary);
+                    assert_eq!(
+                        actual,
+                        expected,
+                        "{variant:?} {neighbourhood:?} on a {boundary:?} world, generation {}",
+                        generation + 1
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn sand_falls_as_on_the_cpu() {
+    let ctx = context();
+    // Odd sides are walled, even ones wrap on a torus
+    for (width, height) in [(64, 48), (63, 47)] {
+        for boundary in [Boundary::Torus, Boundary::Dead] {
+            let config = ConwayConfig {
+                boundary,
+                variant: Variant::Sand,
+                ..Default::default()
+            };
+            let mut rng = StdRng::seed_from_u64(11);
+            let mut expected = Grid::random(width, height, 0.6, &mut rng);
+            expected.recolour(None, Variant::Sand.colours(), &mut rng);
+            for (generation, actual) in run(&ctx, &expected, config).into_iter().enumerate() {
+                expected = sand::step(&expected, boundary, generation as u64);
+                assert_eq!(
+                    actual,
+                    expected,
+                    "{width} × {height} {boundary:?} world, generation {}",
+                    generation + 1
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn update_schemes_pick_the_cells_the_cpu_does() {
+    let ctx = context();
+    for scheme in [
+        UpdateScheme::Probabilistic {
+            birth: 0.3,
+            death: 0.7,
+        },
+        UpdateScheme::Asynchronous { alpha: 0.5 },
+        UpdateScheme::Checkerboard,
+        UpdateScheme::Sweep,
+    ] {
+        for variant in [Variant::Life, Variant::QuadLife] {
+            let rule = Neighbourhood::Moore.rule();
+            let config = ConwayConfig {
+                rule,
+                boundary: Boundary::Torus,
+                variant,
+                scheme,
+                ..Default::default()
+            };
+            let mut expected = soup(variant, 13);
+            for (generation, actual) in run(&ctx, &expected, config).into_iter().enumerate() {
+                expected =
+                    scheme::step(&expected, &rule, Boundary::Torus, scheme, generation as u32);
+                assert_eq!(
+                    actual,
+                    expected,
+                    "{scheme} {variant:?}, generation {}",
+                    generation + 1
+                );
+            }
+        }
+    }
+}
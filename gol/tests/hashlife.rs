@@ -0,0 +1,80 @@
+//! HashLife checked against the CPU reference, one generation at a time and in jumps of 2^k.
+
+use gol::grid::{Boundary, Grid};
+use gol::hashlife::HashLife;
+use gol::rule::{Neighbourhood, Rule};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Cells across the reference grid, enough that nothing reaches its edges in the generations
+/// run here.
+const SIZE: u32 = 256;
+
+/// A 16 × 16 soup in the middle of an empty reference grid, and HashLife holding the same cells.
+fn soup(rule: Rule, seed: u64) -> (Grid, HashLife) {
+    let soup = Grid::random(16, 16, 0.5, &mut StdRng::seed_from_u64(seed));
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.paste(&soup, SIZE / 2 - 8, SIZE / 2 - 8);
+    let origin = i64::from(SIZE / 2 - 8);
+    let life = HashLife::from_grid(rule, &soup, (origin, origin)).unwrap();
+    (grid, life)
+}
+
+fn window(life: &HashLife) -> Grid {
+    life.window((0, 0), SIZE, SIZE)
+}
+
+#[test]
+fn single_steps_match_the_cpu() {
+    for neighbourhood in [Neighbourhood::Moore, Neighbourhood::Hexagonal] {
+        let rule = neighbourhood.rule();
+        let (mut expected, mut life) = soup(rule, 29);
+        for generation in 1..=40 {
+            life.step().unwrap();
+            expected = expected.step(&rule, Boundary::Dead);
+            assert_eq!(life.generation(), generation);
+            assert_eq!(window(&life), expected, "{rule}, generation {generation}");
+        }
+        assert_eq!(life.population(), expected.population() as u64);
+    }
+}
+
+#[test]
+fn jumps_match_the_cpu() {
+    for neighbourhood in [Neighbourhood::Moore, Neighbourhood::Hexagonal] {
+        let rule = neighbourhood.rule();
+        // Once with room for every result, once collecting garbage after every jump
+        for limit in [None, Some(1)] {
+            let (mut expected, mut life) = soup(rule, 31);
+            if let Some(limit) = limit {
+                life.set_node_limit(limit);
+            }
+            for k in 1..=5 {
+                life.set_step_exponent(k);
+                life.step().unwrap();
+                for _ in 0..1 << k {
+                    expected = expected.step(&rule, Boundary::Dead);
+                }
+                assert_eq!(
+                    window(&life),
+                    expected,
+                    "{rule}, a jump of 2^{k} to generation {}",
+                    life.generation()
+                );
+            }
+            assert_eq!(life.generation(), 62);
+        }
+    }
+}
+
+#[test]
+fn the_universe_stops_growing_before_positions_overflow() {
+    let mut life = HashLife::new(Neighbourhood::Moore.rule()).unwrap();
+    assert!(life.set(i64::MAX, 0, true).is_err());
+    assert!(life.set(i64::MIN, 0, true).is_err());
+    life.set(1 << 60, 0, true).unwrap();
+    assert!(life.get(1 << 60, 0));
+    // Stepping a cell this far out would need a wider universe than positions can reach
+    assert!(life.step().is_err());
+    assert_eq!(life.generation(), 0);
+}
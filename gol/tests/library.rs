@@ -0,0 +1,32 @@
+//! The patterns compiled into the binary, behind the number keys.
+
+use gol::library;
+use gol::rule::Rule;
+
+#[test]
+fn every_pattern_parses_with_its_cells() {
+    let populations: Vec<_> = library::patterns()
+        .iter()
+        .map(|pattern| {
+            assert!(pattern.name.is_some());
+            assert!(pattern.rule.is_none_or(|rule| rule == Rule::CONWAY));
+            (pattern.name.clone().unwrap(), pattern.grid.population())
+        })
+        .collect();
+    let expected = [
+        ("Glider", 5),
+        ("Lightweight spaceship", 9),
+        ("Middleweight spaceship", 11),
+        ("Heavyweight spaceship", 13),
+        ("Pulsar", 48),
+        ("Pentadecathlon", 12),
+        ("Gosper glider gun", 36),
+        ("R-pentomino", 5),
+        ("Acorn", 7),
+        ("Diehard", 7),
+    ];
+    assert_eq!(
+        populations,
+        expected.map(|(name, population)| (name.to_string(), population))
+    );
+}
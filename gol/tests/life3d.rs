@@ -0,0 +1,29 @@
+//! Life in three dimensions on the GPU, checked against the CPU reference.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::life3d::{Grid3d, Life3dCompute, RULES_3D};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+#[test]
+fn steps_match_the_cpu() {
+    let ctx = context();
+    for (rule, density) in RULES_3D {
+        // The soup fills the cube, so cells meet across the joined faces from the start
+        let mut expected = Grid3d::soup(20, 20, density, &mut StdRng::seed_from_u64(17));
+        let mut compute = Life3dCompute::new(&ctx.device, &ctx.queue, &expected, rule);
+        for generation in 1..=6 {
+            let mut encoder = ctx.device.create_command_encoder(&Default::default());
+            compute.step(&mut encoder);
+            ctx.queue.submit(Some(encoder.finish()));
+            expected = expected.step(&rule);
+            let actual = pollster::block_on(compute.read(&ctx.device, &ctx.queue)).unwrap();
+            assert!(actual == expected, "{rule}, generation {generation}");
+        }
+        assert_eq!(compute.generation(), 6);
+        assert!(expected.population() > 0, "{rule} died out");
+    }
+}
@@ -0,0 +1,74 @@
+use gol::hashlife::HashLife;
+use gol::library;
+use gol::macrocell;
+use gol::options::MAX_SIZE;
+use gol::pattern::Pattern;
+use gol::rule::Rule;
+
+const GLIDER: &str = include_str!("fixtures/glider.mc");
+const FAR_APART: &str = include_str!("fixtures/far-apart.mc");
+
+#[test]
+fn reads_a_glider() {
+    let life = macrocell::read(GLIDER).unwrap();
+    let (pattern, origin) = life.to_pattern().unwrap();
+    let glider = Pattern::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+    assert_eq!(pattern.grid, glider.grid);
+    assert_eq!(pattern.rule, Some(Rule::default()));
+    // The level 4 root is centred, and the leaf is its south-east quadrant
+    assert_eq!(origin, (0, 0));
+    assert_eq!(Pattern::from_text(GLIDER).unwrap().grid, glider.grid);
+}
+
+#[test]
+fn fixtures_round_trip() {
+    for text in [GLIDER, FAR_APART] {
+        let life = macrocell::read(text).unwrap();
+        let again = macrocell::read(&macrocell::write(&life)).unwrap();
+        assert_eq!(again.rule(), life.rule());
+        assert_eq!(again.generation(), life.generation());
+        assert_eq!(again.population(), life.population());
+        assert_eq!(again.extent(), life.extent());
+    }
+}
+
+#[test]
+fn library_patterns_round_trip() {
+    for pattern in library::patterns() {
+        let life = HashLife::from_pattern(&pattern, (0, 0)).unwrap();
+        let text = macrocell::write(&life);
+        let again = macrocell::read(&text).unwrap();
+        assert_eq!(
+            again.to_grid().unwrap().0,
+            life.to_grid().unwrap().0,
+            "{:?} changed in\n{text}",
+            pattern.name
+        );
+    }
+}
+
+#[test]
+fn reads_patterns_too_large_for_a_grid() {
+    let life = macrocell::read(FAR_APART).unwrap();
+    assert_eq!(life.population(), 10);
+    assert_eq!(life.generation(), 42);
+    let ((left, top), (width, height)) = life.extent().unwrap();
+    assert_eq!((left, top), (-4096, -4096));
+    assert_eq!((width, height), (8187, 8187));
+    assert!(width > u64::from(MAX_SIZE));
+}
+
+#[test]
+fn writes_an_empty_universe() {
+    let life = HashLife::new(Rule::default()).unwrap();
+    let again = macrocell::read(&macrocell::write(&life)).unwrap();
+    assert_eq!(again.population(), 0);
+}
+
+#[test]
+fn rejects_malformed_patterns() {
+    assert!(macrocell::read("#R B3/S23\n.*$\n").is_err());
+    assert!(macrocell::read("[M2]\n4 0 0 0 2\n.*$\n").is_err());
+    assert!(macrocell::read("[M2]\n.*$\n5 0 0 0 1\n").is_err());
+    assert!(macrocell::read("[M2]\n.........*$\n").is_err());
+}
@@ -0,0 +1,107 @@
+//! Options read back from the `key=value` pairs the web build keeps in the page URL.
+
+use gol::grid::{Boundary, Grid};
+use gol::options::Options;
+use gol::pattern::Pattern;
+use gol::rule::Variant;
+
+#[test]
+fn pairs_round_trip_through_set() {
+    let mut glider = Grid::new(3, 3);
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        glider.set(x, y, true);
+    }
+    let options = Options {
+        rule: Some("B36/S23".parse().unwrap()),
+        size: 300,
+        boundary: Boundary::Dead,
+        variant: Variant::Immigration,
+        scheme: "probabilistic:0.5,0.25".parse().unwrap(),
+        seed: Some(42),
+        density: 0.45,
+        pattern: Some(Pattern {
+            name: Some("Glider".to_string()),
+            rule: Some("B3/S23".parse().unwrap()),
+            grid: glider,
+        }),
+        speed: Some(7.5),
+        connect: Some("ws://localhost:8080".to_string()),
+        ..Options::default()
+    };
+    let mut read = Options::default();
+    for (key, value) in options.pairs() {
+        read.set(key, &value).unwrap();
+    }
+    assert_eq!(read, options);
+
+    // Defaults are left out, and still come back
+    let mut read = Options::default();
+    for (key, value) in Options::default().pairs() {
+        read.set(key, &value).unwrap();
+    }
+    assert_eq!(read, Options::default());
+}
+
+#[test]
+fn bad_values_are_rejected_and_change_nothing() {
+    for (key, value) in [
+        ("rule", "B9/S23"),
+        ("size", "0"),
+        ("size", "big"),
+        ("boundary", "sphere"),
+        ("variant", "water"),
+        ("scheme", "asynchronous:2"),
+        ("seed", "-1"),
+        ("density", "1.5"),
+        ("pattern", "x = 100000, y = 100000\no!"),
+        ("speed", "0"),
+        ("window-size", "1280"),
+        ("present-mode", "sometimes"),
+        ("backend", "glide"),
+        ("colour", "red"),
+    ] {
+        let mut options = Options::default();
+        assert!(options.set(key, value).is_err(), "{key}={value}");
+        assert_eq!(options, Options::default(), "{key}={value}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn flags_override_the_config_file_which_overrides_the_defaults() {
+    use clap::Parser;
+    use gol::options::OptionArgs;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        options: OptionArgs,
+    }
+
+    let dir = std::env::temp_dir().join(format!("gol-options-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("gol.toml");
+    std::fs::write(&config, "rule = \"B36/S23\"\nsize = 128\ndensity = 0.5\n").unwrap();
+    let cli = Cli::parse_from([
+        "gol".as_ref(),
+        "--config".as_ref(),
+        config.as_os_str(),
+        "--size".as_ref(),
+        "256".as_ref(),
+        "--seed".as_ref(),
+        "7".as_ref(),
+    ]);
+    let options = cli.options.options();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let options = options.unwrap();
+
+    // The config file over the defaults
+    assert_eq!(options.rule, Some("B36/S23".parse().unwrap()));
+    assert_eq!(options.density, 0.5);
+    // The flags over both
+    assert_eq!(options.size, 256);
+    assert_eq!(options.seed, Some(7));
+    // The defaults where neither says otherwise
+    assert_eq!(options.boundary, Options::default().boundary);
+    assert_eq!(options.speed, None);
+}
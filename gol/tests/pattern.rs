@@ -0,0 +1,35 @@
+//! Pattern files that ask for more than any world could hold are refused, not obeyed.
+
+use gol::pattern::Pattern;
+
+#[test]
+fn oversized_rle_is_refused() {
+    for text in [
+        "x = 100000, y = 100000\no!",
+        "x = 3, y = 3\n4294967296o!",
+        "x = 3, y = 3\n4294967295b4294967295bo!",
+        "x = 3, y = 3\n4294967295$4294967295$o!",
+    ] {
+        let error = format!("{:#}", Pattern::from_rle(text).err().unwrap());
+        assert!(
+            error.contains("too long") || error.contains("more than"),
+            "{error}"
+        );
+    }
+    assert!(Pattern::from_rle("x = 16384, y = 16384\no!").is_ok());
+}
+
+#[test]
+fn cells_too_far_apart_are_refused() {
+    let life_106 = "#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n";
+    let life_105 = "#Life 1.05\n#P 9223372036854775807 0\n.**\n";
+    let rows = "#Life 1.05\n#P 0 9223372036854775807\n*\n*\n";
+    for result in [
+        Pattern::from_life_106(life_106),
+        Pattern::from_life_105(life_105),
+        Pattern::from_life_105(rows),
+    ] {
+        let error = format!("{:#}", result.err().unwrap());
+        assert!(error.contains("more than"), "{error}");
+    }
+}
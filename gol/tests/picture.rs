@@ -0,0 +1,66 @@
+//! Pictures turned into cells, by threshold alone and by each kind of dithering.
+
+use std::io::Cursor;
+
+use gol::picture::{Dither, Picture};
+use image::{ImageFormat, Rgba, RgbaImage};
+
+/// `image` as the PNG a user would drop on the window, read back at its own size.
+fn picture(image: &RgbaImage) -> Picture {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+    Picture::decode(&png, image.width(), image.height()).unwrap()
+}
+
+fn grey(width: u32, height: u32, level: u8) -> RgbaImage {
+    RgbaImage::from_pixel(width, height, Rgba([level, level, level, 255]))
+}
+
+#[test]
+fn cells_brighter_than_the_threshold_are_alive() {
+    let mut image = grey(4, 1, 0);
+    for (x, level) in [(1, 64), (2, 192), (3, 255)] {
+        image.put_pixel(x, 0, Rgba([level, level, level, 255]));
+    }
+    let picture = picture(&image);
+    let alive = |threshold| {
+        let grid = picture.to_grid(threshold, Dither::None);
+        (0..4).map(|x| grid.get(x, 0)).collect::<Vec<_>>()
+    };
+    assert_eq!(alive(0.5), [false, false, true, true]);
+    assert_eq!(alive(0.1), [false, true, true, true]);
+    assert_eq!(alive(0.9), [false, false, false, true]);
+}
+
+#[test]
+fn dithering_keeps_the_brightness_of_a_grey() {
+    let half = picture(&grey(8, 8, 128));
+    // On its own every cell of a grey just above the threshold is alive
+    assert_eq!(half.to_grid(0.5, Dither::None).population(), 64);
+    // The Bayer pattern lights exactly half of each 4 × 4 block
+    let ordered = half.to_grid(0.5, Dither::Ordered);
+    assert_eq!(ordered.population(), 32);
+    for (bx, by) in [(0, 0), (4, 0), (0, 4), (4, 4)] {
+        let block = (0..16).filter(|i| ordered.get(bx + i % 4, by + i / 4));
+        assert_eq!(block.count(), 8);
+    }
+    // Spreading the error lights about half, and no row or column is all alive or all dead
+    let diffused = half.to_grid(0.5, Dither::FloydSteinberg);
+    assert!((30..=34).contains(&diffused.population()));
+    for i in 0..8 {
+        let row = (0..8).filter(|&x| diffused.get(x, i)).count();
+        let column = (0..8).filter(|&y| diffused.get(i, y)).count();
+        assert!((1..8).contains(&row) && (1..8).contains(&column));
+    }
+
+    // White and black stay as they are, whatever the dithering
+    for dither in [Dither::None, Dither::Ordered, Dither::FloydSteinberg] {
+        assert_eq!(
+            picture(&grey(8, 8, 255)).to_grid(0.5, dither).population(),
+            64
+        );
+        assert_eq!(picture(&grey(8, 8, 0)).to_grid(0.5, dither).population(), 0);
+    }
+}
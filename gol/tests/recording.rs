@@ -0,0 +1,41 @@
+//! Recordings made as a world is stepped, a frame for each generation asked for.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::conway::{ConwayCompute, ConwayConfig};
+use gol::grid::Grid;
+use gol::recording::{Recorder, RecordingOptions};
+
+#[test]
+fn frames_are_every_kth_generation_and_never_repeated() {
+    let ctx = context();
+    let directory = std::env::temp_dir().join(format!("gol-recording-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let options = RecordingOptions {
+        every: 3,
+        output: directory.join("frames.png"),
+        ..Default::default()
+    };
+    let grid = Grid::new(16, 16);
+    let mut conway = ConwayCompute::new(&ctx.device, &ctx.queue, &grid, ConwayConfig::default());
+    let mut recorder = Recorder::new(&ctx.device, &ctx.queue, &conway, &options).unwrap();
+
+    // A paused world is drawn again and again, but is the same generation
+    for _ in 0..4 {
+        recorder.capture(&ctx.device, &ctx.queue, &conway).unwrap();
+    }
+    assert_eq!(recorder.frames(), 1);
+    for _ in 0..10 {
+        let mut encoder = ctx.device.create_command_encoder(&Default::default());
+        conway.step(&mut encoder);
+        ctx.queue.submit(Some(encoder.finish()));
+        recorder.capture(&ctx.device, &ctx.queue, &conway).unwrap();
+        recorder.capture(&ctx.device, &ctx.queue, &conway).unwrap();
+    }
+    // Generations 0, 3, 6 and 9
+    assert_eq!(recorder.frames(), 4);
+    assert!(directory.join("frames-00003.png").exists());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,60 @@
+//! Rules read from text in each of the forms `--rule` accepts.
+
+use gol::rule::{Neighbourhood, Rule};
+
+/// A mask of the neighbour counts given.
+fn counts(counts: &[u32]) -> u16 {
+    counts.iter().map(|n| 1 << n).sum()
+}
+
+#[test]
+fn every_form_parses() {
+    for (text, birth, survive, neighbourhood) in [
+        ("B3/S23", &[3][..], &[2, 3][..], Neighbourhood::Moore),
+        ("b3s23", &[3], &[2, 3], Neighbourhood::Moore),
+        ("23/3", &[3], &[2, 3], Neighbourhood::Moore),
+        ("B2/S34H", &[2], &[3, 4], Neighbourhood::Hexagonal),
+        ("34/2h", &[2], &[3, 4], Neighbourhood::Hexagonal),
+        ("B4/S345L", &[4], &[3, 4, 5], Neighbourhood::Triangular),
+        ("B/S", &[], &[], Neighbourhood::Moore),
+    ] {
+        let rule: Rule = text.parse().unwrap();
+        let expected = Rule {
+            birth: counts(birth),
+            survive: counts(survive),
+            neighbourhood,
+        };
+        assert_eq!(rule, expected, "{text}");
+        assert_eq!(
+            rule.to_string().parse::<Rule>().unwrap(),
+            expected,
+            "{text}"
+        );
+    }
+    assert_eq!("B2/S34H".parse::<Rule>().unwrap().to_string(), "B2/S34H");
+    assert_eq!("23/3".parse::<Rule>().unwrap().to_string(), "B3/S23");
+}
+
+#[test]
+fn counts_above_the_neighbourhood_are_refused() {
+    for neighbourhood in [Neighbourhood::Moore, Neighbourhood::Hexagonal] {
+        let suffix = if neighbourhood == Neighbourhood::Hexagonal {
+            "H"
+        } else {
+            ""
+        };
+        let most = neighbourhood.size();
+        assert!(format!("B{most}/S{suffix}").parse::<Rule>().is_ok());
+        for text in [
+            format!("B{}/S{suffix}", most + 1),
+            format!("B3/S{}{suffix}", most + 1),
+        ] {
+            assert!(text.parse::<Rule>().is_err(), "{text}");
+        }
+    }
+    // Triangular cells have 12 neighbours, but a single digit only reaches 9
+    assert!("B9/S9L".parse::<Rule>().is_ok());
+    for text in ["B3/S2x", "B3", "3", "B3/S23/", ""] {
+        assert!(text.parse::<Rule>().is_err(), "{text}");
+    }
+}
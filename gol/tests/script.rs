@@ -0,0 +1,86 @@
+//! Scripts driving worlds on the CPU. Native only, as scripting is.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::PathBuf;
+
+use gol::grid::Boundary;
+use gol::options::Options;
+use gol::script::{ScriptWorld, run_script};
+
+/// An empty world of `size` cells a side, or an unbounded one.
+fn world(boundary: Boundary, size: u32) -> ScriptWorld {
+    let options = Options {
+        size,
+        boundary,
+        density: 0.0,
+        seed: Some(1),
+        ..Options::default()
+    };
+    ScriptWorld::new(options.session()).unwrap()
+}
+
+const GLIDER: &str = r#"
+    for cell in [[1, 0], [2, 1], [0, 2], [1, 2], [2, 2]] {
+        set_cell(cell[0], cell[1], true);
+    }
+"#;
+
+#[test]
+fn a_glider_moves_one_cell_every_four_generations() {
+    for boundary in [Boundary::Torus, Boundary::Unbounded] {
+        let source = format!(
+            r#"{GLIDER}
+            while generation() < 40 {{ step(); }}
+            let box = bounding_box();
+            if box.x != 10 || box.y != 10 || population() != 5 || !get_cell(12, 12) {{
+                throw `the glider went astray: ${{box}}`;
+            }}"#
+        );
+        let world = run_script(&source, world(boundary, 64), PathBuf::from(".")).unwrap();
+        assert_eq!(world.generation(), 40);
+        assert_eq!(world.bounding_box(), Some(((10, 10), (3, 3))));
+    }
+}
+
+#[test]
+fn snapshots_load_again() {
+    let dir = std::env::temp_dir().join(format!("gol-script-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = format!(
+        r#"{GLIDER}
+        save("glider.rle");
+        save("glider.mc");
+        set_rule("B36/S23");
+        step(10);
+        load("glider.mc");"#
+    );
+    let unbounded = run_script(&source, world(Boundary::Unbounded, 64), dir.clone()).unwrap();
+    assert_eq!(unbounded.population(), 5);
+    assert_eq!(unbounded.generation(), 0);
+
+    let bounded = run_script(
+        r#"load("glider.rle");"#,
+        world(Boundary::Dead, 8),
+        dir.clone(),
+    );
+    assert_eq!(bounded.unwrap().population(), 5);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn errors_name_the_problem() {
+    let bounded = run_script(
+        "set_cell(20, 0, true);",
+        world(Boundary::Torus, 16),
+        ".".into(),
+    );
+    let error = format!("{:#}", bounded.err().unwrap());
+    assert!(error.contains("outside the 16 × 16 world"), "{error}");
+
+    let rule = run_script(
+        r#"set_rule("nonsense");"#,
+        world(Boundary::Torus, 16),
+        ".".into(),
+    );
+    assert!(rule.is_err());
+}
@@ -0,0 +1,37 @@
+//! The transforms applied to selected cells, which undo themselves when repeated.
+
+use gol::grid::Grid;
+use gol::rule::Variant;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// An uneven soup that is not square, in every QuadLife colour, so that any cell out of place
+/// shows.
+fn cells() -> Grid {
+    let mut rng = StdRng::seed_from_u64(28);
+    let mut cells = Grid::random(7, 4, 0.5, &mut rng);
+    cells.recolour(None, Variant::QuadLife.colours(), &mut rng);
+    cells
+}
+
+#[test]
+fn four_rotations_give_back_the_selection() {
+    let cells = cells();
+    let mut rotated = cells.rotate_clockwise();
+    assert_eq!((rotated.width, rotated.height), (4, 7));
+    assert_eq!(rotated.colour(3, 0), cells.colour(0, 0));
+    for _ in 0..3 {
+        assert_ne!(rotated, cells);
+        rotated = rotated.rotate_clockwise();
+    }
+    assert_eq!(rotated, cells);
+}
+
+#[test]
+fn two_flips_give_back_the_selection() {
+    let cells = cells();
+    let flipped = cells.flip_horizontal();
+    assert_ne!(flipped, cells);
+    assert_eq!(flipped.colour(6, 1), cells.colour(0, 1));
+    assert_eq!(flipped.flip_horizontal(), cells);
+}
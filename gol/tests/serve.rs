@@ -0,0 +1,191 @@
+//! A server and its clients talking over localhost. Native only, as the server is.
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gol::grid::{Boundary, Grid};
+use gol::options::Options;
+use gol::pattern::Pattern;
+use gol::rule::Rule;
+use gol::serve::{Client, Server};
+use gol::shared::{ClientMessage, PROTOCOL_VERSION, ServerMessage, SharedWorld};
+use tungstenite::Message;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves an empty 32 × 32 torus on a free port.
+fn serve() -> SocketAddr {
+    let options = Options {
+        size: 32,
+        boundary: Boundary::Torus,
+        density: 0.0,
+        seed: Some(1),
+        speed: Some(50.0),
+        ..Options::default()
+    };
+    let server = Server::bind("127.0.0.1:0", &options).unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    address
+}
+
+fn join(address: SocketAddr) -> (Client, SharedWorld) {
+    let (mut client, world) = Client::connect(&format!("ws://{address}")).unwrap();
+    client.set_timeout(Some(Duration::from_millis(20))).unwrap();
+    (client, world)
+}
+
+/// Applies messages from the server until `done` holds of the world.
+fn wait_for(client: &mut Client, world: &mut SharedWorld, done: impl Fn(&SharedWorld) -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done(world) {
+        assert!(Instant::now() < deadline, "timed out at {world:?}");
+        if let Some(message) = client.receive().unwrap() {
+            world.apply(message).unwrap();
+        }
+    }
+}
+
+/// Applies every message the server sends within a short while.
+fn settle(client: &mut Client, world: &mut SharedWorld) {
+    let until = Instant::now() + Duration::from_millis(200);
+    while Instant::now() < until {
+        if let Some(message) = client.receive().unwrap() {
+            world.apply(message).unwrap();
+        }
+    }
+}
+
+#[test]
+fn clients_share_edits_and_generations() {
+    let address = serve();
+    let (mut alice, mut alice_world) = join(address);
+    let (mut bob, mut bob_world) = join(address);
+    assert_eq!((alice_world.grid.width, alice_world.grid.height), (32, 32));
+    assert_eq!(alice_world.grid.population(), 0);
+
+    // A glider stamped by one client runs for both
+    let glider = Pattern::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+    alice
+        .send(&ClientMessage::Stamp {
+            origin: (10, 10),
+            cells: glider.grid,
+        })
+        .unwrap();
+    for (client, world) in [(&mut alice, &mut alice_world), (&mut bob, &mut bob_world)] {
+        wait_for(client, world, |world| world.grid.population() == 5);
+        let generation = world.generation;
+        wait_for(client, world, |world| world.generation >= generation + 4);
+        assert_eq!(world.grid.population(), 5);
+    }
+
+    // Pausing stops both at the same generation with the same cells
+    bob.send(&ClientMessage::Pause).unwrap();
+    wait_for(&mut alice, &mut alice_world, |world| world.paused);
+    wait_for(&mut bob, &mut bob_world, |world| world.paused);
+    settle(&mut alice, &mut alice_world);
+    settle(&mut bob, &mut bob_world);
+    assert_eq!(alice_world, bob_world);
+    let paused_at = alice_world.generation;
+
+    // Edits still arrive while paused, without stepping
+    bob.send(&ClientMessage::Paint {
+        cells: vec![(0, 0), (-1, -1)],
+        alive: true,
+    })
+    .unwrap();
+    wait_for(&mut alice, &mut alice_world, |world| world.grid.get(0, 0));
+    assert!(alice_world.grid.get(31, 31));
+    assert_eq!(alice_world.generation, paused_at);
+
+    // So do rule changes, and the world goes on under the new rule when resumed
+    let highlife: Rule = "B36/S23".parse().unwrap();
+    alice.send(&ClientMessage::SetRule(highlife)).unwrap();
+    alice.send(&ClientMessage::Resume).unwrap();
+    wait_for(&mut bob, &mut bob_world, |world| {
+        world.rule == highlife && world.generation > paused_at
+    });
+    assert!(!bob_world.paused);
+}
+
+#[test]
+fn late_joiners_see_the_world_as_it_is() {
+    let address = serve();
+    let (mut alice, mut alice_world) = join(address);
+    let mut block = Grid::new(2, 2);
+    block.cells.fill(1);
+    alice
+        .send(&ClientMessage::Stamp {
+            origin: (4, 4),
+            cells: block,
+        })
+        .unwrap();
+    alice.send(&ClientMessage::Pause).unwrap();
+    wait_for(&mut alice, &mut alice_world, |world| {
+        world.paused && world.grid.population() == 4
+    });
+
+    let (_, bob_world) = join(address);
+    assert_eq!(bob_world, alice_world);
+}
+
+#[test]
+fn other_protocol_versions_are_refused() {
+    let address = serve();
+    let (mut socket, _) = tungstenite::connect(format!("ws://{address}")).unwrap();
+    let hello = ClientMessage::Hello {
+        version: PROTOCOL_VERSION + 1,
+    };
+    socket.send(Message::binary(hello.to_bytes())).unwrap();
+    let reply = socket.read().unwrap();
+    let message = ServerMessage::from_bytes(&reply.into_data()).unwrap();
+    assert!(matches!(message, ServerMessage::Refused(_)), "{message:?}");
+}
+
+#[test]
+fn messages_round_trip() {
+    let mut grid = Grid::new(40, 3);
+    for x in [0, 2, 3, 20, 39] {
+        grid.set_colour(x, 1, 2);
+    }
+    let world = SharedWorld {
+        grid: grid.clone(),
+        rule: "B2/S34H".parse().unwrap(),
+        boundary: Boundary::Cube,
+        variant: gol::rule::Variant::QuadLife,
+        generation: 1 << 40,
+        paused: true,
+    };
+    let messages = [
+        ServerMessage::Welcome(world),
+        ServerMessage::Rule(Rule::default()),
+        ServerMessage::Paused(false),
+        ServerMessage::Refused("full".to_string()),
+    ];
+    for message in messages {
+        assert_eq!(
+            ServerMessage::from_bytes(&message.to_bytes()).unwrap(),
+            message
+        );
+    }
+    let edits = [
+        ClientMessage::Hello { version: 7 },
+        ClientMessage::Paint {
+            cells: vec![(i32::MIN, i32::MAX), (-3, 0)],
+            alive: false,
+        },
+        ClientMessage::Stamp {
+            origin: (-5, 9),
+            cells: grid,
+        },
+        ClientMessage::Pause,
+        ClientMessage::Resume,
+        ClientMessage::SetRule("B36/S23".parse().unwrap()),
+    ];
+    for edit in edits {
+        assert_eq!(ClientMessage::from_bytes(&edit.to_bytes()).unwrap(), edit);
+    }
+    assert!(ServerMessage::from_bytes(&[1, 5]).is_err());
+}
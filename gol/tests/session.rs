@@ -0,0 +1,32 @@
+//! Sessions read back from text, including ones no version of the simulator would have saved.
+
+use gol::grid::Boundary;
+use gol::options::MAX_SIZE;
+use gol::session::Session;
+
+#[test]
+fn a_cube_is_always_six_faces_side_by_side() {
+    let text = "#S version 1\n#S boundary cube\nx = 10, y = 10\n10o!";
+    let session = Session::from_text(text).unwrap();
+    assert_eq!(session.boundary, Boundary::Cube);
+    assert_eq!((session.grid.width, session.grid.height), (60, 10));
+    assert_eq!(session.grid.population(), 10);
+
+    // Steps without running off the end of a face
+    session.grid.step(&session.rule, session.boundary);
+
+    let again = Session::from_text(&session.to_text()).unwrap();
+    assert_eq!(again, session);
+}
+
+#[test]
+fn cube_faces_fit_one_texture() {
+    let text = format!("#S version 1\n#S boundary torus\nx = {MAX_SIZE}, y = 4\n{MAX_SIZE}o!");
+    let session = Session::from_text(&text)
+        .unwrap()
+        .with_boundary(Boundary::Cube);
+    let (width, height) = (session.grid.width, session.grid.height);
+    assert_eq!(width, 6 * height);
+    assert!(width <= MAX_SIZE, "{width}");
+    assert_eq!(session.grid.population(), height as usize);
+}
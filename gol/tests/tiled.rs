@@ -0,0 +1,136 @@
+//! Worlds split into tiles, checked against the CPU reference.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::gpu::HeadlessContext;
+use gol::grid::{Boundary, Grid, Rect};
+use gol::rule::{Neighbourhood, Rule};
+use gol::tiled::TiledWorld;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Tiles of 16 words by 16 rows, three by three of them.
+fn world(ctx: &HeadlessContext, rule: Rule, boundary: Boundary) -> TiledWorld {
+    let world = TiledWorld::new(&ctx.device, (1056, 48), rule, boundary, 16).unwrap();
+    assert_eq!(world.tiles(), (3, 3));
+    assert_eq!(world.size(), (1056, 48));
+    world
+}
+
+fn read(ctx: &HeadlessContext, world: &TiledWorld) -> Grid {
+    pollster::block_on(world.read(&ctx.device, &ctx.queue)).unwrap()
+}
+
+#[test]
+fn steps_match_the_cpu_across_tiles() {
+    let ctx = context();
+    let mut rng = StdRng::seed_from_u64(7);
+    for neighbourhood in [
+        Neighbourhood::Moore,
+        Neighbourhood::Hexagonal,
+        Neighbourhood::Triangular,
+    ] {
+        let rule = neighbourhood.rule();
+        for boundary in [Boundary::Torus, Boundary::Dead] {
+            let mut world = world(&ctx, rule, boundary);
+            let mut expected = Grid::random(1056, 48, 0.4, &mut rng);
+            world.write(&ctx.device, &ctx.queue, &expected);
+            assert_eq!(read(&ctx, &world), expected);
+
+            for _ in 0..4 {
+                let mut encoder = ctx.device.create_command_encoder(&Default::default());
+                world.step(&mut encoder);
+                ctx.queue.submit(Some(encoder.finish()));
+                expected = expected.step(&rule, boundary);
+            }
+            assert_eq!(world.generation(), 4);
+            assert_eq!(
+                read(&ctx, &world),
+                expected,
+                "{neighbourhood:?} on a {boundary:?} world"
+            );
+        }
+    }
+}
+
+#[test]
+fn stamps_wrap_on_a_torus_and_clip_otherwise() {
+    let ctx = context();
+    let mut pattern = Grid::new(40, 3);
+    for x in 0..40 {
+        pattern.set(x, 1, true);
+    }
+
+    for boundary in [Boundary::Torus, Boundary::Dead] {
+        let mut world = world(&ctx, Rule::CONWAY, boundary);
+        world.soup(&ctx.device, &ctx.queue, 1.0, 1);
+        world.stamp(&ctx.device, &ctx.queue, &pattern, (1040, 46));
+
+        let mut expected = Grid::new(1056, 48);
+        for y in 0..48 {
+            for x in 0..1056 {
+                expected.set(x, y, true);
+            }
+        }
+        for j in 0..3 {
+            for i in 0..40 {
+                let (x, y) = (1040 + i, 46 + j);
+                if boundary == Boundary::Torus {
+                    expected.set(x % 1056, y % 48, j == 1);
+                } else if x < 1056 && y < 48 {
+                    expected.set(x, y, j == 1);
+                }
+            }
+        }
+        assert_eq!(read(&ctx, &world), expected, "{boundary:?}");
+    }
+}
+
+#[test]
+fn regions_read_back_across_tiles() {
+    let ctx = context();
+    let mut world = world(&ctx, Rule::CONWAY, Boundary::Torus);
+    let grid = Grid::random(1000, 40, 0.5, &mut StdRng::seed_from_u64(3));
+    world.write(&ctx.device, &ctx.queue, &grid);
+
+    let rect = Rect {
+        x: 300,
+        y: 10,
+        width: 500,
+        height: 25,
+    };
+    let region = pollster::block_on(world.read_region(&ctx.device, &ctx.queue, rect)).unwrap();
+    assert_eq!(region, grid.crop(300, 10, 500, 25));
+
+    let soup = {
+        world.soup(&ctx.device, &ctx.queue, 0.5, 9);
+        read(&ctx, &world).population() as f64 / (1056.0 * 48.0)
+    };
+    assert!((0.45..0.55).contains(&soup), "{soup}");
+}
+
+#[test]
+fn tiles_divide_sizes_that_the_largest_tile_does_not() {
+    let ctx = context();
+    let rule = Rule::CONWAY;
+    // 33 words by 50 rows take three tiles of 11 words, and five of 10 rows rather than four
+    let mut world = TiledWorld::new(&ctx.device, (1056, 50), rule, Boundary::Torus, 16).unwrap();
+    assert_eq!(world.tiles(), (3, 5));
+    assert_eq!(world.size(), (1056, 50));
+
+    let mut expected = Grid::random(1056, 50, 0.4, &mut StdRng::seed_from_u64(5));
+    world.write(&ctx.device, &ctx.queue, &expected);
+    for _ in 0..4 {
+        let mut encoder = ctx.device.create_command_encoder(&Default::default());
+        world.step(&mut encoder);
+        ctx.queue.submit(Some(encoder.finish()));
+        expected = expected.step(&rule, Boundary::Torus);
+    }
+    assert_eq!(read(&ctx, &world), expected);
+
+    let dead = TiledWorld::new(&ctx.device, (1000, 50), rule, Boundary::Dead, 16).unwrap();
+    assert_eq!(dead.size(), (1024, 50));
+    assert!(TiledWorld::new(&ctx.device, (1000, 50), rule, Boundary::Torus, 16).is_err());
+}
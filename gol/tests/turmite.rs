@@ -0,0 +1,38 @@
+//! Turmites on the GPU, checked against the CPU reference, crowded enough that ants keep meeting
+//! on the same cell.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::context;
+use gol::turmite::{Start, TURMITES, Turmite, TurmiteCompute, TurmiteWorld};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+#[test]
+fn ants_move_as_on_the_cpu() {
+    let ctx = context();
+    for rule in TURMITES {
+        let rule: Turmite = rule.parse().unwrap();
+        for start in [Start::Cluster, Start::Line, Start::Random] {
+            let ants = start.place(50, (40, 24), &mut StdRng::seed_from_u64(23));
+            let mut expected = TurmiteWorld::new(40, 24, ants);
+            let mut compute = TurmiteCompute::new(&ctx.device, &ctx.queue, &expected, &rule);
+            // A single step, then many in one pass
+            for steps in [1, 99, 400] {
+                let mut encoder = ctx.device.create_command_encoder(&Default::default());
+                compute.step(&mut encoder, steps);
+                ctx.queue.submit(Some(encoder.finish()));
+                for _ in 0..steps {
+                    expected.step(&rule);
+                }
+                let actual = pollster::block_on(compute.read(&ctx.device, &ctx.queue)).unwrap();
+                assert!(
+                    actual == expected,
+                    "{rule} {start} after {} steps",
+                    compute.steps()
+                );
+            }
+        }
+    }
+}
@@ -1,12 +1,7 @@
 mod app;
-mod camera;
-mod camera_controller;
-mod instance;
 mod light;
-mod model;
 mod resources;
 mod state;
-mod texture;
 
 use app::App;
 use winit::event_loop::EventLoop;
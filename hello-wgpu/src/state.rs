@@ -1,13 +1,13 @@
 #![allow(dead_code, unused_imports)]
 
-use crate::camera::{Camera, CameraUniform};
-use crate::camera_controller::CameraController;
-use crate::instance::{Instance, InstanceRaw};
 use crate::light::LightUniform;
-use crate::model::{DrawLight, DrawModel, Model, ModelVertex, Vertex};
 use crate::resources::load_model;
-use crate::texture::Texture;
 use cgmath::prelude::*;
+use scene::camera::{Camera, CameraUniform};
+use scene::camera_controller::CameraController;
+use scene::instance::{Instance, InstanceRaw};
+use scene::model::{DrawLight, DrawModel, Model, ModelVertex, Vertex};
+use scene::texture::Texture;
 use std::cmp;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
@@ -53,3 +53,9 @@ impl CameraUniform {
         self.view_proj = camera.build_view_projection_matrix().into();
     }
 }
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,8 @@
+//! The camera, its controller, and the models and instances of hello-wgpu's scene, shared with
+//! the 3D views of gol.
+
+pub mod camera;
+pub mod camera_controller;
+pub mod instance;
+pub mod model;
+pub mod texture;